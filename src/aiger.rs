@@ -8,12 +8,112 @@ use flussab::DeferredWriter;
 use flussab_aiger::aig::*;
 use flussab_aiger::*;
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::io::{self, Read, Write};
 
 use crate::AssignEntry;
 
+/// Additional information of AIGER logic.
+///
+/// Names are optional and given by index of AIGER input, latch or output. Vectors of names
+/// can be shorter than number of AIGER inputs, latches or outputs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AIGERInfo {
+    /// Names of AIGER inputs (circuit inputs without state).
+    pub input_names: Vec<Option<String>>,
+    /// Names of AIGER latches (state).
+    pub latch_names: Vec<Option<String>>,
+    /// Initial values of AIGER latches: None - uninitialized (nondeterministic).
+    /// If vector is shorter than state then rest of latches are initialized by zero.
+    pub latch_inits: Vec<Option<bool>>,
+    /// Names of AIGER outputs and properties (circuit outputs without state).
+    pub output_names: Vec<Option<String>>,
+    /// Roles of circuit outputs without state. If vector is shorter than number of
+    /// these outputs then rest of outputs are ordinary outputs.
+    pub output_roles: Vec<AIGEROutputRole>,
+    /// Comment.
+    pub comment: Option<String>,
+}
+
+/// Role of circuit output in AIGER logic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AIGEROutputRole {
+    /// Ordinary output.
+    #[default]
+    Output,
+    /// Bad state property.
+    BadState,
+    /// Invariant constraint.
+    InvariantConstraint,
+    /// Justice property with its index. Outputs with this same index
+    /// are local fairness constraints of one justice property.
+    Justice(usize),
+    /// Fairness constraint.
+    FairnessConstraint,
+}
+
+// mapping: key - AIGER literal, value - assignment in circuit
+type AIGERMap = Vec<(usize, AssignEntry)>;
+
+// AIGER outputs and properties sorted by roles
+struct AIGERSections {
+    outputs: Vec<usize>,
+    bad_states: Vec<usize>,
+    invariant_constraints: Vec<usize>,
+    // key - justice property index
+    justices: BTreeMap<usize, Vec<usize>>,
+    fairness_constraints: Vec<usize>,
+    // symbols of outputs
+    symbols: Vec<Symbol<'static>>,
+}
+
+impl AIGERSections {
+    fn new(lits: impl Iterator<Item = usize>, info: &AIGERInfo) -> Self {
+        let mut sections = Self {
+            outputs: vec![],
+            bad_states: vec![],
+            invariant_constraints: vec![],
+            justices: BTreeMap::new(),
+            fairness_constraints: vec![],
+            symbols: vec![],
+        };
+        for (i, l) in lits.enumerate() {
+            match info.output_roles.get(i).copied().unwrap_or_default() {
+                AIGEROutputRole::Output => {
+                    // names of properties are not written, because parser of
+                    // property symbols limits their indices by number of latches.
+                    if let Some(name) = info.output_names.get(i).cloned().flatten() {
+                        sections.symbols.push(Symbol {
+                            target: SymbolTarget::Output(sections.outputs.len()),
+                            name: Cow::Owned(name),
+                        });
+                    }
+                    sections.outputs.push(l);
+                }
+                AIGEROutputRole::BadState => sections.bad_states.push(l),
+                AIGEROutputRole::InvariantConstraint => sections.invariant_constraints.push(l),
+                AIGEROutputRole::Justice(j) => sections.justices.entry(j).or_default().push(l),
+                AIGEROutputRole::FairnessConstraint => sections.fairness_constraints.push(l),
+            }
+        }
+        sections
+    }
+}
+
+fn aiger_symbols(
+    names: &[Option<String>],
+    target: fn(usize) -> SymbolTarget,
+) -> impl Iterator<Item = Symbol<'static>> + '_ {
+    names.iter().enumerate().filter_map(move |(i, name)| {
+        name.as_ref().map(|name| Symbol {
+            target: target(i),
+            name: Cow::Owned(name.clone()),
+        })
+    })
+}
+
 /// Converts circuit to AIGER format.
 ///
 /// Function writes Gate circuit logic in AIGER format to `out`. `circuit` is circuit
@@ -29,12 +129,34 @@ pub fn to_aiger(
     state_len: usize,
     out: impl Write,
     binmode: bool,
+) -> io::Result<()> {
+    to_aiger_with_info(circuit, state_len, out, binmode, &AIGERInfo::default())
+}
+
+/// Converts circuit to AIGER format with additional information.
+///
+/// Function works like `to_aiger`, but it also writes names of inputs, latches
+/// and outputs as AIGER symbols, initial values of latches and comment given in `info`. Circuit outputs (without
+/// state) can be written as bad state properties, invariant constraints, justice properties
+/// or fairness constraints if roles of outputs are given in `info`. Names of properties
+/// are not written.
+pub fn to_aiger_with_info(
+    circuit: &Circuit<usize>,
+    state_len: usize,
+    out: impl Write,
+    binmode: bool,
+    info: &AIGERInfo,
 ) -> io::Result<()> {
     let input_len = circuit.input_len();
     let output_len = circuit.outputs().len();
     let outputs = circuit.outputs();
     assert!(state_len <= input_len);
     assert!(state_len <= output_len);
+    assert!(info.input_names.len() <= input_len - state_len);
+    assert!(info.latch_names.len() <= state_len);
+    assert!(info.latch_inits.len() <= state_len);
+    assert!(info.output_names.len() <= output_len - state_len);
+    assert!(info.output_roles.len() <= output_len - state_len);
     // convert to OrderedAig
     // in circuit - states are first.
     // in AIGER - states are next after inputs.
@@ -89,25 +211,32 @@ pub fn to_aiger(
             }
         }
     }
+    let sections = AIGERSections::new(
+        outputs[state_len..]
+            .iter()
+            .map(|(o, n)| wires2lits[*o] + usize::from(*n)),
+        info,
+    );
     let ord_aig = OrderedAig {
         max_var_index: var_index,
         input_count: input_len - state_len,
         latches: (0..state_len)
             .map(|i| OrderedLatch {
                 next_state: wires2lits[outputs[i].0] + usize::from(outputs[i].1),
-                initialization: Some(false),
+                initialization: info.latch_inits.get(i).copied().unwrap_or(Some(false)),
             })
             .collect::<Vec<_>>(),
-        outputs: (state_len..output_len)
-            .map(|i| wires2lits[outputs[i].0] + usize::from(outputs[i].1))
-            .collect::<Vec<_>>(),
-        bad_state_properties: vec![],
-        invariant_constraints: vec![],
-        justice_properties: vec![],
-        fairness_constraints: vec![],
+        outputs: sections.outputs,
+        bad_state_properties: sections.bad_states,
+        invariant_constraints: sections.invariant_constraints,
+        justice_properties: sections.justices.into_values().collect::<Vec<_>>(),
+        fairness_constraints: sections.fairness_constraints,
         and_gates,
-        symbols: vec![],
-        comment: None,
+        symbols: aiger_symbols(&info.input_names, SymbolTarget::Input)
+            .chain(aiger_symbols(&info.latch_names, SymbolTarget::Latch))
+            .chain(sections.symbols)
+            .collect::<Vec<_>>(),
+        comment: info.comment.clone(),
     };
     let mut dwriter = DeferredWriter::from_write(out);
     if binmode {
@@ -139,11 +268,16 @@ pub enum AIGERError {
     /// If bad input.
     #[error("Bad input")]
     BadInput,
+    /// If symbol refers to item beyond its section.
+    #[error("Symbol {0} bad index")]
+    SymbolBadIndex(String),
 }
 
+// if with_props is true then properties are also converted to circuit outputs.
 fn from_aiger_int(
     aig: &Aig<usize>,
-) -> Result<(Circuit<usize>, Vec<(usize, AssignEntry)>), AIGERError> {
+    with_props: bool,
+) -> Result<(Circuit<usize>, AIGERMap), AIGERError> {
     use gategen::boolvar::*;
     use gategen::dynintvar::*;
     let state_len = aig.latches.len();
@@ -205,12 +339,22 @@ fn from_aiger_int(
     // XOR subpart gates will be skipped - if they are part of other path then included
     // automatically. Any negation propagation, constant assignments will be done
     // automatically gategen.
-    let outputs = aig
+    let mut outputs = aig
         .latches
         .iter()
         .map(|latch| latch.next_state)
         .chain(aig.outputs.iter().copied())
         .collect::<Vec<_>>();
+    if with_props {
+        outputs.extend(
+            aig.bad_state_properties
+                .iter()
+                .chain(aig.invariant_constraints.iter())
+                .chain(aig.justice_properties.iter().flatten())
+                .chain(aig.fairness_constraints.iter())
+                .copied(),
+        );
+    }
     for ol in &outputs {
         stack.push(StackEntry { way: 0, lit: *ol });
         while !stack.is_empty() {
@@ -393,10 +537,21 @@ fn from_aiger_int(
 // return: circuit, map for AIGER variables (input, latches and output)
 // format of AIGER map: (AIGER literal, AIGER Entry)
 
+fn parse_aiger(input: impl Read, binmode: bool) -> Result<Aig<usize>, AIGERError> {
+    Ok(if binmode {
+        let parser = binary::Parser::<usize>::from_read(input, binary::Config::default())?;
+        parser.parse()?.into()
+    } else {
+        let parser = ascii::Parser::<usize>::from_read(input, ascii::Config::default())?;
+        parser.parse()?
+    })
+}
+
 /// Converts AIGER logic to Gate circuit.
 ///
 /// `input` is read stream with AIGER logic. Function returns Gate circuit with its mapping.
 /// Mapping in form: key - original variable in AIGER logic, value - assignment in circuit.
+/// Only ordinary outputs are converted - properties are ignored.
 ///
 /// `binmode` sets mode used while writing to AIGER mode - if true then use binary mode,
 /// otherwise textual mode.
@@ -405,12 +560,101 @@ pub fn from_aiger(
     binmode: bool,
 ) -> Result<(Circuit<usize>, Vec<(usize, AssignEntry)>), AIGERError> {
     use gategen::boolvar::*;
-    let aig = if binmode {
-        let parser = binary::Parser::<usize>::from_read(input, binary::Config::default())?;
-        parser.parse()?.into()
-    } else {
-        let parser = ascii::Parser::<usize>::from_read(input, ascii::Config::default())?;
-        parser.parse()?
+    let aig = parse_aiger(input, binmode)?;
+    callsys(|| from_aiger_int(&aig, false))
+}
+
+/// Converts AIGER logic to Gate circuit with additional information.
+///
+/// Function works like `from_aiger`, but it also returns additional information:
+/// names of inputs, latches and outputs from AIGER symbols, initial values of latches
+/// and comment.
+///
+/// Bad state properties, invariant constraints, justice properties and fairness constraints
+/// are converted to circuit outputs placed after ordinary outputs (in this order). Mapping
+/// contains also literals of these outputs. Roles of these outputs are returned in
+/// `output_roles` and not in mapping, because a key of mapping is an AIGER literal and
+/// the same literal can be an output and a property. The i-th role applies to the i-th
+/// mapping entry after entries of latches, inputs and next states.
+///
+/// If symbol refers to an item beyond its section then function returns
+/// `AIGERError::SymbolBadIndex`.
+pub fn from_aiger_with_info(
+    input: impl Read,
+    binmode: bool,
+) -> Result<(Circuit<usize>, AIGERMap, AIGERInfo), AIGERError> {
+    use gategen::boolvar::*;
+    let aig = parse_aiger(input, binmode)?;
+    let (circuit, aiger_map) = callsys(|| from_aiger_int(&aig, true))?;
+    // roles of outputs and positions of first outputs of AIGER sections
+    let mut output_roles = vec![AIGEROutputRole::Output; aig.outputs.len()];
+    let mut push_roles = |role: AIGEROutputRole, len: usize| {
+        let start = output_roles.len();
+        output_roles.resize(start + len, role);
+        start
+    };
+    let bad_start = push_roles(AIGEROutputRole::BadState, aig.bad_state_properties.len());
+    let invariant_start = push_roles(
+        AIGEROutputRole::InvariantConstraint,
+        aig.invariant_constraints.len(),
+    );
+    let justice_starts = aig
+        .justice_properties
+        .iter()
+        .enumerate()
+        .map(|(j, justice)| push_roles(AIGEROutputRole::Justice(j), justice.len()))
+        .collect::<Vec<_>>();
+    let fairness_start = push_roles(
+        AIGEROutputRole::FairnessConstraint,
+        aig.fairness_constraints.len(),
+    );
+    let mut info = AIGERInfo {
+        input_names: vec![None; aig.inputs.len()],
+        latch_names: vec![None; aig.latches.len()],
+        latch_inits: aig.latches.iter().map(|l| l.initialization).collect(),
+        output_names: vec![None; output_roles.len()],
+        output_roles,
+        comment: aig.comment.clone(),
     };
-    callsys(|| from_aiger_int(&aig))
+    for symbol in &aig.symbols {
+        let name = Some(symbol.name.to_string());
+        // parser checks symbol indices only against number of latches
+        let section_len = match symbol.target {
+            SymbolTarget::Input(_) => aig.inputs.len(),
+            SymbolTarget::Latch(_) => aig.latches.len(),
+            SymbolTarget::Output(_) => aig.outputs.len(),
+            SymbolTarget::BadStateProperty(_) => aig.bad_state_properties.len(),
+            SymbolTarget::InvariantConstraint(_) => aig.invariant_constraints.len(),
+            SymbolTarget::JusticeProperty(_) => aig.justice_properties.len(),
+            SymbolTarget::FairnessConstraint(_) => aig.fairness_constraints.len(),
+        };
+        let index = match symbol.target {
+            SymbolTarget::Input(i)
+            | SymbolTarget::Latch(i)
+            | SymbolTarget::Output(i)
+            | SymbolTarget::BadStateProperty(i)
+            | SymbolTarget::InvariantConstraint(i)
+            | SymbolTarget::JusticeProperty(i)
+            | SymbolTarget::FairnessConstraint(i) => i,
+        };
+        if index >= section_len {
+            return Err(AIGERError::SymbolBadIndex(symbol.name.to_string()));
+        }
+        match symbol.target {
+            SymbolTarget::Input(i) => info.input_names[i] = name,
+            SymbolTarget::Latch(i) => info.latch_names[i] = name,
+            SymbolTarget::Output(i) => info.output_names[i] = name,
+            SymbolTarget::BadStateProperty(i) => info.output_names[bad_start + i] = name,
+            SymbolTarget::InvariantConstraint(i) => info.output_names[invariant_start + i] = name,
+            SymbolTarget::JusticeProperty(i) => {
+                // empty justice property doesn't have outputs
+                if aig.justice_properties[i].is_empty() {
+                    continue;
+                }
+                info.output_names[justice_starts[i]] = name
+            }
+            SymbolTarget::FairnessConstraint(i) => info.output_names[fairness_start + i] = name,
+        }
+    }
+    Ok((circuit, aiger_map, info))
 }
@@ -0,0 +1,280 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to conversion between Gate circuit and the ISCAS bench format.
+//!
+//! ISCAS bench format (used by ISCAS-85 and ISCAS-89 benchmarks) describes circuit as list
+//! of declarations of inputs (`INPUT(x)`) and outputs (`OUTPUT(y)`) and gates in form
+//! `z = NAND(a, b, c)`. D flip-flops (`q = DFF(d)`) are states of circuit.
+
+use crate::gatesim::*;
+use crate::AssignEntry;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+use crate::vbinopcircuit::*;
+use crate::vcircuit::VGateFunc;
+use crate::vexpr::*;
+use crate::VNegs::*;
+
+/// Converts circuit to ISCAS bench format.
+///
+/// Function writes Gate circuit in ISCAS bench format to `out`. `circuit` is circuit
+/// to convert. `state_len` is length of state that represents in bench as D flip-flops.
+/// Negations are optimized while conversion and gates are written as `AND`, `NAND`, `OR`,
+/// `NOR`, `XOR`, `XNOR`, `NOT` and `BUFF`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_bench(circuit: &Circuit<usize>, state_len: usize, out: impl Write) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let input_len = circuit.input_len();
+    let output_len = circuit.outputs().len();
+    assert!(state_len <= input_len);
+    assert!(state_len <= output_len);
+
+    let circuit = {
+        let mut circuit = VBinOpCircuit::from(circuit.clone());
+        circuit.optimize_negs();
+        circuit
+    };
+
+    let mut wire_out_map = BTreeMap::new();
+    let mut dup_map = vec![];
+    for (oi, (o, n)) in circuit.outputs.iter().enumerate() {
+        if let Some((old_oi, _)) = wire_out_map.get(&(*o, *n)) {
+            // resolve duplicate
+            dup_map.push((oi, *old_oi));
+        } else {
+            wire_out_map.insert((*o, *n), (oi, *n));
+        }
+    }
+    let resolve_name = |i| {
+        if let Some((oi, _)) = wire_out_map.get(&(i, false)).filter(|_| i >= input_len) {
+            format!("o{}", oi)
+        } else {
+            format!("i{}", i)
+        }
+    };
+    for i in state_len..input_len {
+        writeln!(out, "INPUT(i{})", i)?;
+    }
+    for i in state_len..output_len {
+        writeln!(out, "OUTPUT(o{})", i)?;
+    }
+    for i in 0..state_len {
+        writeln!(out, "i{0} = DFF(o{0})", i)?;
+    }
+    // negations of second inputs of gates - named as n{wire}
+    let mut negs = HashSet::new();
+    for (i, (g, n)) in circuit.gates.iter().enumerate() {
+        let func = match (g.func, *n == NegOutput) {
+            (VGateFunc::And, false) => "AND",
+            (VGateFunc::And, true) => "NAND",
+            (VGateFunc::Or, false) => "OR",
+            (VGateFunc::Or, true) => "NOR",
+            (VGateFunc::Xor, false) => "XOR",
+            (VGateFunc::Xor, true) => "XNOR",
+            _ => {
+                panic!("Unexpected!");
+            }
+        };
+        let i1 = if *n == NegInput1 {
+            if negs.insert(g.i1) {
+                writeln!(out, "n{} = NOT({})", g.i1, resolve_name(g.i1))?;
+            }
+            format!("n{}", g.i1)
+        } else {
+            resolve_name(g.i1)
+        };
+        writeln!(
+            out,
+            "{} = {}({}, {})",
+            resolve_name(i + input_len),
+            func,
+            resolve_name(g.i0),
+            i1
+        )?;
+    }
+    // generate negations and outputs that are inputs
+    for ((o, _), (oi, n)) in &wire_out_map {
+        if *n {
+            writeln!(out, "o{} = NOT({})", *oi, resolve_name(*o))?;
+        } else if *o < input_len {
+            writeln!(out, "o{} = BUFF({})", *oi, resolve_name(*o))?;
+        }
+    }
+    // generate output duplicates
+    for (oi, old_oi) in dup_map {
+        writeln!(out, "o{} = BUFF(o{})", oi, old_oi)?;
+    }
+    Ok(())
+}
+
+/// Bench error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum BenchError {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unknown gate.
+    #[error("{0}: Unknown gate {1}")]
+    UnknownGate(usize, String),
+    /// If wrong number of arguments of gate.
+    #[error("{0}: Wrong number of arguments of {1}")]
+    WrongArgNum(usize, String),
+    /// If net is already driven.
+    #[error("{0}: Net {1} already driven")]
+    NetAlreadyDriven(usize, String),
+    /// If net is driven by nothing.
+    #[error("Net {0} is undriven")]
+    UndrivenNet(String),
+    /// If circuit have cycle.
+    #[error("Cycle in circuit caused by {0}")]
+    CycleInCircuit(String),
+}
+
+// mapping: key - name of net, value - assignment in circuit
+type BenchMap = Vec<(String, AssignEntry)>;
+
+fn is_bench_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '(' | ')' | ',' | '=' | '#'))
+}
+
+// parse call in form 'FUNC(arg, ...)'. Returns function name (in uppercase) and arguments.
+fn parse_bench_call(line_no: usize, text: &str) -> Result<(String, Vec<String>), BenchError> {
+    let text = text.trim();
+    let syntax_error = || BenchError::SyntaxError(line_no, text.to_string());
+    let (func, args) = text.split_once('(').ok_or_else(syntax_error)?;
+    let args = args.strip_suffix(')').ok_or_else(syntax_error)?;
+    let func = func.trim();
+    if !is_bench_name(func) {
+        return Err(syntax_error());
+    }
+    let args = if args.trim().is_empty() {
+        vec![]
+    } else {
+        args.split(',')
+            .map(|a| {
+                let a = a.trim();
+                if is_bench_name(a) {
+                    Ok(a.to_string())
+                } else {
+                    Err(syntax_error())
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    Ok((func.to_ascii_uppercase(), args))
+}
+
+// expression of gate with arguments.
+fn bench_gate_expr(func: &str, args: Vec<String>) -> VExpr {
+    let nets = args.into_iter().map(VExpr::Net).collect::<Vec<_>>();
+    match func {
+        "NOT" => VExpr::Not(Box::new(nets[0].clone())),
+        "BUFF" | "BUF" => nets[0].clone(),
+        "AND" => VExpr::And(nets),
+        "NAND" => VExpr::Not(Box::new(VExpr::And(nets))),
+        "OR" => VExpr::Or(nets),
+        "NOR" => VExpr::Not(Box::new(VExpr::Or(nets))),
+        "XOR" => VExpr::Xor(nets),
+        "XNOR" => VExpr::Not(Box::new(VExpr::Xor(nets))),
+        _ => {
+            panic!("Unexpected!");
+        }
+    }
+}
+
+/// Converts ISCAS bench logic to Gate circuit.
+///
+/// `input` is read stream with ISCAS bench logic. Supported gates are: `AND`, `NAND`, `OR`,
+/// `NOR`, `XOR`, `XNOR` (with any number of arguments), `NOT`, `BUFF` (or `BUF`) and `DFF`.
+/// Names of gates are case insensitive.
+///
+/// D flip-flops are states of the circuit. The circuit inputs are organized in form:
+/// `[state,inputs]`. The circuit outputs are organized in form: `[state,outputs]`, where
+/// state are next states (inputs of D flip-flops). States and next states are in order of
+/// D flip-flops, inputs and outputs in order of declarations.
+///
+/// Function returns Gate circuit with its mapping. Mapping in form: key - name of D
+/// flip-flop output, input, D flip-flop input or output (in this order),
+/// value - assignment in circuit.
+pub fn from_bench(input: impl Read) -> Result<(Circuit<usize>, BenchMap), BenchError> {
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    let mut dffs = vec![];
+    let mut drivers = HashMap::new();
+    let mut driven = HashSet::new();
+    for (i, line) in BufReader::new(input).lines().enumerate() {
+        let line_no = i + 1;
+        let line = line?;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (output, (func, args)) = if let Some((output, call)) = line.split_once('=') {
+            let output = output.trim();
+            if !is_bench_name(output) {
+                return Err(BenchError::SyntaxError(line_no, line.to_string()));
+            }
+            (Some(output), parse_bench_call(line_no, call)?)
+        } else {
+            (None, parse_bench_call(line_no, line)?)
+        };
+        let arg_num_ok = match func.as_str() {
+            "INPUT" | "OUTPUT" | "NOT" | "BUFF" | "BUF" | "DFF" => args.len() == 1,
+            "AND" | "NAND" | "OR" | "NOR" | "XOR" | "XNOR" => !args.is_empty(),
+            _ => {
+                return Err(BenchError::UnknownGate(line_no, func));
+            }
+        };
+        // INPUT and OUTPUT are declarations, other are gates
+        if output.is_some() == matches!(func.as_str(), "INPUT" | "OUTPUT") {
+            return Err(BenchError::SyntaxError(line_no, line.to_string()));
+        }
+        if !arg_num_ok {
+            return Err(BenchError::WrongArgNum(line_no, func));
+        }
+        let driven_name = match func.as_str() {
+            "INPUT" => args[0].as_str(),
+            "OUTPUT" => {
+                outputs.push(args[0].clone());
+                continue;
+            }
+            _ => output.unwrap(),
+        };
+        if !driven.insert(driven_name.to_string()) {
+            return Err(BenchError::NetAlreadyDriven(
+                line_no,
+                driven_name.to_string(),
+            ));
+        }
+        match func.as_str() {
+            "INPUT" => inputs.push(driven_name.to_string()),
+            "DFF" => dffs.push((driven_name.to_string(), args[0].clone())),
+            _ => {
+                drivers.insert(driven_name.to_string(), bench_gate_expr(&func, args));
+            }
+        }
+    }
+    let circuit_inputs = dffs
+        .iter()
+        .map(|(q, _)| q.clone())
+        .chain(inputs)
+        .collect::<Vec<_>>();
+    let circuit_outputs = dffs
+        .iter()
+        .map(|(_, d)| d.clone())
+        .chain(outputs)
+        .collect::<Vec<_>>();
+    vexprs_to_circuit(circuit_inputs, circuit_outputs, &drivers).map_err(|e| match e {
+        VNetError::UndrivenNet(name) => BenchError::UndrivenNet(name),
+        VNetError::Cycle(name) => BenchError::CycleInCircuit(name),
+    })
+}
@@ -2,12 +2,15 @@
 //! Module to conversion between Gate circuit and the BLIF logic format.
 
 use crate::gatesim::*;
-use crate::AssignEntry;
+use crate::{AssignEntry, HierCircuit, HierInstance, NameError, WriterNames};
 use gategen::boolvar::*;
 use gategen::dynintvar::*;
 use gateutil::{reverse_trans, translate_inputs, translate_outputs};
 
 use crate::blif_pla::*;
+use crate::genlib::*;
+use crate::kiss2::*;
+use crate::lutmap::*;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
@@ -15,6 +18,69 @@ use std::fs::File;
 use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 
+/// Options for conversion to BLIF format.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BLIFOptions {
+    /// Initial values of latches: None - unknown. If vector is shorter than state then
+    /// rest of latches are written without initial value.
+    pub latch_inits: Vec<Option<bool>>,
+    /// Cell library. If given then gates are written as `.gate` with library cells.
+    pub library: Option<GenlibLibrary>,
+    /// Maximal number of inputs of `.names`. If greater than 2 then fanout-free cones of
+    /// gates with up to this number of inputs are collapsed into single `.names` with
+    /// minimized cover. Cone is collapsed only if its cover has no more cubes than
+    /// number of its gates. Values greater than 6 are treated as 6, because cover is
+    /// minimized from full truth table. Ignored if cell library is given.
+    pub max_names_inputs: usize,
+    /// Names of inputs, outputs and internal wires.
+    pub names: WriterNames,
+}
+
+// max number of inputs of collapsed `.names` - cover is minimized from full truth table
+const MAX_NAMES_INPUTS: usize = 6;
+
+// BLIF names can't have whitespaces, comments and '=' (used in `.gate` and `.subckt`).
+fn blif_check_name(name: &str) -> Result<String, NameError> {
+    if name.is_empty()
+        || name
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '#' || c == '=')
+    {
+        Err(NameError::InvalidName(name.to_string()))
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// Options for conversion from BLIF format.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BLIFReadOptions {
+    /// Cell library used by `.gate` and `.mlatch`. If not given then `.gate` and `.mlatch`
+    /// are not supported.
+    pub library: Option<GenlibLibrary>,
+    /// If true then external don't care network (`.exdc`) is read and its functions are
+    /// extra don't care outputs (one per model output) named `output#exdc`.
+    /// Otherwise external don't care network is ignored and reported in
+    /// `BLIFInfo::exdc_ignored`. Only network of top model is reported: ignored network
+    /// of sub-model doesn't set this flag.
+    pub exdc_outputs: bool,
+    /// Encoding of states of finite state machines (`.start_kiss`).
+    pub fsm_encoding: KISS2Encoding,
+}
+
+/// Additional information of BLIF logic.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BLIFInfo {
+    /// Initial values of latches (in state order): None - unknown, don't care or not given.
+    pub latch_inits: Vec<Option<bool>>,
+    /// States of finite state machine of top model with their codes. Code is list of
+    /// values of state bits `fsm#stateN`.
+    pub fsm_states: Vec<(String, Vec<bool>)>,
+    /// True if external don't care network (`.exdc`) of top model was ignored,
+    /// because `exdc_outputs` is false.
+    pub exdc_ignored: bool,
+}
+
 /// Converts circuit to BLIF format.
 ///
 /// Function writes Gate circuit logic in BLIF format to `out`. `circuit` is circuit
@@ -31,15 +97,194 @@ pub fn to_blif(
     model_name: &str,
     out: impl Write,
 ) -> io::Result<()> {
-    let input_len = circuit.input_len();
-    let output_len = circuit.outputs().len();
-    assert!(state_len + clock_num <= input_len);
-    assert!(state_len <= output_len);
+    to_blif_with_options(
+        circuit,
+        state_len,
+        clock_num,
+        model_name,
+        &BLIFOptions::default(),
+        out,
+    )
+}
 
+/// Converts circuit to BLIF format with options.
+///
+/// Function works like `to_blif`, but it also uses `options`. Initial values of latches
+/// are written as last parameter of `.latch`: 0, 1 or 3 (unknown). If cell library is given
+/// then gates, negations and output duplicates are written as `.gate` with a cell (with
+/// smallest area) that implements their function. If library doesn't have that cell then
+/// they are written as `.names`.
+///
+/// Names of inputs, outputs and wires are given by `names`. Names can't have whitespaces,
+/// `#` and `=`. If names collide or name is invalid then function returns error of kind
+/// `InvalidInput` that holds `NameError`.
+pub fn to_blif_with_options(
+    circuit: &Circuit<usize>,
+    state_len: usize,
+    clock_num: usize,
+    model_name: &str,
+    options: &BLIFOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write_blif_model(
+        BLIFLogic::Gates(circuit),
+        state_len,
+        clock_num,
+        model_name,
+        options,
+        &[],
+        &mut out,
+    )
+}
+
+/// Converts hierarchical circuit to BLIF format.
+///
+/// Function works like `to_blif_with_options`, but it writes hierarchical circuit.
+/// The top circuit is written as first model that includes instances of sub-circuits as
+/// `.subckt`. Sub-circuits are written as next models with names of sub-circuits.
+///
+/// If name of sub-circuit is same as name of top model then function returns error of kind
+/// `InvalidInput` that holds `NameError`.
+///
+/// The top circuit inputs are organized in form: `[state,clocks,inputs,instance outputs]`.
+/// The top circuit outputs are organized in form: `[state,outputs,instance inputs]`.
+pub fn to_blif_hier(
+    hcircuit: &HierCircuit,
+    state_len: usize,
+    clock_num: usize,
+    model_name: &str,
+    options: &BLIFOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    hcircuit.check_top_name(model_name, |n| n.to_string())?;
     let mut out = BufWriter::new(out);
+    write_blif_model(
+        BLIFLogic::Gates(hcircuit.circuit()),
+        state_len,
+        clock_num,
+        model_name,
+        options,
+        &hcircuit.hier_instances(),
+        &mut out,
+    )?;
+    let sc_options = BLIFOptions {
+        latch_inits: vec![],
+        library: options.library.clone(),
+        max_names_inputs: options.max_names_inputs,
+        names: WriterNames::default(),
+    };
+    for (name, sc) in hcircuit.subcircuits() {
+        write_blif_model(BLIFLogic::Gates(sc), 0, 0, name, &sc_options, &[], &mut out)?;
+    }
+    Ok(())
+}
+
+/// Converts LUT circuit to BLIF format.
+///
+/// Function writes LUT circuit in BLIF format to `out`. Every LUT is written as `.names`
+/// with minimized cover. `state_len` is length of state that represents in BLIF as latches.
+/// `clock_num` is number of clocks in BLIF logic. `model_name` is name of top logic
+/// circuit in BLIF format.
+///
+/// The circuit inputs are organized in form: `[state,clocks,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_blif_lut(
+    circuit: &LUTCircuit,
+    state_len: usize,
+    clock_num: usize,
+    model_name: &str,
+    out: impl Write,
+) -> io::Result<()> {
+    to_blif_lut_with_options(
+        circuit,
+        state_len,
+        clock_num,
+        model_name,
+        &BLIFOptions::default(),
+        out,
+    )
+}
+
+/// Converts LUT circuit to BLIF format with options.
+///
+/// Function works like `to_blif_lut`, but it also uses `options` in this same way as
+/// `to_blif_with_options`. LUTs are always written as `.names`, hence cell library is
+/// used only for negations and output duplicates and `max_names_inputs` is ignored.
+pub fn to_blif_lut_with_options(
+    circuit: &LUTCircuit,
+    state_len: usize,
+    clock_num: usize,
+    model_name: &str,
+    options: &BLIFOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write_blif_model(
+        BLIFLogic::LUTs(circuit),
+        state_len,
+        clock_num,
+        model_name,
+        options,
+        &[],
+        &mut out,
+    )
+}
+
+// logic of model: circuit with gates or LUT circuit. Outputs of gates or LUTs are next
+// wires after circuit inputs.
+#[derive(Clone, Copy)]
+enum BLIFLogic<'a> {
+    Gates(&'a Circuit<usize>),
+    LUTs(&'a LUTCircuit),
+}
+
+impl BLIFLogic<'_> {
+    fn input_len(&self) -> usize {
+        match self {
+            BLIFLogic::Gates(circuit) => circuit.input_len(),
+            BLIFLogic::LUTs(circuit) => circuit.input_len(),
+        }
+    }
+
+    fn outputs(&self) -> &[(usize, bool)] {
+        match self {
+            BLIFLogic::Gates(circuit) => circuit.outputs(),
+            BLIFLogic::LUTs(circuit) => circuit.outputs(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            BLIFLogic::Gates(circuit) => circuit.len(),
+            BLIFLogic::LUTs(circuit) => circuit.luts().len(),
+        }
+    }
+}
+
+// write model with instances of sub-circuits. Top circuit inputs that are instance outputs
+// and top circuit outputs that are instance inputs are not model inputs and outputs.
+fn write_blif_model(
+    logic: BLIFLogic,
+    state_len: usize,
+    clock_num: usize,
+    model_name: &str,
+    options: &BLIFOptions,
+    instances: &[HierInstance],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let input_len = logic.input_len();
+    let outputs = logic.outputs();
+    let output_len = outputs.len();
+    let port_input_len = input_len - instances.iter().map(|i| i.output_len).sum::<usize>();
+    let port_output_len = output_len - instances.iter().map(|i| i.input_len).sum::<usize>();
+    assert!(state_len + clock_num <= port_input_len);
+    assert!(state_len <= port_output_len);
+    assert!(options.latch_inits.len() <= state_len);
+
     let mut wire_out_map = BTreeMap::new();
     let mut dup_map = vec![];
-    for (oi, (o, n)) in circuit.outputs().iter().enumerate() {
+    for (oi, (o, n)) in outputs.iter().enumerate() {
         if let Some((old_oi, _)) = wire_out_map.get(&(*o, *n)) {
             // resolve duplicate
             dup_map.push((oi, *old_oi));
@@ -47,59 +292,399 @@ pub fn to_blif(
             wire_out_map.insert((*o, *n), (oi, *n));
         }
     }
+    let names = options.names.resolve(
+        &[],
+        input_len,
+        output_len,
+        (input_len..input_len + logic.len()).filter(|wi| !wire_out_map.contains_key(&(*wi, false))),
+        blif_check_name,
+        |name| name.to_string(),
+    )?;
     writeln!(out, ".model {}", model_name)?;
-    for i in 0..state_len {
-        writeln!(out, ".inputs i{}", i)?;
-    }
-    for i in state_len + clock_num..input_len {
-        writeln!(out, ".inputs i{}", i)?;
+    for name in names.inputs[0..state_len]
+        .iter()
+        .chain(&names.inputs[state_len + clock_num..port_input_len])
+    {
+        writeln!(out, ".inputs {}", name)?;
     }
-    for i in 0..output_len {
-        writeln!(out, ".outputs o{}", i)?;
+    for name in &names.outputs[0..port_output_len] {
+        writeln!(out, ".outputs {}", name)?;
     }
-    for i in state_len..state_len + clock_num {
-        writeln!(out, ".clock i{}", i)?;
+    for name in &names.inputs[state_len..state_len + clock_num] {
+        writeln!(out, ".clock {}", name)?;
     }
     for i in 0..state_len {
-        writeln!(out, ".latch o{0} i{0}", i)?;
+        write!(out, ".latch {} {}", names.outputs[i], names.inputs[i])?;
+        match options.latch_inits.get(i) {
+            Some(Some(false)) => out.write_all(b" 0\n")?,
+            Some(Some(true)) => out.write_all(b" 1\n")?,
+            Some(None) => out.write_all(b" 3\n")?,
+            None => out.write_all(b"\n")?,
+        }
     }
+    // instance inputs that are top circuit inputs - they are connected directly
+    let direct_input = |oi: usize| {
+        let (o, n) = outputs[oi];
+        oi >= port_output_len && !n && o < input_len
+    };
+    let instance_input = |oi: usize| {
+        if direct_input(oi) {
+            &names.inputs[outputs[oi].0]
+        } else {
+            &names.outputs[oi]
+        }
+    };
     let resolve_name = |i| {
-        if let Some((oi, _)) = wire_out_map.get(&(i, false)) {
-            format!("o{}", oi)
+        if let Some((oi, _)) = wire_out_map.get(&(i, false)).filter(|_| i >= input_len) {
+            names.outputs[*oi].clone()
+        } else if i < input_len {
+            names.inputs[i].clone()
         } else {
-            format!("i{}", i)
+            names.wire(i).to_string()
         }
     };
-    for (i, g) in circuit.gates().iter().enumerate() {
-        writeln!(
-            out,
-            ".names {} {} {}",
-            resolve_name(g.i0),
-            resolve_name(g.i1),
-            resolve_name(i + input_len)
-        )?;
-        let pla_tbl = match g.func {
-            GateFunc::And => b"11 1\n".as_slice(),
-            GateFunc::Nor => b"00 1\n".as_slice(),
-            GateFunc::Nimpl => b"10 1\n".as_slice(),
-            GateFunc::Xor => b"10 1\n01 1\n".as_slice(),
-        };
-        out.write(pla_tbl)?;
+    // library cells for: And, Nor, Nimpl, Xor, negation and duplicate.
+    let find_cell = |input_num, table| {
+        options
+            .library
+            .as_ref()
+            .and_then(|library| find_library_cell(library, input_num, table))
+    };
+    let gate_cells = [
+        find_cell(2, 8),
+        find_cell(2, 1),
+        find_cell(2, 2),
+        find_cell(2, 6),
+    ];
+    let not_cell = find_cell(1, 1);
+    let buf_cell = find_cell(1, 2);
+    // roots of cones written as negated outputs
+    let mut neg_roots = HashSet::new();
+    match logic {
+        BLIFLogic::LUTs(circuit) => {
+            for (i, lut) in circuit.luts().iter().enumerate() {
+                let inputs = lut
+                    .inputs
+                    .iter()
+                    .map(|x| resolve_name(*x))
+                    .collect::<Vec<_>>();
+                let table = (0..1 << inputs.len())
+                    .map(|t| ((lut.table >> t) & 1) != 0)
+                    .collect::<Vec<_>>();
+                write_names_cover(out, &inputs, &resolve_name(input_len + i), &table)?;
+            }
+        }
+        BLIFLogic::Gates(circuit) if options.library.is_none() && options.max_names_inputs > 2 => {
+            let cones = collapse_gates(circuit, options.max_names_inputs.min(MAX_NAMES_INPUTS));
+            let all_leaves = cones
+                .iter()
+                .flat_map(|c| c.leaves.iter().copied())
+                .collect::<HashSet<_>>();
+            for cone in cones {
+                let leaves = cone
+                    .leaves
+                    .iter()
+                    .map(|l| resolve_name(*l))
+                    .collect::<Vec<_>>();
+                // if cone is used only by negated output then write negated cone to output
+                if !all_leaves.contains(&cone.root)
+                    && !wire_out_map.contains_key(&(cone.root, false))
+                {
+                    if let Some((oi, _)) = wire_out_map.get(&(cone.root, true)) {
+                        write_names(out, &leaves, &names.outputs[*oi], &cone.cover, !cone.value)?;
+                        neg_roots.insert(cone.root);
+                        continue;
+                    }
+                }
+                write_names(
+                    out,
+                    &leaves,
+                    &resolve_name(cone.root),
+                    &cone.cover,
+                    cone.value,
+                )?;
+            }
+        }
+        BLIFLogic::Gates(circuit) => {
+            for (i, g) in circuit.gates().iter().enumerate() {
+                let gate_cell = match g.func {
+                    GateFunc::And => &gate_cells[0],
+                    GateFunc::Nor => &gate_cells[1],
+                    GateFunc::Nimpl => &gate_cells[2],
+                    GateFunc::Xor => &gate_cells[3],
+                };
+                if let Some((cell, perm)) = gate_cell {
+                    write_library_cell(
+                        out,
+                        cell,
+                        perm,
+                        &[resolve_name(g.i0), resolve_name(g.i1)],
+                        &resolve_name(i + input_len),
+                    )?;
+                    continue;
+                }
+                writeln!(
+                    out,
+                    ".names {} {} {}",
+                    resolve_name(g.i0),
+                    resolve_name(g.i1),
+                    resolve_name(i + input_len)
+                )?;
+                let pla_tbl = match g.func {
+                    GateFunc::And => b"11 1\n".as_slice(),
+                    GateFunc::Nor => b"00 1\n".as_slice(),
+                    GateFunc::Nimpl => b"10 1\n".as_slice(),
+                    GateFunc::Xor => b"10 1\n01 1\n".as_slice(),
+                };
+                out.write_all(pla_tbl)?;
+            }
+        }
     }
-    // generate negations
+    // generate negations and outputs that are inputs
     for ((o, _), (oi, n)) in &wire_out_map {
-        if *n {
-            write!(out, ".names {} o{}\n0 1\n", resolve_name(*o), *oi)?;
+        let name = &names.outputs[*oi];
+        if !*n && *o < input_len && !direct_input(*oi) {
+            if let Some((cell, perm)) = &buf_cell {
+                write_library_cell(out, cell, perm, &[resolve_name(*o)], name)?;
+            } else {
+                write!(out, ".names {} {}\n1 1\n", resolve_name(*o), name)?;
+            }
+        } else if *n && !neg_roots.contains(o) {
+            if let Some((cell, perm)) = &not_cell {
+                write_library_cell(out, cell, perm, &[resolve_name(*o)], name)?;
+            } else {
+                write!(out, ".names {} {}\n0 1\n", resolve_name(*o), name)?;
+            }
         }
     }
     // generate output duplicates
-    for (oi, old_oi) in dup_map {
-        write!(out, ".names o{} o{}\n1 1\n", old_oi, oi)?;
+    for (oi, old_oi) in dup_map.into_iter().filter(|(oi, _)| !direct_input(*oi)) {
+        let (name, old_name) = (&names.outputs[oi], &names.outputs[old_oi]);
+        if let Some((cell, perm)) = &buf_cell {
+            write_library_cell(out, cell, perm, std::slice::from_ref(old_name), name)?;
+        } else {
+            write!(out, ".names {} {}\n1 1\n", old_name, name)?;
+        }
     }
-    out.write(b".end\n")?;
+    // generate instances of sub-circuits
+    for inst in instances {
+        write!(out, ".subckt {}", inst.name)?;
+        for i in 0..inst.input_len {
+            write!(out, " i{}={}", i, instance_input(inst.input_start + i))?;
+        }
+        for i in 0..inst.output_len {
+            write!(out, " o{}={}", i, names.inputs[inst.output_start + i])?;
+        }
+        out.write_all(b"\n")?;
+    }
+    out.write_all(b".end\n")?;
     Ok(())
 }
 
+// Cone of gates collapsed into single `.names`: root wire, leaf wires and minimized cover
+// of truth table with value of cover.
+struct GateCone {
+    root: usize,
+    leaves: Vec<usize>,
+    cover: Vec<Vec<PLACell>>,
+    value: bool,
+}
+
+// Collapse fanout-free cones of gates into cones with up to `max_inputs` leaves.
+// Gates used by many gates or by outputs are always roots of cones. If cover of cone has
+// more cubes than cone has gates then only root gate is collapsed.
+fn collapse_gates(circuit: &Circuit<usize>, max_inputs: usize) -> Vec<GateCone> {
+    let input_len = circuit.input_len();
+    let gates = circuit.gates();
+    let mut fanouts = vec![0usize; gates.len()];
+    for g in gates {
+        for i in [g.i0, g.i1] {
+            if i >= input_len {
+                fanouts[i - input_len] += 1;
+            }
+        }
+    }
+    let mut roots = fanouts.into_iter().map(|f| f != 1).collect::<Vec<_>>();
+    for (o, _) in circuit.outputs() {
+        if *o >= input_len {
+            roots[*o - input_len] = true;
+        }
+    }
+    // minimized cover of cone given by leaves and inner gates (sorted)
+    let cone_cover = |root: usize, leaves: &[usize], inner: &[usize]| {
+        let table = (0..1usize << leaves.len())
+            .map(|t| {
+                let mut values = leaves
+                    .iter()
+                    .enumerate()
+                    .map(|(b, l)| (*l, ((t >> b) & 1) != 0))
+                    .collect::<HashMap<_, _>>();
+                for w in inner {
+                    let g = &gates[*w - input_len];
+                    let v = g.eval_args(values[&g.i0], values[&g.i1]);
+                    values.insert(*w, v);
+                }
+                values[&root]
+            })
+            .collect::<Vec<_>>();
+        names_cover(leaves.len(), &table)
+    };
+    let mut cones = vec![];
+    for gi in (0..gates.len()).rev() {
+        if !roots[gi] {
+            continue;
+        }
+        let root = gi + input_len;
+        let mut leaves = vec![gates[gi].i0, gates[gi].i1];
+        leaves.sort();
+        leaves.dedup();
+        let gate_leaves = leaves.clone();
+        let mut inner = vec![root];
+        // expand leaves that are not roots while number of leaves is not greater than max.
+        while let Some((l, new_leaves)) = leaves
+            .iter()
+            .rev()
+            .filter(|l| **l >= input_len && !roots[**l - input_len])
+            .find_map(|l| {
+                let g = &gates[*l - input_len];
+                let mut new_leaves = leaves
+                    .iter()
+                    .copied()
+                    .filter(|x| x != l)
+                    .chain([g.i0, g.i1])
+                    .collect::<Vec<_>>();
+                new_leaves.sort();
+                new_leaves.dedup();
+                (new_leaves.len() <= max_inputs).then_some((*l, new_leaves))
+            })
+        {
+            inner.push(l);
+            leaves = new_leaves;
+        }
+        inner.sort();
+        let (mut cover, mut value) = cone_cover(root, &leaves, &inner);
+        if inner.len() > 1 && cover.len() > inner.len() {
+            // cone is not smaller than its gates
+            leaves = gate_leaves;
+            (cover, value) = cone_cover(root, &leaves, &[root]);
+        }
+        // not expanded gates are roots of other cones
+        for l in &leaves {
+            if *l >= input_len {
+                roots[*l - input_len] = true;
+            }
+        }
+        cones.push(GateCone {
+            root,
+            leaves,
+            cover,
+            value,
+        });
+    }
+    cones.reverse();
+    cones
+}
+
+// Minimized cover of truth table (bit of index - value of input) and value of cover.
+// Cover of false values is returned if it is smaller.
+fn names_cover(input_len: usize, table: &[bool]) -> (Vec<Vec<PLACell>>, bool) {
+    let neg_table = table.iter().map(|v| !v).collect::<Vec<_>>();
+    let on_cover = truth_table_to_pla(input_len, table);
+    let off_cover = truth_table_to_pla(input_len, &neg_table);
+    if !off_cover.is_empty() && off_cover.len() < on_cover.len() {
+        (off_cover, false)
+    } else {
+        (on_cover, true)
+    }
+}
+
+// Write `.names` with cover that gives `value` for its cubes.
+fn write_names(
+    out: &mut impl Write,
+    inputs: &[String],
+    output: &str,
+    cover: &[Vec<PLACell>],
+    value: bool,
+) -> io::Result<()> {
+    writeln!(out, ".names {} {}", inputs.join(" "), output)?;
+    for entry in cover {
+        let entry = entry
+            .iter()
+            .map(|c| match c {
+                PLACell::Zero => '0',
+                PLACell::One => '1',
+                PLACell::Unknown => '-',
+            })
+            .collect::<String>();
+        writeln!(out, "{} {}", entry, u8::from(value))?;
+    }
+    Ok(())
+}
+
+// Write `.names` with minimized cover of truth table (bit of index - value of input).
+// Cover of false values is written if it is smaller.
+fn write_names_cover(
+    out: &mut impl Write,
+    inputs: &[String],
+    output: &str,
+    table: &[bool],
+) -> io::Result<()> {
+    let (cover, value) = names_cover(inputs.len(), table);
+    write_names(out, inputs, output, &cover, value)
+}
+
+// Find combinational cell with smallest area that implements function with `input_num` inputs
+// given by truth table `table` (bit index - combination of input values).
+// Returns cell and permutation: index - cell input, value - function input.
+fn find_library_cell(
+    library: &GenlibLibrary,
+    input_num: usize,
+    table: u8,
+) -> Option<(&GenlibCell, Vec<usize>)> {
+    let perms: &[&[usize]] = if input_num == 1 {
+        &[&[0]]
+    } else {
+        &[&[0, 1], &[1, 0]]
+    };
+    let mut best: Option<(&GenlibCell, Vec<usize>)> = None;
+    for cell in &library.cells {
+        if cell.is_latch() || cell.inputs.len() != input_num {
+            continue;
+        }
+        if best
+            .as_ref()
+            .map(|(c, _)| c.area <= cell.area)
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        for perm in perms {
+            if (0..1 << input_num).all(|c| {
+                let values = perm.iter().map(|p| ((c >> p) & 1) != 0).collect::<Vec<_>>();
+                cell.function.eval(&values) == (((table >> c) & 1) != 0)
+            }) {
+                best = Some((cell, perm.to_vec()));
+                break;
+            }
+        }
+    }
+    best
+}
+
+fn write_library_cell(
+    out: &mut impl Write,
+    cell: &GenlibCell,
+    perm: &[usize],
+    args: &[String],
+    output: &str,
+) -> io::Result<()> {
+    write!(out, ".gate {}", cell.name)?;
+    for (pin, p) in cell.inputs.iter().zip(perm.iter()) {
+        write!(out, " {}={}", pin, args[*p])?;
+    }
+    writeln!(out, " {}={}", cell.output, output)
+}
+
 // Read lines. Concatenate lines, remove comments and trim lines.
 struct BLIFTokensReader<R: Read> {
     br: BufReader<R>,
@@ -202,14 +787,28 @@ pub enum BLIFError {
     UnknownModel(String, usize, String),
     #[error("{0}:{1}: Too few parameters")]
     TooFewParameters(String, usize),
+    #[error("{0}:{1}: Too many parameters")]
+    TooManyParameters(String, usize),
+    #[error("{0}:{1}: Bad latch initial value")]
+    BadLatchInitValue(String, usize),
     #[error("{0}:{1}: Unsupported latch input and output")]
     UnsupportedLatch(String, usize),
     #[error("{0}:{1}: Unsupported External Don't Care")]
     UnsupportedEXDC(String, usize),
     #[error("{0}:{1}: Unsupported FSM definition")]
     UnsupportedFSM(String, usize),
+    #[error("{0}: FSM definition without end")]
+    NoFSMEnd(String),
+    #[error("{0}:{1}: Bad FSM definition: {2}")]
+    BadFSM(String, usize, KISS2Error),
     #[error("{0}:{1}: Unsupported library gate")]
     UnsupportedGate(String, usize),
+    #[error("{0}:{1}: Unknown library cell {2}")]
+    UnknownCell(String, usize, String),
+    #[error("{0}:{1}: Bad library cell {2} type")]
+    BadCellType(String, usize, String),
+    #[error("{0}:{1}: Bad library cell {2} mapping")]
+    BadCellMapping(String, usize, String),
     #[error("{0}:{1}: Bad gate PLA table")]
     BadGateTable(String, usize),
     #[error("{0}:{1}: Bad subcircuit {2} mapping")]
@@ -280,13 +879,23 @@ struct Model {
     inputs: Vec<String>,
     outputs: Vec<String>,
     latches: Vec<(String, String)>,
+    // initial values of latches: None - unknown or don't care
+    latch_inits: Vec<Option<bool>>,
     clocks: Vec<String>,
     gates: Vec<Gate>,
     subcircuits: Vec<Subcircuit>,
+    // internal states (latches that are not tied to model inputs and outputs,
+    // also from subcircuits): (name, initial value).
+    states: Vec<(String, Option<bool>)>,
+    // states of finite state machine with their codes.
+    fsm_states: Vec<(String, Vec<bool>)>,
+    // true if external don't care network was ignored.
+    exdc_ignored: bool,
     // circuit: format:
     // first element - table circuit - same circuit,
     // second element - circuit mapping: in form:
-    //     index - in order: [model inputs, model clocks, model outputs]
+    //     index - in order: [model inputs, model clocks, model outputs,
+    //         internal states, internal next states]
     //     value - (name of model, name of wire, mapping to circuit)
     circuit: Option<(Circuit<usize>, Vec<CircuitMapping>)>,
 }
@@ -299,6 +908,8 @@ impl Model {
         let model_input_len = self.inputs.len();
         let model_clock_len = self.clocks.len();
         let model_output_len = self.outputs.len();
+        let port_len = model_input_len + model_clock_len + model_output_len;
+        let state_len = self.states.len();
         // circuit_mapping_indexes: index - mapping index,
         //    value - for input or clock is circuit input index or for output is circuit output
         let circuit_mapping_names = self
@@ -307,27 +918,28 @@ impl Model {
             .cloned()
             .chain(self.clocks.iter().cloned())
             .chain(self.outputs.iter().cloned())
+            .chain(self.states.iter().map(|(s, _)| s.clone()))
             .collect::<Vec<_>>();
         let circuit_mapping_indexes = {
-            let mut circuit_mapping_indexes =
-                vec![None; model_input_len + model_clock_len + model_output_len];
+            let mut circuit_mapping_indexes = vec![None; port_len + 2 * state_len];
             let mut input_count = 0;
-            for (i, cm) in mapping[0..model_input_len + model_clock_len]
-                .iter()
-                .enumerate()
-            {
-                if !matches!(cm, CircuitMapping::NoMapping | CircuitMapping::Value(_)) {
+            for i in (0..model_input_len + model_clock_len).chain(port_len..port_len + state_len) {
+                if !matches!(
+                    mapping[i],
+                    CircuitMapping::NoMapping | CircuitMapping::Value(_)
+                ) {
                     circuit_mapping_indexes[i] = Some(input_count);
                     input_count += 1;
                 }
             }
             let mut output_count = 0;
-            for (i, cm) in mapping
-                .iter()
-                .enumerate()
-                .skip(model_input_len + model_clock_len)
+            for i in (model_input_len + model_clock_len..port_len)
+                .chain(port_len + state_len..port_len + 2 * state_len)
             {
-                if !matches!(cm, CircuitMapping::NoMapping | CircuitMapping::Value(_)) {
+                if !matches!(
+                    mapping[i],
+                    CircuitMapping::NoMapping | CircuitMapping::Value(_)
+                ) {
                     circuit_mapping_indexes[i] = Some(output_count);
                     output_count += 1;
                 }
@@ -343,6 +955,9 @@ impl Model {
         let state_mapping = self
             .latches
             .iter()
+            .filter(|(model_out, model_in)| {
+                model_output_map.contains_key(model_out) && model_input_map.contains_key(model_in)
+            })
             .map(|(model_out, model_in)| {
                 (
                     model_input_len + model_clock_len + model_output_map[model_out],
@@ -355,6 +970,13 @@ impl Model {
         let circuit_input_trans_rev = state_mapping
             .iter()
             .filter_map(|(_, model_input_idx)| circuit_mapping_indexes[*model_input_idx])
+            .chain(
+                // internal states
+                circuit_mapping_indexes[port_len..port_len + state_len]
+                    .iter()
+                    .copied()
+                    .flatten(),
+            )
             .chain(
                 // clocks
                 circuit_mapping_indexes[model_input_len..model_input_len + model_clock_len]
@@ -380,11 +1002,18 @@ impl Model {
         let circuit_output_trans_rev = state_mapping
             .iter()
             .filter_map(|(model_output_idx, _)| circuit_mapping_indexes[*model_output_idx])
+            .chain(
+                // internal next states
+                circuit_mapping_indexes[port_len + state_len..]
+                    .iter()
+                    .copied()
+                    .flatten(),
+            )
             .chain(
                 // outputs (not states)
-                circuit_mapping_indexes[model_input_len + model_clock_len..]
+                circuit_mapping_indexes[model_input_len + model_clock_len..port_len]
                     .iter()
-                    .zip(mapping[model_input_len + model_clock_len..].iter())
+                    .zip(mapping[model_input_len + model_clock_len..port_len].iter())
                     .filter_map(|(idx, cm)| {
                         if matches!(cm, CircuitMapping::Output(false)) {
                             *idx
@@ -396,8 +1025,9 @@ impl Model {
             .collect::<Vec<_>>();
         let circuit_input_trans = reverse_trans(circuit_input_trans_rev);
         // mapping
-        let assign_mapping = mapping
-            .into_iter()
+        // internal next states are not in mapping - only internal states by latch name
+        let assign_mapping = mapping[0..port_len + state_len]
+            .iter()
             .zip(circuit_mapping_indexes.into_iter())
             .zip(circuit_mapping_names.into_iter())
             .map(|((cm, ci), cn)| {
@@ -456,12 +1086,148 @@ impl GateCacheKey {
 
 type GateCache = HashMap<GateCacheKey, TableCircuit>;
 type ModelMap = HashMap<String, Model>;
+type BLIFMap = Vec<(String, AssignEntry)>;
+
+// library cell
+struct Cell {
+    inputs: Vec<String>,
+    output: String,
+    circuit: TableCircuit,
+    latch: bool,
+}
+
+type CellMap = HashMap<String, Cell>;
+
+fn genlib_expr_to_boolvar(expr: &GenlibExpr, vars: &[BoolVarSys]) -> BoolVarSys {
+    match expr {
+        GenlibExpr::Value(v) => BoolVarSys::from(*v),
+        GenlibExpr::Input(i) => vars[*i].clone(),
+        GenlibExpr::Not(a) => !genlib_expr_to_boolvar(a, vars),
+        GenlibExpr::And(a, b) => genlib_expr_to_boolvar(a, vars) & genlib_expr_to_boolvar(b, vars),
+        GenlibExpr::Or(a, b) => genlib_expr_to_boolvar(a, vars) | genlib_expr_to_boolvar(b, vars),
+        GenlibExpr::Xor(a, b) => genlib_expr_to_boolvar(a, vars) ^ genlib_expr_to_boolvar(b, vars),
+    }
+}
+
+fn gen_cell_map(library: &GenlibLibrary) -> CellMap {
+    CellMap::from_iter(library.cells.iter().map(|cell| {
+        let circuit = callsys(|| {
+            let vars = (0..cell.inputs.len())
+                .map(|_| BoolVarSys::var())
+                .collect::<Vec<_>>();
+            let expr = genlib_expr_to_boolvar(&cell.function, &vars);
+            if let Some(v) = expr.value() {
+                TableCircuit::Value(v)
+            } else {
+                TableCircuit::Circuit(
+                    UDynVarSys::from_iter([expr]).to_translated_circuit_with_map(vars.into_iter()),
+                )
+            }
+        });
+        (
+            cell.name.clone(),
+            Cell {
+                inputs: cell.inputs.clone(),
+                output: cell.output.clone(),
+                circuit,
+                latch: cell.is_latch(),
+            },
+        )
+    }))
+}
+
+// generate circuits of next state bits and outputs of finite state machine.
+// circuit inputs: [fsm inputs, state bits].
+fn gen_fsm_circuits(
+    fsm: &KISS2FSM,
+    encoding: KISS2Encoding,
+    codes: &[Vec<bool>],
+) -> Vec<TableCircuit> {
+    callsys(|| {
+        let vars = (0..fsm.input_len + codes[0].len())
+            .map(|_| BoolVarSys::var())
+            .collect::<Vec<_>>();
+        let (nexts, outputs) = fsm.exprs(
+            encoding,
+            codes,
+            &vars[0..fsm.input_len],
+            &vars[fsm.input_len..],
+        );
+        nexts
+            .into_iter()
+            .chain(outputs)
+            .map(|expr| {
+                if let Some(v) = expr.value() {
+                    TableCircuit::Value(v)
+                } else {
+                    TableCircuit::Circuit(
+                        UDynVarSys::from_iter([expr])
+                            .to_translated_circuit_with_map(vars.iter().cloned()),
+                    )
+                }
+            })
+            .collect()
+    })
+}
+
+// check whether gate output is not model input or clock and whether it is not already defined.
+fn check_gate_output(
+    filename: &str,
+    line_no: usize,
+    output: &str,
+    model_input_set: &HashSet<String>,
+    model_clock_set: &HashSet<String>,
+    all_outputs: &mut HashSet<String>,
+) -> Result<(), BLIFError> {
+    // check whether output is not in inputs of model
+    if model_input_set.contains(output) {
+        return Err(BLIFError::DefinedAsModelInput(
+            filename.to_string(),
+            line_no,
+            output.to_string(),
+        ));
+    }
+    // check whether output is not in clocks of model
+    if model_clock_set.contains(output) {
+        return Err(BLIFError::DefinedAsModelClock(
+            filename.to_string(),
+            line_no,
+            output.to_string(),
+        ));
+    }
+    if !all_outputs.insert(output.to_string()) {
+        // if not already newly inserted
+        return Err(BLIFError::AlreadyDefinedAsOutput(
+            filename.to_string(),
+            line_no,
+            output.to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_latch_init(filename: &str, line_no: usize, init: &str) -> Result<Option<bool>, BLIFError> {
+    match init {
+        "0" => Ok(Some(false)),
+        "1" => Ok(Some(true)),
+        "2" | "3" => Ok(None),
+        _ => Err(BLIFError::BadLatchInitValue(filename.to_string(), line_no)),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct ModelParseOptions<'a> {
+    cells: Option<&'a CellMap>,
+    exdc_outputs: bool,
+    fsm_encoding: KISS2Encoding,
+}
 
 fn parse_model<R: Read>(
     filename: &str,
     reader: &mut BLIFTokensReader<R>,
     circuit_cache: &mut CircuitCache,
     gate_cache: &mut GateCache,
+    options: ModelParseOptions,
 ) -> Result<(String, Model), BLIFError> {
     // get model name
     let mut model_name = String::new();
@@ -484,9 +1250,13 @@ fn parse_model<R: Read>(
         inputs: vec![],
         outputs: vec![],
         latches: vec![],
+        latch_inits: vec![],
         clocks: vec![],
         gates: vec![],
         subcircuits: vec![],
+        states: vec![],
+        fsm_states: vec![],
+        exdc_ignored: false,
         circuit: None,
     };
     let mut model_input_set = HashSet::new();
@@ -495,7 +1265,24 @@ fn parse_model<R: Read>(
     let mut after_model_decls = false;
     let mut all_outputs = HashSet::new();
     let mut have_end = false;
-    while let Some((line_no, line)) = reader.read_tokens()? {
+    // if in external don't care network
+    let mut exdc = false;
+    while let Some((line_no, mut line)) = reader.read_tokens()? {
+        if exdc {
+            // only gates are in external don't care network. Its wires (except model inputs)
+            // are renamed to separate them from main network ('#' can't be in BLIF names).
+            match line[0].as_str() {
+                ".names" => {
+                    for name in &mut line[1..] {
+                        if !model_input_set.contains(name) {
+                            *name = format!("{}#exdc", name);
+                        }
+                    }
+                }
+                ".end" => (),
+                _ => return Err(BLIFError::UnsupportedEXDC(filename.to_string(), line_no)),
+            }
+        }
         match line[0].as_str() {
             ".names" => {
                 // gate
@@ -507,30 +1294,14 @@ fn parse_model<R: Read>(
                 let mut last_set_value = true;
                 let var_num = line.len() - 2;
 
-                // check whether output is not in inputs of model
-                if model_input_set.contains(line.last().unwrap()) {
-                    return Err(BLIFError::DefinedAsModelInput(
-                        filename.to_string(),
-                        line_no,
-                        line.last().unwrap().clone(),
-                    ));
-                }
-                // check whether output is not in clocks of model
-                if model_clock_set.contains(line.last().unwrap()) {
-                    return Err(BLIFError::DefinedAsModelClock(
-                        filename.to_string(),
-                        line_no,
-                        line.last().unwrap().clone(),
-                    ));
-                }
-                if !all_outputs.insert(line.last().unwrap().clone()) {
-                    // if not already newly inserted
-                    return Err(BLIFError::AlreadyDefinedAsOutput(
-                        filename.to_string(),
-                        line_no,
-                        line.last().unwrap().clone(),
-                    ));
-                }
+                check_gate_output(
+                    filename,
+                    line_no,
+                    line.last().unwrap(),
+                    &model_input_set,
+                    &model_clock_set,
+                    &mut all_outputs,
+                )?;
 
                 while let Some((line_no, line)) = reader.read_tokens()? {
                     if let Some((entry, set_value, line_no)) =
@@ -666,13 +1437,19 @@ fn parse_model<R: Read>(
                 if line.len() < 3 {
                     return Err(BLIFError::TooFewParameters(filename.to_string(), line_no));
                 }
-                if !model_output_set.contains(&line[1]) {
-                    return Err(BLIFError::UnsupportedLatch(filename.to_string(), line_no));
-                }
-                if !model_input_set.contains(&line[2]) {
+                // latch output can be model input only if latch input is model output
+                // (state in model inputs and outputs).
+                if model_input_set.contains(&line[2]) && !model_output_set.contains(&line[1]) {
                     return Err(BLIFError::UnsupportedLatch(filename.to_string(), line_no));
                 }
+                // .latch input output [type control] [init-val]
+                let init = match line.len() {
+                    3 | 5 => None,
+                    4 | 6 => parse_latch_init(filename, line_no, &line[line.len() - 1])?,
+                    _ => return Err(BLIFError::TooManyParameters(filename.to_string(), line_no)),
+                };
                 model.latches.push((line[1].clone(), line[2].clone()));
+                model.latch_inits.push(init);
             }
             ".subckt" => {
                 after_model_decls = true;
@@ -716,10 +1493,188 @@ fn parse_model<R: Read>(
                 });
             }
             ".start_kiss" => {
-                return Err(BLIFError::UnsupportedFSM(filename.to_string(), line_no));
+                after_model_decls = true;
+                if !model.fsm_states.is_empty() {
+                    return Err(BLIFError::UnsupportedFSM(filename.to_string(), line_no));
+                }
+                let mut kiss_lines = vec![];
+                let mut have_end_kiss = false;
+                while let Some((line_no, line)) = reader.read_tokens()? {
+                    if line[0] == ".end_kiss" {
+                        have_end_kiss = true;
+                        break;
+                    }
+                    kiss_lines.push((line_no, line));
+                }
+                if !have_end_kiss {
+                    return Err(BLIFError::NoFSMEnd(filename.to_string()));
+                }
+                let fsm = KISS2FSM::parse(kiss_lines)
+                    .map_err(|e| BLIFError::BadFSM(filename.to_string(), line_no, e))?;
+                // FSM inputs and outputs are first model inputs and outputs.
+                if fsm.input_len > model.inputs.len() || fsm.output_len > model.outputs.len() {
+                    return Err(BLIFError::UnsupportedFSM(filename.to_string(), line_no));
+                }
+                let codes = fsm.codes(options.fsm_encoding);
+                let state_len = codes[0].len();
+                // state bits - name with '#' can't be in BLIF names.
+                let states = (0..state_len)
+                    .map(|i| format!("fsm#state{}", i))
+                    .collect::<Vec<_>>();
+                let nexts = (0..state_len)
+                    .map(|i| format!("fsm#next{}", i))
+                    .collect::<Vec<_>>();
+                for output in &model.outputs[0..fsm.output_len] {
+                    check_gate_output(
+                        filename,
+                        line_no,
+                        output,
+                        &model_input_set,
+                        &model_clock_set,
+                        &mut all_outputs,
+                    )?;
+                }
+                let params = model.inputs[0..fsm.input_len]
+                    .iter()
+                    .chain(states.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let circuits = gen_fsm_circuits(&fsm, options.fsm_encoding, &codes);
+                for (output, circuit) in nexts
+                    .iter()
+                    .chain(model.outputs[0..fsm.output_len].iter())
+                    .zip(circuits)
+                {
+                    model.gates.push(Gate {
+                        params: params.clone(),
+                        output: output.clone(),
+                        circuit,
+                    });
+                }
+                for ((next, state), init) in nexts.into_iter().zip(states).zip(&codes[fsm.reset]) {
+                    model.latches.push((next, state));
+                    model.latch_inits.push(Some(*init));
+                }
+                model.fsm_states = fsm.states.into_iter().zip(codes).collect();
             }
             ".gate" | ".mlatch" => {
-                return Err(BLIFError::UnsupportedGate(filename.to_string(), line_no));
+                after_model_decls = true;
+                let Some(cells) = options.cells else {
+                    return Err(BLIFError::UnsupportedGate(filename.to_string(), line_no));
+                };
+                if line.len() < 2 {
+                    return Err(BLIFError::TooFewParameters(filename.to_string(), line_no));
+                }
+                let cell = cells.get(&line[1]).ok_or_else(|| {
+                    BLIFError::UnknownCell(filename.to_string(), line_no, line[1].clone())
+                })?;
+                let mlatch = line[0] == ".mlatch";
+                if cell.latch != mlatch {
+                    return Err(BLIFError::BadCellType(
+                        filename.to_string(),
+                        line_no,
+                        line[1].clone(),
+                    ));
+                }
+                // .gate name formal=actual ...
+                // .mlatch name formal=actual ... control [init-val]
+                let map_end = line[2..]
+                    .iter()
+                    .position(|s| !s.contains('='))
+                    .map(|p| p + 2)
+                    .unwrap_or(line.len());
+                let init = if mlatch {
+                    match line.len() - map_end {
+                        0 => {
+                            return Err(BLIFError::TooFewParameters(filename.to_string(), line_no))
+                        }
+                        1 => None,
+                        2 => parse_latch_init(filename, line_no, &line[line.len() - 1])?,
+                        _ => {
+                            return Err(BLIFError::TooManyParameters(filename.to_string(), line_no))
+                        }
+                    }
+                } else {
+                    if map_end != line.len() {
+                        return Err(BLIFError::BadCellMapping(
+                            filename.to_string(),
+                            line_no,
+                            line[1].clone(),
+                        ));
+                    }
+                    None
+                };
+                let bad_mapping =
+                    || BLIFError::BadCellMapping(filename.to_string(), line_no, line[1].clone());
+                let mut pin_map = HashMap::<&str, &str>::new();
+                for s in &line[2..map_end] {
+                    let (formal, actual) = s.split_at(s.find('=').unwrap());
+                    if (formal != cell.output && !cell.inputs.iter().any(|p| p == formal))
+                        || actual.len() == 1
+                        || pin_map.insert(formal, &actual[1..]).is_some()
+                    {
+                        return Err(bad_mapping());
+                    }
+                }
+                let output = pin_map.get(cell.output.as_str()).ok_or_else(bad_mapping)?;
+                let params = cell
+                    .inputs
+                    .iter()
+                    .map(|p| pin_map.get(p.as_str()).map(|x| x.to_string()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(bad_mapping)?;
+                if mlatch {
+                    // latch output can be model input only if latch input is model output.
+                    if model_input_set.contains(*output) {
+                        return Err(BLIFError::UnsupportedLatch(filename.to_string(), line_no));
+                    }
+                    check_gate_output(
+                        filename,
+                        line_no,
+                        output,
+                        &model_input_set,
+                        &model_clock_set,
+                        &mut all_outputs,
+                    )?;
+                    // next state - name with '#' can't be in BLIF names.
+                    let next = format!("{}#next", output);
+                    model.gates.push(Gate {
+                        params,
+                        output: next.clone(),
+                        circuit: cell.circuit.clone(),
+                    });
+                    model.latches.push((next, output.to_string()));
+                    model.latch_inits.push(init);
+                } else {
+                    check_gate_output(
+                        filename,
+                        line_no,
+                        output,
+                        &model_input_set,
+                        &model_clock_set,
+                        &mut all_outputs,
+                    )?;
+                    model.gates.push(Gate {
+                        params,
+                        output: output.to_string(),
+                        circuit: cell.circuit.clone(),
+                    });
+                }
+            }
+            ".exdc" => {
+                after_model_decls = true;
+                if options.exdc_outputs {
+                    exdc = true;
+                } else {
+                    model.exdc_ignored = true;
+                    while let Some((_, line)) = reader.read_tokens()? {
+                        if line[0] == ".end" {
+                            have_end = true;
+                            break;
+                        }
+                    }
+                    break;
+                }
             }
             ".end" => {
                 have_end = true;
@@ -740,6 +1695,20 @@ fn parse_model<R: Read>(
     if model.outputs.is_empty() {
         return Err(BLIFError::ModelWithoutOutputs(model_name.clone()));
     }
+    if exdc {
+        // add don't care outputs - if not defined then don't care is false.
+        for output in model.outputs.clone() {
+            let dc_output = format!("{}#exdc", output);
+            if !all_outputs.contains(&dc_output) {
+                model.gates.push(Gate {
+                    params: vec![],
+                    output: dc_output.clone(),
+                    circuit: TableCircuit::Value(false),
+                });
+            }
+            model.outputs.push(dc_output);
+        }
+    }
     // next phase - checking graph of gates and subcircuits - check whether graph have cycles.
     // next phase will be done while resolving graph of models.
     Ok((model_name, model))
@@ -758,11 +1727,13 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
         ModelClock(usize),
         Gate,
         Subcircuit,
+        Latch,
     }
     #[derive(Clone, Debug)]
     enum OutputNode {
         Gate(usize),              // gate index
         Subcircuit(usize, usize), // subcircuit index, output index
+        Latch(usize),             // internal latch index
     }
     #[derive(Clone)]
     enum Node {
@@ -770,6 +1741,7 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
         ModelClock(usize),
         Gate(usize),
         Subcircuit(usize, usize),
+        Latch(usize),
     }
     #[derive(Clone)]
     struct SubcircuitMapping {
@@ -783,7 +1755,25 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
         way: usize,
     }
 
+    let model_input_set = HashSet::<String>::from_iter(model.inputs.iter().cloned());
     let model_output_set = HashSet::<String>::from_iter(model.outputs.iter().cloned());
+    // internal latches - latches whose output is not model input:
+    // (latch input, latch output, initial value)
+    let internal_latches = model
+        .latches
+        .iter()
+        .enumerate()
+        .filter(|(_, (lin, lout))| {
+            !model_input_set.contains(lout) || !model_output_set.contains(lin)
+        })
+        .map(|(i, (lin, lout))| {
+            (
+                lin.clone(),
+                lout.clone(),
+                model.latch_inits.get(i).copied().flatten(),
+            )
+        })
+        .collect::<Vec<_>>();
     let mut wire_in_outs = HashMap::<String, (Vec<InputNode>, Option<OutputNode>)>::new();
     for (i, input) in model.inputs.iter().enumerate() {
         if let Some((wi, _)) = wire_in_outs.get_mut(input) {
@@ -821,16 +1811,42 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
             wire_in_outs.insert(g.output.clone(), (vec![], Some(OutputNode::Gate(i))));
         }
     }
+    // resolve internal latch inputs and outputs
+    for (i, (lin, lout, _)) in internal_latches.iter().enumerate() {
+        if let Some((wi, _)) = wire_in_outs.get_mut(lin) {
+            wi.push(InputNode::Latch);
+        } else {
+            wire_in_outs.insert(lin.clone(), (vec![InputNode::Latch], None));
+        }
+        if let Some((_, wo)) = wire_in_outs.get_mut(lout) {
+            if wo.is_some() {
+                return Err(BLIFError::AlreadyDefinedAsOutput2(
+                    model_name.to_string(),
+                    lout.clone(),
+                ));
+            } else {
+                *wo = Some(OutputNode::Latch(i));
+            }
+        } else {
+            wire_in_outs.insert(lout.clone(), (vec![], Some(OutputNode::Latch(i))));
+        }
+    }
 
     let mut sc_mappings = Vec::<SubcircuitMapping>::new();
     for (i, sc) in model.subcircuits.iter().enumerate() {
         if let Some(subc_model) = model_map.get(&sc.model) {
-            if subc_model.circuit.as_ref().unwrap().1.iter().any(|c| {
-                matches!(
-                    c,
-                    CircuitMapping::Input(true) | CircuitMapping::Output(true)
-                )
-            }) {
+            let sc_port_len =
+                subc_model.inputs.len() + subc_model.clocks.len() + subc_model.outputs.len();
+            // only latches tied to model inputs and outputs are not allowed
+            if subc_model.circuit.as_ref().unwrap().1[0..sc_port_len]
+                .iter()
+                .any(|c| {
+                    matches!(
+                        c,
+                        CircuitMapping::Input(true) | CircuitMapping::Output(true)
+                    )
+                })
+            {
                 return Err(BLIFError::ModelHaveLatches(
                     sc.filename.to_string(),
                     sc.line_no,
@@ -954,6 +1970,17 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
             ));
         }
     }
+    // internal states: internal latches and states of subcircuits
+    let states = internal_latches
+        .iter()
+        .map(|(_, lout, init)| (lout.clone(), *init))
+        .chain(model.subcircuits.iter().enumerate().flat_map(|(j, sc)| {
+            model_map[&sc.model]
+                .states
+                .iter()
+                .map(move |(name, init)| (format!("{}#{}/{}", sc.model, j, name), *init))
+        }))
+        .collect::<Vec<_>>();
     // check whether name tied to some output
     for (name, (wi, wo)) in &wire_in_outs {
         if wo.is_some() && wi.is_empty() && !model_output_set.contains(name) {
@@ -973,12 +2000,41 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
         // path_visited - to detect cycles
         let mut path_visited = HashSet::<String>::new();
         let mut stack = vec![];
-        for outname in &model.outputs {
-            if let Some((wi, wo)) = wire_in_outs.get(outname) {
+        // variables of internal latch outputs
+        let latch_vars = (0..internal_latches.len())
+            .map(|_| BoolVarSys::var())
+            .collect::<Vec<_>>();
+        // states and next states of subcircuits - set after evaluating subcircuit
+        let mut sc_states = vec![None::<(Vec<BoolVarSys>, Vec<BoolVarSys>)>; sc_mappings.len()];
+        // roots: model outputs, internal latch inputs and subcircuits with states
+        // (name of subcircuit root contains '#' that can't be in BLIF names).
+        let roots = model
+            .outputs
+            .iter()
+            .chain(internal_latches.iter().map(|(lin, _, _)| lin))
+            .map(|name| (name.clone(), None))
+            .chain(
+                model
+                    .subcircuits
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, sc)| !model_map[&sc.model].states.is_empty())
+                    .map(|(j, _)| (format!("#{}", j), Some(j))),
+            )
+            .collect::<Vec<_>>();
+        for (outname, sc_root) in &roots {
+            if let Some(j) = sc_root {
+                stack.push(StackEntry {
+                    name: outname.clone(),
+                    node: Node::Subcircuit(*j, 0),
+                    way: 0,
+                });
+            } else if let Some((wi, wo)) = wire_in_outs.get(outname) {
                 let node = if let Some(wo) = wo {
                     match wo {
                         OutputNode::Gate(g) => Node::Gate(*g),
                         OutputNode::Subcircuit(sc, sco) => Node::Subcircuit(*sc, *sco),
+                        OutputNode::Latch(l) => Node::Latch(*l),
                     }
                 } else if !wi.is_empty() {
                     wi.iter()
@@ -1008,7 +2064,7 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                 let top = stack.last_mut().unwrap();
                 let way = top.way;
                 let way_num = match top.node {
-                    Node::ModelInput(_) | Node::ModelClock(_) => 0,
+                    Node::ModelInput(_) | Node::ModelClock(_) | Node::Latch(_) => 0,
                     Node::Gate(j) => model.gates[j].params.len(),
                     Node::Subcircuit(j, _) => sc_mappings[j].inputs.len(),
                 };
@@ -1019,6 +2075,7 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                     Node::Subcircuit(j, k) => {
                         sc_mappings[j].outputs[k].clone().unwrap_or(String::new())
                     }
+                    Node::Latch(j) => internal_latches[j].1.clone(),
                 };
                 if way == 0 {
                     if !path_visited.contains(&top.name) {
@@ -1050,6 +2107,7 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                                 match wo {
                                     OutputNode::Gate(g) => Node::Gate(*g),
                                     OutputNode::Subcircuit(sc, sco) => Node::Subcircuit(*sc, *sco),
+                                    OutputNode::Latch(l) => Node::Latch(*l),
                                 }
                             } else if !wi.is_empty() {
                                 wi.iter()
@@ -1083,6 +2141,11 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                                 boolvar_map.insert(name.clone(), BoolVarSys::var());
                             }
                         }
+                        Node::Latch(j) => {
+                            if !boolvar_map.contains_key(&name) {
+                                boolvar_map.insert(name.clone(), latch_vars[j].clone());
+                            }
+                        }
                         Node::Gate(j) => {
                             // add gate's circuit to expressions and resolve gate output.
                             if !boolvar_map.contains_key(&name) {
@@ -1111,11 +2174,17 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                         Node::Subcircuit(j, _) => {
                             // generate subcircuit's circuit to expressions
                             // and resolve subcircuit outputs.
-                            if !boolvar_map.contains_key(&name) {
+                            if sc_states[j].is_none() {
                                 let sc_mapping = &sc_mappings[j];
                                 let subc_model = &model_map[&model.subcircuits[j].model];
                                 let circuit_mapping = &subc_model.circuit.as_ref().unwrap().1;
                                 let total_input_len = subc_model.inputs.len();
+                                let sc_output_start = total_input_len + subc_model.clocks.len();
+                                let sc_port_len = sc_output_start + subc_model.outputs.len();
+                                let sc_state_len = subc_model.states.len();
+                                let state_vars = (0..sc_state_len)
+                                    .map(|_| BoolVarSys::var())
+                                    .collect::<Vec<_>>();
                                 // add outputs to expressions
                                 let circ_outputs = BoolVarSys::from_circuit(
                                     subc_model.circuit.as_ref().unwrap().0.clone(),
@@ -1133,13 +2202,28 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                                                     Some(BoolVarSys::from(false))
                                                 }
                                             } else {
-                                                Some(BoolVarSys::from(false))
+                                                None
                                             }
-                                        }),
+                                        })
+                                        .chain(
+                                            circuit_mapping
+                                                [sc_port_len..sc_port_len + sc_state_len]
+                                                .iter()
+                                                .zip(state_vars.iter())
+                                                .filter_map(|(p, v)| {
+                                                    if matches!(p, CircuitMapping::Input(_)) {
+                                                        Some(v.clone())
+                                                    } else {
+                                                        None
+                                                    }
+                                                }),
+                                        ),
                                 );
                                 let mut out_count = 0;
                                 // add subcircuit outputs to boolvar_map expression
-                                for (i, c) in circuit_mapping[total_input_len..].iter().enumerate()
+                                for (i, c) in circuit_mapping[sc_output_start..sc_port_len]
+                                    .iter()
+                                    .enumerate()
                                 {
                                     match c {
                                         CircuitMapping::Value(v) => {
@@ -1161,6 +2245,20 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                                         _ => (),
                                     }
                                 }
+                                // next states of subcircuit
+                                let state_nexts = circuit_mapping[sc_port_len + sc_state_len..]
+                                    .iter()
+                                    .map(|c| {
+                                        if let CircuitMapping::Value(v) = c {
+                                            BoolVarSys::from(*v)
+                                        } else {
+                                            let old_out_count = out_count;
+                                            out_count += 1;
+                                            circ_outputs[old_out_count].clone()
+                                        }
+                                    })
+                                    .collect::<Vec<_>>();
+                                sc_states[j] = Some((state_vars, state_nexts));
                             }
                         }
                     };
@@ -1170,11 +2268,41 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
             }
         }
         // generate circuit
-        let latch_inputs =
-            HashSet::<String>::from_iter(model.latches.iter().map(|(s, _)| s.clone()));
-        let latch_outputs =
-            HashSet::<String>::from_iter(model.latches.iter().map(|(_, s)| s.clone()));
-        let outputs = UDynVarSys::from_iter(model.outputs.iter().map(|s| boolvar_map[s].clone()));
+        // latches tied to model inputs and outputs
+        let io_latches = model
+            .latches
+            .iter()
+            .filter(|(lin, lout)| model_input_set.contains(lout) && model_output_set.contains(lin));
+        let latch_inputs = HashSet::<String>::from_iter(io_latches.clone().map(|(s, _)| s.clone()));
+        let latch_outputs = HashSet::<String>::from_iter(io_latches.map(|(_, s)| s.clone()));
+        // internal states and next states
+        let (state_vars, state_nexts): (Vec<_>, Vec<_>) = latch_vars
+            .into_iter()
+            .zip(
+                internal_latches
+                    .iter()
+                    .map(|(lin, _, _)| boolvar_map[lin].clone()),
+            )
+            .chain(
+                sc_states
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|(vars, nexts)| vars.into_iter().zip(nexts)),
+            )
+            .unzip();
+        let outputs = UDynVarSys::from_iter(
+            model
+                .outputs
+                .iter()
+                .map(|s| boolvar_map[s].clone())
+                .chain(state_nexts.iter().cloned()),
+        );
+        let port_input_len = model
+            .inputs
+            .iter()
+            .chain(model.clocks.iter())
+            .filter(|s| boolvar_map.contains_key(*s))
+            .count();
         let (circuit, input_map) = outputs.to_translated_circuit_with_map(
             model
                 .inputs
@@ -1185,8 +2313,10 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                         .clocks
                         .iter()
                         .filter_map(|s| boolvar_map.get(s).cloned()),
-                ),
+                )
+                .chain(state_vars),
         );
+        let state_input_map = input_map[port_input_len..].to_vec();
         // fix input map - because some model inputs can be removed while filtering
         let input_map = {
             let mut input_map_new = vec![None; model.inputs.len() + model.clocks.len()];
@@ -1241,27 +2371,89 @@ fn gen_model_circuit(model_name: &str, model_map: &mut ModelMap) -> Result<(), B
                     }),
             )
             .chain(circuit_out_mapping.into_iter())
+            .chain(state_input_map.into_iter().map(|opti| {
+                if opti.is_some() {
+                    CircuitMapping::Input(true)
+                } else {
+                    CircuitMapping::NoMapping
+                }
+            }))
+            .chain(state_nexts.into_iter().map(|b| {
+                if let Some(v) = b.value() {
+                    CircuitMapping::Value(v)
+                } else {
+                    CircuitMapping::Output(true)
+                }
+            }))
             .collect::<Vec<_>>();
         Ok((circuit, circuit_mapping))
     })?;
     let model = model_map.get_mut(model_name).unwrap();
+    model.states = states;
     model.circuit = Some((circuit, circuit_mapping));
     Ok(())
 }
 
-fn parse_file<P: AsRef<Path> + Debug>(path: P) -> Result<(ModelMap, String), BLIFError> {
+/// Resolver of BLIF `.search` includes.
+///
+/// It opens a file given in `.search` directive. `including` is name of file that contains
+/// `.search` directive, `path` is argument of `.search` directive. Method returns name of
+/// resolved file (used in error messages and to resolve nested includes) and reader.
+pub trait BLIFSearchResolver {
+    /// Type of reader of resolved file.
+    type Reader: Read;
+    /// Resolves `.search` include.
+    fn resolve(&mut self, including: &str, path: &str) -> io::Result<(String, Self::Reader)>;
+}
+
+/// Default resolver of BLIF `.search` includes.
+///
+/// It opens files from filesystem. Paths are relative to directory of including file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BLIFFileResolver;
+
+impl BLIFSearchResolver for BLIFFileResolver {
+    type Reader = File;
+    fn resolve(&mut self, including: &str, path: &str) -> io::Result<(String, File)> {
+        let path = if let Some(parent) = Path::new(including).parent() {
+            parent.join(path)
+        } else {
+            Path::new(path).to_path_buf()
+        };
+        let file = File::open(&path)?;
+        Ok((path.to_string_lossy().to_string(), file))
+    }
+}
+
+fn parse_file<P: AsRef<Path> + Debug>(
+    path: P,
+    options: ModelParseOptions,
+) -> Result<(ModelMap, String), BLIFError> {
+    let name = path.as_ref().to_string_lossy().to_string();
+    parse_reader(&name, File::open(path)?, &mut BLIFFileResolver, options)
+}
+
+fn parse_reader<'a, R: Read + 'a, SR: BLIFSearchResolver>(
+    name: &str,
+    input: R,
+    resolver: &mut SR,
+    options: ModelParseOptions,
+) -> Result<(ModelMap, String), BLIFError>
+where
+    SR::Reader: 'a,
+{
     let mut circuit_cache = CircuitCache::new();
     let mut gate_cache = GateCache::new();
     let mut model_map = ModelMap::new();
-    struct Stack {
+    struct Stack<'a> {
         path: String,
-        reader: BLIFTokensReader<File>,
+        reader: BLIFTokensReader<Box<dyn Read + 'a>>,
     }
     let mut stack = vec![];
     let mut first_model = None;
     stack.push(Stack {
-        path: path.as_ref().to_str().unwrap().to_string(),
-        reader: BLIFTokensReader::<File>::new(File::open(path)?),
+        path: name.to_string(),
+        reader: BLIFTokensReader::new(Box::new(input) as Box<dyn Read + 'a>),
     });
 
     'a: while !stack.is_empty() {
@@ -1271,9 +2463,10 @@ fn parse_file<P: AsRef<Path> + Debug>(path: P) -> Result<(ModelMap, String), BLI
         let top = stack.last_mut().unwrap();
         while let Some((_, line)) = top.reader.read_tokens()? {
             if line[0] == ".search" {
+                let (path, reader) = resolver.resolve(&top.path, &line[1])?;
                 stack.push(Stack {
-                    path: line[1].clone(),
-                    reader: BLIFTokensReader::<File>::new(File::open(&line[1])?),
+                    path,
+                    reader: BLIFTokensReader::new(Box::new(reader) as Box<dyn Read + 'a>),
                 });
                 continue 'a; // to main loop at stack
             } else {
@@ -1284,6 +2477,7 @@ fn parse_file<P: AsRef<Path> + Debug>(path: P) -> Result<(ModelMap, String), BLI
                     &mut top.reader,
                     &mut circuit_cache,
                     &mut gate_cache,
+                    options,
                 )?;
                 if first_model.is_none() {
                     first_model = Some(name.clone());
@@ -1363,15 +2557,118 @@ fn resolve_model(top_name: &str, model_map: &mut ModelMap) -> Result<(), BLIFErr
 ///
 /// Argument is path to file with logic in BLIF format. Function returns Gate circuit with its
 /// mapping. Mapping in form: key - original variable in AIGER logic,
-/// value - assignment in circuit.
+/// value - assignment in circuit. Files given in `.search` directives are opened relative to
+/// directory of including file.
+///
+/// Latches whose output is model input and input is model output are states of the circuit.
+/// Other latches (internal latches, also in subcircuits) are also states that are placed after
+/// them. The circuit inputs are organized in form: `[state,clocks,inputs]` and the circuit
+/// outputs in form: `[state,outputs]`. Internal latches are in mapping by name of latch output.
+/// Latch in subcircuit has name in form `model#index/name`, where `model` is subcircuit
+/// model name, `index` is index of subcircuit in parent model.
 pub fn from_blif<P: AsRef<Path> + Debug>(
     path: P,
 ) -> Result<(Circuit<usize>, Vec<(String, AssignEntry)>), BLIFError> {
-    let (mut model_map, model_name) = parse_file(path)?;
+    let (mut model_map, model_name) = parse_file(path, ModelParseOptions::default())?;
     resolve_model(&model_name, &mut model_map)?;
     Ok(model_map.remove(&model_name).unwrap().top_mapping())
 }
 
+/// Converts logic in BLIF from to Gate circuit with additional information.
+///
+/// Function works like `from_blif`, but it also returns additional information:
+/// initial values of latches of top model, states of finite state machine and whether
+/// external don't care network was ignored.
+pub fn from_blif_with_info<P: AsRef<Path> + Debug>(
+    path: P,
+) -> Result<(Circuit<usize>, BLIFMap, BLIFInfo), BLIFError> {
+    from_blif_with_options(path, &BLIFReadOptions::default())
+}
+
+/// Converts logic in BLIF from to Gate circuit with options.
+///
+/// Function works like `from_blif_with_info`, but it also uses `options`. If cell library
+/// is given then `.gate` and `.mlatch` are expanded by using functions of library cells.
+/// Latches from `.mlatch` are internal latches. External don't care network is read
+/// if `exdc_outputs` is true and don't care outputs are placed after model outputs.
+pub fn from_blif_with_options<P: AsRef<Path> + Debug>(
+    path: P,
+    options: &BLIFReadOptions,
+) -> Result<(Circuit<usize>, BLIFMap, BLIFInfo), BLIFError> {
+    let cells = options.library.as_ref().map(gen_cell_map);
+    let (model_map, model_name) = parse_file(
+        path,
+        ModelParseOptions {
+            cells: cells.as_ref(),
+            exdc_outputs: options.exdc_outputs,
+            fsm_encoding: options.fsm_encoding,
+        },
+    )?;
+    top_model_with_info(model_map, &model_name)
+}
+
+// resolve top model and convert it to Gate circuit with mapping and additional information.
+fn top_model_with_info(
+    mut model_map: ModelMap,
+    model_name: &str,
+) -> Result<(Circuit<usize>, BLIFMap, BLIFInfo), BLIFError> {
+    resolve_model(model_name, &mut model_map)?;
+    let model = model_map.remove(model_name).unwrap();
+    // first latches tied to model inputs and outputs, next internal states
+    let info = BLIFInfo {
+        latch_inits: model
+            .latches
+            .iter()
+            .zip(model.latch_inits.iter())
+            .filter(|((lin, lout), _)| model.inputs.contains(lout) && model.outputs.contains(lin))
+            .map(|(_, init)| *init)
+            .chain(model.states.iter().map(|(_, init)| *init))
+            .collect(),
+        fsm_states: model.fsm_states.clone(),
+        exdc_ignored: model.exdc_ignored,
+    };
+    let (circuit, mapping) = model.top_mapping();
+    Ok((circuit, mapping, info))
+}
+
+/// Converts logic in BLIF from reader to Gate circuit.
+///
+/// Function works like `from_blif`, but it reads BLIF logic from `input`. `name` is name
+/// of input used in error messages and passed to `resolver` as name of including file.
+/// `resolver` opens files given in `.search` directives.
+pub fn from_blif_reader<R: Read, SR: BLIFSearchResolver>(
+    name: &str,
+    input: R,
+    resolver: &mut SR,
+) -> Result<(Circuit<usize>, BLIFMap), BLIFError> {
+    from_blif_reader_with_options(name, input, resolver, &BLIFReadOptions::default())
+        .map(|(circuit, mapping, _)| (circuit, mapping))
+}
+
+/// Converts logic in BLIF from reader to Gate circuit with options.
+///
+/// Function works like `from_blif_reader`, but it also uses `options` and returns
+/// additional information like `from_blif_with_options`.
+pub fn from_blif_reader_with_options<R: Read, SR: BLIFSearchResolver>(
+    name: &str,
+    input: R,
+    resolver: &mut SR,
+    options: &BLIFReadOptions,
+) -> Result<(Circuit<usize>, BLIFMap, BLIFInfo), BLIFError> {
+    let cells = options.library.as_ref().map(gen_cell_map);
+    let (model_map, model_name) = parse_reader(
+        name,
+        input,
+        resolver,
+        ModelParseOptions {
+            cells: cells.as_ref(),
+            exdc_outputs: options.exdc_outputs,
+            fsm_encoding: options.fsm_encoding,
+        },
+    )?;
+    top_model_with_info(model_map, &model_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1457,8 +2754,14 @@ c   # ‘\’ here only to demonstrate its use
         let mut circuit_cache = CircuitCache::new();
         let mut gate_cache = GateCache::new();
         let mut bytes = BLIFTokensReader::new(text.as_bytes());
-        parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-            .map_err(|e| e.to_string())
+        parse_model(
+            "top.blif",
+            &mut bytes,
+            &mut circuit_cache,
+            &mut gate_cache,
+            ModelParseOptions::default(),
+        )
+        .map_err(|e| e.to_string())
     }
 
     fn strs_to_vec_string<'a>(iter: impl IntoIterator<Item = &'a str>) -> Vec<String> {
@@ -1481,6 +2784,7 @@ c   # ‘\’ here only to demonstrate its use
                     inputs: vec![],
                     outputs: strs_to_vec_string(["x", "y", "z"]),
                     latches: vec![],
+                    latch_inits: vec![],
                     clocks: vec![],
                     gates: vec![
                         Gate {
@@ -1500,6 +2804,9 @@ c   # ‘\’ here only to demonstrate its use
                         }
                     ],
                     subcircuits: vec![],
+                    states: vec![],
+                    fsm_states: vec![],
+                    exdc_ignored: false,
                     circuit: None,
                 }
             )),
@@ -1523,6 +2830,7 @@ c   # ‘\’ here only to demonstrate its use
                     inputs: strs_to_vec_string(["a", "b", "c", "d", "e", "f", "i"]),
                     outputs: strs_to_vec_string(["x", "y", "z", "w", "t", "t1", "x1", "z1"]),
                     latches: strs2_to_vec_string([("x", "a"), ("y", "c")]),
+                    latch_inits: vec![None, None],
                     clocks: strs_to_vec_string(["g", "h"]),
                     gates: vec![
                         Gate {
@@ -1586,6 +2894,9 @@ nor(0,4) xor(3,4) nimpl(7,5) nor(6,8):0}(4)"##
                         filename: "top.blif".to_string(),
                         line_no: 33,
                     }],
+                    states: vec![],
+                    fsm_states: vec![],
+                    exdc_ignored: false,
                     circuit: None,
                 }
             )),
@@ -2007,16 +3318,26 @@ x1 1
         let mut gate_cache = GateCache::new();
         let mut model_map = ModelMap::new();
         let mut bytes = BLIFTokensReader::new(text.as_bytes());
-        let (main_model_name, main_model) =
-            parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-                .map_err(|e| e.to_string())
-                .unwrap();
+        let (main_model_name, main_model) = parse_model(
+            "top.blif",
+            &mut bytes,
+            &mut circuit_cache,
+            &mut gate_cache,
+            ModelParseOptions::default(),
+        )
+        .map_err(|e| e.to_string())
+        .unwrap();
         model_map.insert(main_model_name.clone(), main_model);
         for _ in 0..model_num {
-            let (model_name, model) =
-                parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-                    .map_err(|e| e.to_string())
-                    .unwrap();
+            let (model_name, model) = parse_model(
+                "top.blif",
+                &mut bytes,
+                &mut circuit_cache,
+                &mut gate_cache,
+                ModelParseOptions::default(),
+            )
+            .map_err(|e| e.to_string())
+            .unwrap();
             model_map.insert(model_name.clone(), model);
             gen_model_circuit(&model_name, &mut model_map).map_err(|e| e.to_string())?;
         }
@@ -3005,10 +4326,15 @@ and(10,13) and(16,30):2 nor(21,28) and(24,32):3}(8)"##
         let mut gate_cache = GateCache::new();
         let mut model_map = ModelMap::new();
         let mut bytes = BLIFTokensReader::new(text.as_bytes());
-        let (main_model_name, main_model) =
-            parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-                .map_err(|e| e.to_string())
-                .unwrap();
+        let (main_model_name, main_model) = parse_model(
+            "top.blif",
+            &mut bytes,
+            &mut circuit_cache,
+            &mut gate_cache,
+            ModelParseOptions::default(),
+        )
+        .map_err(|e| e.to_string())
+        .unwrap();
         model_map.insert(main_model_name.clone(), main_model);
         gen_model_circuit(&main_model_name, &mut model_map)
             .map_err(|e| e.to_string())
@@ -3406,7 +4732,7 @@ and(1,2):0 and(2,5):6}(6)"##
 
     fn parse_file_helper(files: impl IntoIterator<Item = (String, String)>) -> (ModelMap, String) {
         let to_remove = write_files(files);
-        parse_file(&to_remove.0[0]).unwrap()
+        parse_file(&to_remove.0[0], ModelParseOptions::default()).unwrap()
     }
 
     #[test]
@@ -3419,6 +4745,7 @@ and(1,2):0 and(2,5):6}(6)"##
                         inputs: vec![],
                         outputs: strs_to_vec_string(["x", "y", "z"]),
                         latches: vec![],
+                        latch_inits: vec![],
                         clocks: vec![],
                         gates: vec![
                             Gate {
@@ -3438,6 +4765,9 @@ and(1,2):0 and(2,5):6}(6)"##
                             }
                         ],
                         subcircuits: vec![],
+                        states: vec![],
+                        fsm_states: vec![],
+                        exdc_ignored: false,
                         circuit: None,
                     }
                 )]),
@@ -3465,6 +4795,7 @@ and(1,2):0 and(2,5):6}(6)"##
                         inputs: strs_to_vec_string(["a", "b"]),
                         outputs: strs_to_vec_string(["x", "y", "z"]),
                         latches: vec![],
+                        latch_inits: vec![],
                         clocks: vec![],
                         gates: vec![],
                         subcircuits: vec![
@@ -3499,6 +4830,9 @@ and(1,2):0 and(2,5):6}(6)"##
                                 line_no: 6,
                             },
                         ],
+                        states: vec![],
+                        fsm_states: vec![],
+                        exdc_ignored: false,
                         circuit: None,
                     },
                 ),
@@ -3508,6 +4842,7 @@ and(1,2):0 and(2,5):6}(6)"##
                         inputs: strs_to_vec_string(["a0", "a1"]),
                         outputs: strs_to_vec_string(["x"]),
                         latches: vec![],
+                        latch_inits: vec![],
                         clocks: vec![],
                         gates: vec![Gate {
                             params: strs_to_vec_string(["a0", "a1"]),
@@ -3518,6 +4853,9 @@ and(1,2):0 and(2,5):6}(6)"##
                             )),
                         }],
                         subcircuits: vec![],
+                        states: vec![],
+                        fsm_states: vec![],
+                        exdc_ignored: false,
                         circuit: None,
                     },
                 ),
@@ -3527,6 +4865,7 @@ and(1,2):0 and(2,5):6}(6)"##
                         inputs: strs_to_vec_string(["a0", "a1"]),
                         outputs: strs_to_vec_string(["x"]),
                         latches: vec![],
+                        latch_inits: vec![],
                         clocks: vec![],
                         gates: vec![Gate {
                             params: strs_to_vec_string(["a0", "a1"]),
@@ -3537,6 +4876,9 @@ and(1,2):0 and(2,5):6}(6)"##
                             )),
                         }],
                         subcircuits: vec![],
+                        states: vec![],
+                        fsm_states: vec![],
+                        exdc_ignored: false,
                         circuit: None,
                     },
                 ),
@@ -3546,6 +4888,7 @@ and(1,2):0 and(2,5):6}(6)"##
                         inputs: strs_to_vec_string(["a0", "a1"]),
                         outputs: strs_to_vec_string(["x"]),
                         latches: vec![],
+                        latch_inits: vec![],
                         clocks: vec![],
                         gates: vec![Gate {
                             params: strs_to_vec_string(["a0", "a1"]),
@@ -3556,6 +4899,9 @@ and(1,2):0 and(2,5):6}(6)"##
                             )),
                         }],
                         subcircuits: vec![],
+                        states: vec![],
+                        fsm_states: vec![],
+                        exdc_ignored: false,
                         circuit: None,
                     },
                 ),
@@ -3794,16 +5140,26 @@ and(1,2):0 and(2,5):6}(6)"##
         let mut gate_cache = GateCache::new();
         let mut model_map = ModelMap::new();
         let mut bytes = BLIFTokensReader::new(text.as_bytes());
-        let (main_model_name, main_model) =
-            parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-                .map_err(|e| e.to_string())
-                .unwrap();
+        let (main_model_name, main_model) = parse_model(
+            "top.blif",
+            &mut bytes,
+            &mut circuit_cache,
+            &mut gate_cache,
+            ModelParseOptions::default(),
+        )
+        .map_err(|e| e.to_string())
+        .unwrap();
         model_map.insert(main_model_name.clone(), main_model);
         for _ in 0..model_num {
-            let (model_name, model) =
-                parse_model("top.blif", &mut bytes, &mut circuit_cache, &mut gate_cache)
-                    .map_err(|e| e.to_string())
-                    .unwrap();
+            let (model_name, model) = parse_model(
+                "top.blif",
+                &mut bytes,
+                &mut circuit_cache,
+                &mut gate_cache,
+                ModelParseOptions::default(),
+            )
+            .map_err(|e| e.to_string())
+            .unwrap();
             model_map.insert(model_name.clone(), model);
         }
         resolve_model(&main_model_name, &mut model_map).map_err(|e| e.to_string())?;
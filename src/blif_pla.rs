@@ -272,6 +272,87 @@ fn pla_to_truth_table(
     out_table
 }
 
+// Generates minimized PLA cover of true values of truth table by using Quine-McCluskey
+// method with greedy covering. Bit of index of table - value of variable.
+pub(crate) fn truth_table_to_pla(var_num: usize, table: &[bool]) -> Vec<Vec<PLACell>> {
+    assert_eq!(1 << var_num, table.len());
+    let minterms = (0..table.len()).filter(|i| table[*i]).collect::<Vec<_>>();
+    // cubes in form: (values, mask of unknowns). values of unknowns are zeroes.
+    let mut cubes = minterms.iter().map(|t| (*t, 0usize)).collect::<Vec<_>>();
+    let mut primes = vec![];
+    while !cubes.is_empty() {
+        let mut merged = vec![false; cubes.len()];
+        let mut next_cubes = vec![];
+        for i in 0..cubes.len() {
+            for j in i + 1..cubes.len() {
+                let ((v0, m0), (v1, m1)) = (cubes[i], cubes[j]);
+                if m0 == m1 && (v0 ^ v1).count_ones() == 1 {
+                    next_cubes.push((v0 & v1, m0 | (v0 ^ v1)));
+                    merged[i] = true;
+                    merged[j] = true;
+                }
+            }
+        }
+        primes.extend(
+            cubes
+                .iter()
+                .zip(merged.iter())
+                .filter(|(_, m)| !**m)
+                .map(|(c, _)| *c),
+        );
+        next_cubes.sort();
+        next_cubes.dedup();
+        cubes = next_cubes;
+    }
+    let covers = |(v, m): (usize, usize), t: usize| (t & !m) == v;
+    let mut cover = vec![];
+    // essential primes
+    for t in &minterms {
+        let mut covering = primes.iter().filter(|p| covers(**p, *t));
+        if let (Some(p), None) = (covering.next(), covering.next()) {
+            if !cover.contains(p) {
+                cover.push(*p);
+            }
+        }
+    }
+    let mut uncovered = minterms
+        .into_iter()
+        .filter(|t| !cover.iter().any(|p| covers(*p, *t)))
+        .collect::<Vec<_>>();
+    // greedy covering: choose prime that covers most uncovered minterms.
+    while !uncovered.is_empty() {
+        let best = primes
+            .iter()
+            .max_by_key(|p| {
+                (
+                    uncovered.iter().filter(|t| covers(**p, **t)).count(),
+                    p.1.count_ones(),
+                )
+            })
+            .copied()
+            .unwrap();
+        cover.push(best);
+        uncovered.retain(|t| !covers(best, *t));
+    }
+    cover.sort();
+    cover
+        .into_iter()
+        .map(|(v, m)| {
+            (0..var_num)
+                .map(|b| {
+                    if ((m >> b) & 1) != 0 {
+                        PLACell::Unknown
+                    } else if ((v >> b) & 1) != 0 {
+                        PLACell::One
+                    } else {
+                        PLACell::Zero
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
 fn gen_pla_table_circuit(
     var_num: usize,
     set_value: bool,
@@ -398,6 +479,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_truth_table_to_pla() {
+        use PLACell::*;
+        assert_eq!(
+            Vec::<Vec<PLACell>>::new(),
+            truth_table_to_pla(2, &[false, false, false, false])
+        );
+        assert_eq!(
+            vec![vec![Unknown, Unknown]],
+            truth_table_to_pla(2, &[true, true, true, true])
+        );
+        // a | b
+        assert_eq!(
+            vec![vec![One, Unknown], vec![Unknown, One]],
+            truth_table_to_pla(2, &[false, true, true, true])
+        );
+        // majority
+        assert_eq!(
+            vec![
+                vec![One, One, Unknown],
+                vec![One, Unknown, One],
+                vec![Unknown, One, One]
+            ],
+            truth_table_to_pla(3, &[false, false, false, true, false, true, true, true])
+        );
+        // check tables for all 4-input functions (step by 97).
+        for value in (0..1u32 << 16).step_by(97) {
+            let table = (0..16).map(|i| ((value >> i) & 1) != 0).collect::<Vec<_>>();
+            let pla = truth_table_to_pla(4, &table)
+                .into_iter()
+                .map(|entry| (entry, true, 0))
+                .collect::<Vec<_>>();
+            assert_eq!(table, pla_to_truth_table(4, true, &pla), "{}", value);
+        }
+    }
+
     fn str_to_vecbool(s: &str) -> Vec<bool> {
         s.chars()
             .filter(|x| *x == '0' || *x == '1')
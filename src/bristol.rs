@@ -0,0 +1,354 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to conversion between Gate circuit and the Bristol Fashion circuit format.
+//!
+//! Bristol Fashion format is used by secure computation (MPC and FHE) frameworks.
+//! Circuit is described by header with number of gates and wires, numbers of input and
+//! output values and their lengths, and list of gates in form
+//! `in_num out_num in_wires... out_wires... OP`. Supported operations are: `AND`, `XOR`,
+//! `INV`, `EQ`, `EQW` and `MAND`. Inputs are first wires and outputs are last wires.
+
+use crate::gatesim::*;
+use crate::AssignEntry;
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+use crate::vbinopcircuit::*;
+use crate::vcircuit::VGateFunc;
+use crate::vexpr::*;
+use crate::VNegs::*;
+
+/// Additional information of Bristol Fashion circuit.
+///
+/// Lengths of input and output values. If vector of lengths is empty then all inputs
+/// (or outputs) are in one value. Sum of lengths must be equal to number of circuit inputs
+/// (or outputs).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BristolInfo {
+    /// Lengths of input values.
+    pub input_values: Vec<usize>,
+    /// Lengths of output values.
+    pub output_values: Vec<usize>,
+}
+
+// Bristol gates with temporary wires. Wires of inputs are not changed.
+struct BristolGates {
+    // input wires, output wire and operation
+    gates: Vec<(Vec<usize>, usize, &'static str)>,
+    wire_count: usize,
+    // cached inversions of wires
+    invs: HashMap<usize, usize>,
+}
+
+impl BristolGates {
+    fn push(&mut self, inputs: Vec<usize>, op: &'static str) -> usize {
+        let output = self.wire_count;
+        self.gates.push((inputs, output, op));
+        self.wire_count += 1;
+        output
+    }
+
+    // get wire with value of circuit wire (negated if neg is true)
+    fn wire(&mut self, wires: &[(usize, bool)], w: usize, neg: bool) -> usize {
+        let (bw, bneg) = wires[w];
+        if bneg == neg {
+            bw
+        } else if let Some(inv) = self.invs.get(&bw) {
+            *inv
+        } else {
+            let inv = self.push(vec![bw], "INV");
+            self.invs.insert(bw, inv);
+            inv
+        }
+    }
+}
+
+/// Converts circuit to Bristol Fashion format.
+///
+/// Function writes Gate circuit in Bristol Fashion format to `out`. `circuit` is circuit
+/// to convert. All inputs and outputs are written as one input value and one output value.
+/// Negations are optimized while conversion and gates are written as `AND`, `XOR`,
+/// `INV` and `EQW` (for outputs that are inputs or duplicates of other outputs).
+pub fn to_bristol(circuit: &Circuit<usize>, out: impl Write) -> io::Result<()> {
+    to_bristol_with_info(circuit, &BristolInfo::default(), out)
+}
+
+/// Converts circuit to Bristol Fashion format with additional information.
+///
+/// Function works like `to_bristol`, but it also splits inputs and outputs into values
+/// given in `info`.
+pub fn to_bristol_with_info(
+    circuit: &Circuit<usize>,
+    info: &BristolInfo,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let input_len = circuit.input_len();
+    let output_len = circuit.outputs().len();
+    let values = |values: &[usize], len: usize| {
+        if values.is_empty() && len != 0 {
+            vec![len]
+        } else {
+            assert_eq!(values.iter().sum::<usize>(), len);
+            values.to_vec()
+        }
+    };
+    let input_values = values(&info.input_values, input_len);
+    let output_values = values(&info.output_values, output_len);
+
+    let circuit = {
+        let mut circuit = VBinOpCircuit::from(circuit.clone());
+        circuit.optimize_negs();
+        circuit
+    };
+
+    let mut bgates = BristolGates {
+        gates: vec![],
+        wire_count: input_len,
+        invs: HashMap::new(),
+    };
+    // wire and its negation for every circuit wire
+    let mut wires = (0..input_len).map(|i| (i, false)).collect::<Vec<_>>();
+    for (g, n) in &circuit.gates {
+        let neg_i1 = *n == NegInput1;
+        let neg_out = *n == NegOutput;
+        let (op, i0, i1, neg) = match g.func {
+            VGateFunc::And => (
+                "AND",
+                bgates.wire(&wires, g.i0, false),
+                bgates.wire(&wires, g.i1, neg_i1),
+                neg_out,
+            ),
+            // a | b = !(!a & !b)
+            VGateFunc::Or => (
+                "AND",
+                bgates.wire(&wires, g.i0, true),
+                bgates.wire(&wires, g.i1, !neg_i1),
+                !neg_out,
+            ),
+            VGateFunc::Xor => {
+                let (bw0, bneg0) = wires[g.i0];
+                let (bw1, bneg1) = wires[g.i1];
+                ("XOR", bw0, bw1, bneg0 ^ bneg1 ^ neg_i1 ^ neg_out)
+            }
+            _ => {
+                panic!("Unexpected!");
+            }
+        };
+        wires.push((bgates.push(vec![i0, i1], op), neg));
+    }
+    // outputs - output wires are last wires
+    let mut output_wires = HashMap::new();
+    for (oi, (o, n)) in circuit.outputs.iter().enumerate() {
+        let bw = bgates.wire(&wires, *o, *n);
+        if bw >= input_len && !output_wires.contains_key(&bw) {
+            output_wires.insert(bw, oi);
+        } else {
+            // output is input or duplicate of other output
+            output_wires.insert(bgates.push(vec![bw], "EQW"), oi);
+        }
+    }
+    // renumber wires
+    let wire_count = bgates.wire_count;
+    let output_start = wire_count - output_len;
+    let mut next_wire = input_len;
+    let wire_map = (0..wire_count)
+        .map(|w| {
+            if w < input_len {
+                w
+            } else if let Some(oi) = output_wires.get(&w) {
+                output_start + oi
+            } else {
+                next_wire += 1;
+                next_wire - 1
+            }
+        })
+        .collect::<Vec<_>>();
+
+    writeln!(out, "{} {}", bgates.gates.len(), wire_count)?;
+    for values in [input_values, output_values] {
+        write!(out, "{}", values.len())?;
+        for v in values {
+            write!(out, " {}", v)?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out)?;
+    for (inputs, output, op) in bgates.gates {
+        write!(out, "{} 1", inputs.len())?;
+        for i in inputs {
+            write!(out, " {}", wire_map[i])?;
+        }
+        writeln!(out, " {} {}", wire_map[output], op)?;
+    }
+    Ok(())
+}
+
+/// Bristol error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum BristolError {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unknown gate.
+    #[error("{0}: Unknown gate {1}")]
+    UnknownGate(usize, String),
+    /// If wrong number of arguments of gate.
+    #[error("{0}: Wrong number of arguments of {1}")]
+    WrongArgNum(usize, String),
+    /// If wire index is out of range.
+    #[error("{0}: Wire {1} out of range")]
+    WireOutOfRange(usize, usize),
+    /// If wire is already driven.
+    #[error("{0}: Wire {1} already driven")]
+    WireAlreadyDriven(usize, usize),
+    /// If wire is driven by nothing.
+    #[error("Wire {0} is undriven")]
+    UndrivenWire(usize),
+    /// If circuit have cycle.
+    #[error("Cycle in circuit caused by {0}")]
+    CycleInCircuit(usize),
+    /// If unexpected end of header.
+    #[error("Unexpected end of header")]
+    UnexpectedEnd,
+    /// If number of gates doesn't match to number in header.
+    #[error("Wrong number of gates: expected {0}, got {1}")]
+    WrongGateNum(usize, usize),
+    /// If number of inputs or outputs is greater than number of wires.
+    #[error("Too many inputs and outputs")]
+    TooManyInputsOutputs,
+}
+
+// mapping: key - Bristol wire index, value - assignment in circuit
+type BristolMap = Vec<(usize, AssignEntry)>;
+
+/// Converts Bristol Fashion circuit to Gate circuit.
+///
+/// `input` is read stream with Bristol Fashion circuit. Supported operations are:
+/// `AND`, `XOR`, `INV`, `EQ` (assignment of constant), `EQW` (assignment of wire) and
+/// `MAND` (multiple AND gates).
+///
+/// The circuit inputs are input wires and the circuit outputs are output wires
+/// (in order of values). Function returns Gate circuit with its mapping.
+/// Mapping in form: key - Bristol input wire or output wire, value - assignment in circuit.
+pub fn from_bristol(input: impl Read) -> Result<(Circuit<usize>, BristolMap), BristolError> {
+    from_bristol_with_info(input).map(|(circuit, map, _)| (circuit, map))
+}
+
+/// Converts Bristol Fashion circuit to Gate circuit with additional information.
+///
+/// Function works like `from_bristol`, but it also returns lengths of input and output
+/// values.
+pub fn from_bristol_with_info(
+    input: impl Read,
+) -> Result<(Circuit<usize>, BristolMap, BristolInfo), BristolError> {
+    let mut lines = BufReader::new(input)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| line.map(|line| (i + 1, line)))
+        .filter(|r| r.as_ref().map_or(true, |(_, line)| !line.trim().is_empty()));
+    let mut parse_numbers = |count: Option<usize>| -> Result<Vec<usize>, BristolError> {
+        let (line_no, line) = lines.next().ok_or(BristolError::UnexpectedEnd)??;
+        let numbers = line
+            .split_whitespace()
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| BristolError::SyntaxError(line_no, line.clone()))?;
+        // line with values: number of values and their lengths
+        if numbers.len() != count.unwrap_or_else(|| numbers.first().map_or(1, |x| x + 1)) {
+            return Err(BristolError::SyntaxError(line_no, line));
+        }
+        Ok(numbers)
+    };
+    let header = parse_numbers(Some(2))?;
+    let (gate_num, wire_num) = (header[0], header[1]);
+    let info = BristolInfo {
+        input_values: parse_numbers(None)?[1..].to_vec(),
+        output_values: parse_numbers(None)?[1..].to_vec(),
+    };
+    let input_len = info.input_values.iter().sum::<usize>();
+    let output_len = info.output_values.iter().sum::<usize>();
+    if input_len + output_len > wire_num {
+        return Err(BristolError::TooManyInputsOutputs);
+    }
+
+    let mut drivers = HashMap::new();
+    let mut driven = (0..input_len).collect::<HashSet<_>>();
+    let mut gate_count = 0;
+    for line in lines {
+        let (line_no, line) = line?;
+        let syntax_error = || BristolError::SyntaxError(line_no, line.trim().to_string());
+        let mut tokens = line.split_whitespace().collect::<Vec<_>>();
+        let op = tokens.pop().unwrap();
+        let wires = tokens
+            .into_iter()
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| syntax_error())?;
+        if wires.len() < 2 || wires.len() != 2 + wires[0] + wires[1] {
+            return Err(syntax_error());
+        }
+        let (in_num, out_num) = (wires[0], wires[1]);
+        let (ins, outs) = wires[2..].split_at(in_num);
+        let arg_num_ok = match op {
+            "AND" | "XOR" => in_num == 2 && out_num == 1,
+            "INV" | "EQ" | "EQW" => in_num == 1 && out_num == 1,
+            "MAND" => in_num == 2 * out_num,
+            _ => {
+                return Err(BristolError::UnknownGate(line_no, op.to_string()));
+            }
+        };
+        if !arg_num_ok {
+            return Err(BristolError::WrongArgNum(line_no, op.to_string()));
+        }
+        let wire_error = |w: usize| BristolError::WireOutOfRange(line_no, w);
+        // input of EQ is constant value
+        if op == "EQ" {
+            if ins[0] > 1 {
+                return Err(syntax_error());
+            }
+        } else if let Some(w) = ins.iter().find(|w| **w >= wire_num) {
+            return Err(wire_error(*w));
+        }
+        let net = |w: usize| VExpr::Net(w.to_string());
+        for (i, w) in outs.iter().enumerate() {
+            if *w >= wire_num {
+                return Err(wire_error(*w));
+            }
+            if !driven.insert(*w) {
+                return Err(BristolError::WireAlreadyDriven(line_no, *w));
+            }
+            let expr = match op {
+                "AND" | "MAND" => VExpr::And(vec![net(ins[i]), net(ins[out_num + i])]),
+                "XOR" => VExpr::Xor(vec![net(ins[0]), net(ins[1])]),
+                "INV" => VExpr::Not(Box::new(net(ins[0]))),
+                "EQ" => VExpr::Value(ins[0] != 0),
+                _ => net(ins[0]),
+            };
+            drivers.insert(w.to_string(), expr);
+        }
+        gate_count += 1;
+    }
+    if gate_count != gate_num {
+        return Err(BristolError::WrongGateNum(gate_num, gate_count));
+    }
+    let (circuit, map) = vexprs_to_circuit(
+        (0..input_len).map(|w| w.to_string()).collect(),
+        (wire_num - output_len..wire_num)
+            .map(|w| w.to_string())
+            .collect(),
+        &drivers,
+    )
+    .map_err(|e| match e {
+        VNetError::UndrivenNet(name) => BristolError::UndrivenWire(name.parse().unwrap()),
+        VNetError::Cycle(name) => BristolError::CycleInCircuit(name.parse().unwrap()),
+    })?;
+    let map = map
+        .into_iter()
+        .map(|(name, a)| (name.parse().unwrap(), a))
+        .collect::<Vec<_>>();
+    Ok((circuit, map, info))
+}
@@ -2,12 +2,23 @@
 //! Module to conversion between Gate circuit and the BTOR2 logic format.
 
 use crate::gatesim::*;
+use crate::AssignEntry;
+use gategen::boolvar::*;
+use gategen::dynintvar::*;
 
 use std::collections::HashMap;
-use std::io::{self, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 
 use crate::vcircuit::*;
 
+/// Options for conversion to BTOR2 format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BTOR2Options {
+    /// Initial values of states: None - uninitialized. If vector is shorter than state then
+    /// rest of states are uninitialized.
+    pub state_inits: Vec<Option<bool>>,
+}
+
 /// Converts circuit to BTOR2 format.
 ///
 /// Function writes Gate circuit logic in BTOR2 format to `out`. `circuit` is circuit
@@ -16,10 +27,24 @@ use crate::vcircuit::*;
 /// The circuit inputs are organized in form: `[state,inputs]`.
 /// The circuit outputs are organized in form: `[state,outputs]`.
 pub fn to_btor2(circuit: Circuit<usize>, state_len: usize, out: impl Write) -> io::Result<()> {
+    to_btor2_with_options(circuit, state_len, &BTOR2Options::default(), out)
+}
+
+/// Converts circuit to BTOR2 format with options.
+///
+/// Function works like `to_btor2`, but it also uses `options`. Initial values of states
+/// are written as `init` lines.
+pub fn to_btor2_with_options(
+    circuit: Circuit<usize>,
+    state_len: usize,
+    options: &BTOR2Options,
+    out: impl Write,
+) -> io::Result<()> {
     let input_len = circuit.input_len();
     let output_len = circuit.outputs().len();
     assert!(state_len <= input_len);
     assert!(state_len <= output_len);
+    assert!(options.state_inits.len() <= state_len);
     let circuit = VCircuit::to_op_and_ximpl_circuit(circuit, false);
 
     let mut out = BufWriter::new(out);
@@ -85,5 +110,744 @@ pub fn to_btor2(circuit: Circuit<usize>, state_len: usize, out: impl Write) -> i
         }
         index += 1;
     }
+    // write initial values of states
+    let mut consts = [None, None];
+    for (i, init) in options.state_inits.iter().enumerate() {
+        if let Some(v) = init {
+            let c = if let Some(c) = consts[usize::from(*v)] {
+                c
+            } else {
+                writeln!(out, "{} {} 1", index, if *v { "one" } else { "zero" })?;
+                consts[usize::from(*v)] = Some(index);
+                index += 1;
+                index - 1
+            };
+            writeln!(out, "{} init 1 {} {}", index, i + 2, c)?;
+            index += 1;
+        }
+    }
     Ok(())
 }
+
+/// BTOR2 error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum BTOR2Error {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error")]
+    SyntaxError(usize),
+    /// If node identifier is bad.
+    #[error("{0}: Bad node identifier")]
+    BadNodeId(usize),
+    /// If node already defined.
+    #[error("{0}: Node {1} already defined")]
+    NodeAlreadyDefined(usize, usize),
+    /// If node is not defined or is not value.
+    #[error("{0}: Unknown node {1}")]
+    UnknownNode(usize, usize),
+    /// If sort is not defined.
+    #[error("{0}: Unknown sort {1}")]
+    UnknownSort(usize, usize),
+    /// If array sort is used.
+    #[error("{0}: Unsupported array sort")]
+    UnsupportedArraySort(usize),
+    /// If operator is unknown.
+    #[error("{0}: Unknown operator {1}")]
+    UnknownOperator(usize, String),
+    /// If sorts of arguments or result don't match.
+    #[error("{0}: Sort mismatch")]
+    SortMismatch(usize),
+    /// If constant is bad.
+    #[error("{0}: Bad constant")]
+    BadConstant(usize),
+    /// If node is not state.
+    #[error("{0}: Node {1} is not state")]
+    NotState(usize, usize),
+    /// If next state already defined.
+    #[error("{0}: Next state for {1} already defined")]
+    NextAlreadyDefined(usize, usize),
+    /// If initial state already defined.
+    #[error("{0}: Initial state for {1} already defined")]
+    InitAlreadyDefined(usize, usize),
+    /// If initial state is not constant.
+    #[error("{0}: Initial state is not constant")]
+    NonConstantInit(usize),
+}
+
+type BitVec = Vec<BoolVarSys>;
+// mapping: key - (node id, bit index), value - assignment in circuit
+type BTOR2Map = Vec<((usize, usize), AssignEntry)>;
+// circuit, mapping and initial values of state
+type BTOR2Result = (Circuit<usize>, BTOR2Map, Vec<Option<bool>>);
+
+fn bv_const(width: usize, v: bool) -> BitVec {
+    vec![BoolVarSys::from(v); width]
+}
+
+fn bv_not(a: &[BoolVarSys]) -> BitVec {
+    a.iter().map(|x| !x).collect()
+}
+
+fn bv_ite(c: &BoolVarSys, a: &[BoolVarSys], b: &[BoolVarSys]) -> BitVec {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| bool_opt_ite(c.clone(), x.clone(), y.clone()))
+        .collect()
+}
+
+// returns sum and carry
+fn bv_add(a: &[BoolVarSys], b: &[BoolVarSys], carry: BoolVarSys) -> (BitVec, BoolVarSys) {
+    let mut c = carry;
+    let mut out = Vec::with_capacity(a.len());
+    for (x, y) in a.iter().zip(b.iter()) {
+        let (s, nc) = opt_full_adder(x.clone(), y.clone(), c);
+        out.push(s);
+        c = nc;
+    }
+    (out, c)
+}
+
+// returns difference and carry (true if no borrow)
+fn bv_sub(a: &[BoolVarSys], b: &[BoolVarSys]) -> (BitVec, BoolVarSys) {
+    bv_add(a, &bv_not(b), BoolVarSys::from(true))
+}
+
+fn bv_neg(a: &[BoolVarSys]) -> BitVec {
+    bv_sub(&bv_const(a.len(), false), a).0
+}
+
+fn bv_eq(a: &[BoolVarSys], b: &[BoolVarSys]) -> BoolVarSys {
+    a.iter()
+        .zip(b.iter())
+        .fold(BoolVarSys::from(true), |r, (x, y)| {
+            r & x.clone().bequal(y.clone())
+        })
+}
+
+fn bv_ult(a: &[BoolVarSys], b: &[BoolVarSys]) -> BoolVarSys {
+    !bv_sub(a, b).1
+}
+
+fn bv_slt(a: &[BoolVarSys], b: &[BoolVarSys]) -> BoolVarSys {
+    // flip sign bits and compare as unsigned
+    let w = a.len();
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a[w - 1] = !&a[w - 1];
+    b[w - 1] = !&b[w - 1];
+    bv_ult(&a, &b)
+}
+
+fn bv_mul(a: &[BoolVarSys], b: &[BoolVarSys]) -> BitVec {
+    let w = a.len();
+    let mut acc = bv_const(w, false);
+    for (i, bi) in b.iter().enumerate() {
+        let partial = (0..w)
+            .map(|j| {
+                if j >= i {
+                    &a[j - i] & bi
+                } else {
+                    BoolVarSys::from(false)
+                }
+            })
+            .collect::<Vec<_>>();
+        acc = bv_add(&acc, &partial, BoolVarSys::from(false)).0;
+    }
+    acc
+}
+
+fn bv_extend(a: &[BoolVarSys], width: usize, signed: bool) -> BitVec {
+    let fill = if signed {
+        a[a.len() - 1].clone()
+    } else {
+        BoolVarSys::from(false)
+    };
+    a.iter()
+        .cloned()
+        .chain((a.len()..width).map(|_| fill.clone()))
+        .collect()
+}
+
+// restoring division. For zero divisor, quotient is all ones and remainder is dividend.
+fn bv_udivrem(a: &[BoolVarSys], b: &[BoolVarSys]) -> (BitVec, BitVec) {
+    let w = a.len();
+    let b_ext = bv_extend(b, w + 1, false);
+    let mut q = bv_const(w, false);
+    let mut r = bv_const(w, false);
+    for i in (0..w).rev() {
+        let r_ext = std::iter::once(a[i].clone())
+            .chain(r.iter().cloned())
+            .collect::<Vec<_>>();
+        let (diff, no_borrow) = bv_sub(&r_ext, &b_ext);
+        r = bv_ite(&no_borrow, &diff[0..w], &r_ext[0..w]);
+        q[i] = no_borrow;
+    }
+    (q, r)
+}
+
+fn bv_abs(a: &[BoolVarSys]) -> BitVec {
+    bv_ite(&a[a.len() - 1], &bv_neg(a), a)
+}
+
+fn bv_shift(a: &[BoolVarSys], b: &[BoolVarSys], left: bool, fill: &BoolVarSys) -> BitVec {
+    let w = a.len();
+    let mut r = a.to_vec();
+    let mut overflow = BoolVarSys::from(false);
+    for (k, bk) in b.iter().enumerate() {
+        if k < (usize::BITS - 1) as usize && (1usize << k) < w {
+            let s = 1usize << k;
+            let shifted = (0..w)
+                .map(|i| {
+                    if left {
+                        if i >= s {
+                            r[i - s].clone()
+                        } else {
+                            BoolVarSys::from(false)
+                        }
+                    } else if i + s < w {
+                        r[i + s].clone()
+                    } else {
+                        fill.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
+            r = bv_ite(bk, &shifted, &r);
+        } else {
+            overflow |= bk.clone();
+        }
+    }
+    let fill = if left {
+        BoolVarSys::from(false)
+    } else {
+        fill.clone()
+    };
+    r.into_iter()
+        .map(|x| bool_opt_ite(overflow.clone(), fill.clone(), x))
+        .collect()
+}
+
+fn bv_rotate(a: &[BoolVarSys], b: &[BoolVarSys], left: bool) -> BitVec {
+    let w = a.len();
+    // amount modulo width
+    let wconst = (0..b.len())
+        .map(|i| BoolVarSys::from(i < (usize::BITS as usize) && ((w >> i) & 1) != 0))
+        .collect::<Vec<_>>();
+    let amount = if w.is_power_of_two() {
+        b.to_vec()
+    } else {
+        bv_udivrem(b, &wconst).1
+    };
+    let mut r = a.to_vec();
+    for (k, bk) in amount.iter().enumerate() {
+        if k >= (usize::BITS - 1) as usize || (1usize << k) >= w {
+            break;
+        }
+        let s = (1usize << k) % w;
+        let rotated = (0..w)
+            .map(|i| {
+                if left {
+                    r[(i + w - s) % w].clone()
+                } else {
+                    r[(i + s) % w].clone()
+                }
+            })
+            .collect::<Vec<_>>();
+        r = bv_ite(bk, &rotated, &r);
+    }
+    r
+}
+
+// parse constant in given radix (2, 10 or 16) into bit vector with width.
+fn parse_btor2_const(s: &str, radix: u32, width: usize) -> Option<Vec<bool>> {
+    let (neg, s) = if radix == 10 && s.starts_with('-') {
+        (true, &s[1..])
+    } else {
+        (false, s)
+    };
+    if s.is_empty() {
+        return None;
+    }
+    let mut bits = vec![false; width];
+    for c in s.chars() {
+        let d = c.to_digit(radix)?;
+        // bits = bits * radix + d
+        let mut carry = d as u64;
+        for b in bits.iter_mut() {
+            let v = u64::from(*b) * u64::from(radix) + carry;
+            *b = (v & 1) != 0;
+            carry = v >> 1;
+        }
+        if radix == 2 && carry != 0 {
+            return None;
+        }
+    }
+    if neg {
+        // two's complement
+        let mut carry = true;
+        for b in bits.iter_mut() {
+            let v = !*b;
+            *b = v ^ carry;
+            carry &= v;
+        }
+    }
+    Some(bits)
+}
+
+#[derive(Clone)]
+enum BTOR2Node {
+    Sort(usize),
+    ArraySort,
+    Value(BitVec),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BTOR2InputKind {
+    State,
+    Input,
+}
+
+// return: circuit, map for BTOR2 nodes (states, inputs, nexts, outputs and properties)
+// and initial values of circuit state (only if with_inits is true).
+fn from_btor2_int(lines: &[String], with_inits: bool) -> Result<BTOR2Result, BTOR2Error> {
+    let mut nodes = HashMap::<usize, BTOR2Node>::new();
+    // declared inputs and states: (nid, width, kind)
+    let mut decls = Vec::<(usize, usize, BTOR2InputKind)>::new();
+    let mut decl_vars = Vec::<BitVec>::new();
+    let mut state_map = HashMap::<usize, usize>::new();
+    // nexts: key - state nid, value - (nid of next, value)
+    let mut nexts = HashMap::<usize, (usize, BitVec)>::new();
+    // inits: key - state nid, value - (line number, value)
+    let mut inits = HashMap::<usize, (usize, BitVec)>::new();
+    // outputs and properties: (nid, value)
+    let mut outputs = Vec::<(usize, BitVec)>::new();
+
+    for (line_idx, line) in lines.iter().enumerate() {
+        let line_no = line_idx + 1;
+        let line = if let Some(p) = line.find(';') {
+            &line[0..p]
+        } else {
+            line.as_str()
+        };
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.is_empty() {
+            continue;
+        }
+        if tokens.len() < 2 {
+            return Err(BTOR2Error::SyntaxError(line_no));
+        }
+        let nid = match tokens[0].parse::<usize>() {
+            Ok(nid) if nid != 0 => nid,
+            _ => return Err(BTOR2Error::BadNodeId(line_no)),
+        };
+        if nodes.contains_key(&nid) {
+            return Err(BTOR2Error::NodeAlreadyDefined(line_no, nid));
+        }
+        let op = tokens[1];
+        let arg_num = |i: usize| -> Result<usize, BTOR2Error> {
+            tokens
+                .get(i)
+                .and_then(|s| s.parse::<usize>().ok())
+                .ok_or(BTOR2Error::SyntaxError(line_no))
+        };
+        let sort = |i: usize| -> Result<usize, BTOR2Error> {
+            let sid = arg_num(i)?;
+            match nodes.get(&sid) {
+                Some(BTOR2Node::Sort(w)) => Ok(*w),
+                Some(BTOR2Node::ArraySort) => Err(BTOR2Error::UnsupportedArraySort(line_no)),
+                _ => Err(BTOR2Error::UnknownSort(line_no, sid)),
+            }
+        };
+        // get argument value - negative node identifier means negation
+        let arg = |i: usize| -> Result<BitVec, BTOR2Error> {
+            let v = tokens
+                .get(i)
+                .and_then(|s| s.parse::<isize>().ok())
+                .ok_or(BTOR2Error::SyntaxError(line_no))?;
+            let anid = v.unsigned_abs();
+            match nodes.get(&anid) {
+                Some(BTOR2Node::Value(bv)) => Ok(if v < 0 { bv_not(bv) } else { bv.clone() }),
+                _ => Err(BTOR2Error::UnknownNode(line_no, anid)),
+            }
+        };
+        let node = match op {
+            "sort" => match tokens.get(2).copied() {
+                Some("bitvec") => {
+                    let w = arg_num(3)?;
+                    if w == 0 {
+                        return Err(BTOR2Error::SyntaxError(line_no));
+                    }
+                    BTOR2Node::Sort(w)
+                }
+                Some("array") => BTOR2Node::ArraySort,
+                _ => return Err(BTOR2Error::SyntaxError(line_no)),
+            },
+            "input" | "state" => {
+                let w = sort(2)?;
+                let kind = if op == "state" {
+                    state_map.insert(nid, decls.len());
+                    BTOR2InputKind::State
+                } else {
+                    BTOR2InputKind::Input
+                };
+                let bv = (0..w).map(|_| BoolVarSys::var()).collect::<Vec<_>>();
+                decls.push((nid, w, kind));
+                decl_vars.push(bv.clone());
+                BTOR2Node::Value(bv)
+            }
+            "init" | "next" => {
+                let w = sort(2)?;
+                let snid = arg_num(3)?;
+                let v = arg(4)?;
+                if !state_map.contains_key(&snid) {
+                    return Err(BTOR2Error::NotState(line_no, snid));
+                }
+                if decls[state_map[&snid]].1 != w || v.len() != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                if op == "next" {
+                    if nexts.contains_key(&snid) {
+                        return Err(BTOR2Error::NextAlreadyDefined(line_no, snid));
+                    }
+                    nexts.insert(snid, (nid, v));
+                } else {
+                    if inits.contains_key(&snid) {
+                        return Err(BTOR2Error::InitAlreadyDefined(line_no, snid));
+                    }
+                    // initial values are not part of circuit
+                    inits.insert(snid, (line_no, v));
+                }
+                continue;
+            }
+            "output" | "bad" | "constraint" | "fair" => {
+                outputs.push((nid, arg(2)?));
+                continue;
+            }
+            "justice" => {
+                // arguments are concatenated to get distinct bit indices in mapping
+                let n = arg_num(2)?;
+                let mut bv = vec![];
+                for i in 0..n {
+                    bv.extend(arg(3 + i)?);
+                }
+                outputs.push((nid, bv));
+                continue;
+            }
+            "const" | "constd" | "consth" => {
+                let w = sort(2)?;
+                let radix = match op {
+                    "const" => 2,
+                    "constd" => 10,
+                    _ => 16,
+                };
+                let s = tokens.get(3).ok_or(BTOR2Error::SyntaxError(line_no))?;
+                if radix == 2 && s.len() != w {
+                    return Err(BTOR2Error::BadConstant(line_no));
+                }
+                let bits =
+                    parse_btor2_const(s, radix, w).ok_or(BTOR2Error::BadConstant(line_no))?;
+                BTOR2Node::Value(bits.into_iter().map(BoolVarSys::from).collect())
+            }
+            "zero" | "one" | "ones" => {
+                let w = sort(2)?;
+                let mut bv = bv_const(w, op == "ones");
+                if op == "one" {
+                    bv[0] = BoolVarSys::from(true);
+                }
+                BTOR2Node::Value(bv)
+            }
+            "not" | "inc" | "dec" | "neg" | "redand" | "redor" | "redxor" => {
+                let w = sort(2)?;
+                let a = arg(3)?;
+                let r = match op {
+                    "not" => bv_not(&a),
+                    "inc" => bv_add(&a, &bv_const(a.len(), false), BoolVarSys::from(true)).0,
+                    "dec" => bv_add(&a, &bv_const(a.len(), true), BoolVarSys::from(false)).0,
+                    "neg" => bv_neg(&a),
+                    "redand" => vec![a.iter().fold(BoolVarSys::from(true), |r, x| r & x)],
+                    "redor" => vec![a.iter().fold(BoolVarSys::from(false), |r, x| r | x)],
+                    _ => vec![a.iter().fold(BoolVarSys::from(false), |r, x| r ^ x)],
+                };
+                if r.len() != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(r)
+            }
+            "sext" | "uext" => {
+                let w = sort(2)?;
+                let a = arg(3)?;
+                let ext = arg_num(4)?;
+                if a.len() + ext != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(bv_extend(&a, w, op == "sext"))
+            }
+            "slice" => {
+                let w = sort(2)?;
+                let a = arg(3)?;
+                let upper = arg_num(4)?;
+                let lower = arg_num(5)?;
+                if upper < lower || upper >= a.len() || upper - lower + 1 != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(a[lower..=upper].to_vec())
+            }
+            "ite" => {
+                let w = sort(2)?;
+                let c = arg(3)?;
+                let a = arg(4)?;
+                let b = arg(5)?;
+                if c.len() != 1 || a.len() != w || b.len() != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(bv_ite(&c[0], &a, &b))
+            }
+            "concat" => {
+                let w = sort(2)?;
+                let a = arg(3)?;
+                let b = arg(4)?;
+                if a.len() + b.len() != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(b.into_iter().chain(a).collect())
+            }
+            "iff" | "implies" | "eq" | "neq" | "sgt" | "sgte" | "slt" | "slte" | "ugt" | "ugte"
+            | "ult" | "ulte" | "and" | "nand" | "nor" | "or" | "xnor" | "xor" | "rol" | "ror"
+            | "sll" | "sra" | "srl" | "add" | "mul" | "sdiv" | "udiv" | "smod" | "srem"
+            | "urem" | "sub" | "saddo" | "uaddo" | "sdivo" | "udivo" | "smulo" | "umulo"
+            | "ssubo" | "usubo" => {
+                let w = sort(2)?;
+                let a = arg(3)?;
+                let b = arg(4)?;
+                if a.len() != b.len() {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                let aw = a.len();
+                let bitwise = |f: &dyn Fn(&BoolVarSys, &BoolVarSys) -> BoolVarSys| {
+                    a.iter()
+                        .zip(b.iter())
+                        .map(|(x, y)| f(x, y))
+                        .collect::<Vec<_>>()
+                };
+                let r = match op {
+                    "iff" | "eq" => vec![bv_eq(&a, &b)],
+                    "neq" => vec![!bv_eq(&a, &b)],
+                    "implies" => bitwise(&|x, y| !x | y),
+                    "sgt" => vec![bv_slt(&b, &a)],
+                    "sgte" => vec![!bv_slt(&a, &b)],
+                    "slt" => vec![bv_slt(&a, &b)],
+                    "slte" => vec![!bv_slt(&b, &a)],
+                    "ugt" => vec![bv_ult(&b, &a)],
+                    "ugte" => vec![!bv_ult(&a, &b)],
+                    "ult" => vec![bv_ult(&a, &b)],
+                    "ulte" => vec![!bv_ult(&b, &a)],
+                    "and" => bitwise(&|x, y| x & y),
+                    "nand" => bitwise(&|x, y| !(x & y)),
+                    "nor" => bitwise(&|x, y| !(x | y)),
+                    "or" => bitwise(&|x, y| x | y),
+                    "xnor" => bitwise(&|x, y| !(x ^ y)),
+                    "xor" => bitwise(&|x, y| x ^ y),
+                    "rol" => bv_rotate(&a, &b, true),
+                    "ror" => bv_rotate(&a, &b, false),
+                    "sll" => bv_shift(&a, &b, true, &BoolVarSys::from(false)),
+                    "srl" => bv_shift(&a, &b, false, &BoolVarSys::from(false)),
+                    "sra" => bv_shift(&a, &b, false, &a[aw - 1]),
+                    "add" => bv_add(&a, &b, BoolVarSys::from(false)).0,
+                    "sub" => bv_sub(&a, &b).0,
+                    "mul" => bv_mul(&a, &b),
+                    "udiv" => bv_udivrem(&a, &b).0,
+                    "urem" => bv_udivrem(&a, &b).1,
+                    "sdiv" | "srem" | "smod" => {
+                        let (q, r) = bv_udivrem(&bv_abs(&a), &bv_abs(&b));
+                        let sa = &a[aw - 1];
+                        let sb = &b[aw - 1];
+                        match op {
+                            "sdiv" => bv_ite(&(sa ^ sb), &bv_neg(&q), &q),
+                            "srem" => bv_ite(sa, &bv_neg(&r), &r),
+                            _ => {
+                                // sign of result follows divisor
+                                let r_zero = !r.iter().fold(BoolVarSys::from(false), |x, y| x | y);
+                                let neg_r = bv_neg(&r);
+                                let r_neg_sa = bv_add(&neg_r, &b, BoolVarSys::from(false)).0;
+                                let r_sb = bv_add(&r, &b, BoolVarSys::from(false)).0;
+                                let r_ab = bv_ite(sb, &neg_r, &r_neg_sa);
+                                let r_b = bv_ite(sb, &r_sb, &r);
+                                bv_ite(&r_zero, &r, &bv_ite(sa, &r_ab, &r_b))
+                            }
+                        }
+                    }
+                    "uaddo" => vec![bv_add(&a, &b, BoolVarSys::from(false)).1],
+                    "saddo" => {
+                        let s = bv_add(&a, &b, BoolVarSys::from(false)).0;
+                        vec![
+                            a[aw - 1].clone().bequal(b[aw - 1].clone()) & (&s[aw - 1] ^ &a[aw - 1]),
+                        ]
+                    }
+                    "usubo" => vec![bv_ult(&a, &b)],
+                    "ssubo" => {
+                        let s = bv_sub(&a, &b).0;
+                        vec![(&a[aw - 1] ^ &b[aw - 1]) & (&s[aw - 1] ^ &a[aw - 1])]
+                    }
+                    "udivo" => vec![BoolVarSys::from(false)],
+                    "sdivo" => {
+                        // overflow only for MIN / -1
+                        let min = (0..aw)
+                            .map(|i| BoolVarSys::from(i + 1 == aw))
+                            .collect::<Vec<_>>();
+                        vec![bv_eq(&a, &min) & bv_eq(&b, &bv_const(aw, true))]
+                    }
+                    "umulo" => {
+                        let p =
+                            bv_mul(&bv_extend(&a, 2 * aw, false), &bv_extend(&b, 2 * aw, false));
+                        vec![p[aw..].iter().fold(BoolVarSys::from(false), |x, y| x | y)]
+                    }
+                    _ => {
+                        // smulo: result doesn't fit if high part is not sign extension
+                        let p = bv_mul(&bv_extend(&a, 2 * aw, true), &bv_extend(&b, 2 * aw, true));
+                        let sign = p[aw - 1].clone();
+                        vec![p[aw..]
+                            .iter()
+                            .fold(BoolVarSys::from(false), |x, y| x | (y ^ &sign))]
+                    }
+                };
+                if r.len() != w {
+                    return Err(BTOR2Error::SortMismatch(line_no));
+                }
+                BTOR2Node::Value(r)
+            }
+            _ => {
+                return Err(BTOR2Error::UnknownOperator(line_no, op.to_string()));
+            }
+        };
+        nodes.insert(nid, node);
+    }
+
+    // states without next state are treated as inputs.
+    // circuit inputs: [states with next, other inputs]
+    let input_decls = decls
+        .iter()
+        .zip(decl_vars.iter())
+        .filter(|((nid, _, kind), _)| *kind == BTOR2InputKind::State && nexts.contains_key(nid))
+        .chain(
+            decls
+                .iter()
+                .zip(decl_vars.iter())
+                .filter(|((nid, _, kind), _)| {
+                    *kind == BTOR2InputKind::Input || !nexts.contains_key(nid)
+                }),
+        )
+        .collect::<Vec<_>>();
+    // initial values of states with next state
+    let mut state_inits = vec![];
+    if with_inits {
+        for ((nid, w, _), _) in &input_decls {
+            if !nexts.contains_key(nid) {
+                break;
+            }
+            if let Some((line_no, bv)) = inits.get(nid) {
+                for x in bv {
+                    state_inits.push(Some(
+                        x.value().ok_or(BTOR2Error::NonConstantInit(*line_no))?,
+                    ));
+                }
+            } else {
+                state_inits.extend(std::iter::repeat_n(None, *w));
+            }
+        }
+    }
+    let all_outputs = input_decls
+        .iter()
+        .filter_map(|((nid, _, _), _)| nexts.get(nid).cloned())
+        .chain(outputs)
+        .flat_map(|(nid, bv)| bv.into_iter().enumerate().map(move |(b, x)| (nid, b, x)))
+        .collect::<Vec<_>>();
+    let filtered_outputs = all_outputs
+        .iter()
+        .filter_map(|(_, _, x)| {
+            if x.value().is_none() {
+                Some(x.clone())
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let outint = if !filtered_outputs.is_empty() {
+        UDynVarSys::from_iter(filtered_outputs)
+    } else {
+        UDynVarSys::var(0)
+    };
+    let (circuit, assign_map) = outint.to_translated_circuit_with_map(
+        input_decls
+            .iter()
+            .flat_map(|(_, bv)| bv.iter().cloned())
+            .collect::<Vec<_>>()
+            .into_iter(),
+    );
+    // collect mapping: first are states and inputs
+    let mut btor2_map = input_decls
+        .iter()
+        .flat_map(|((nid, w, _), _)| (0..*w).map(move |b| (*nid, b)))
+        .enumerate()
+        .map(|(i, key)| {
+            (
+                key,
+                if let Some(newidx) = assign_map[i] {
+                    AssignEntry::Var(newidx, false)
+                } else {
+                    AssignEntry::NoMap
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut ocount = 0;
+    for (nid, b, x) in all_outputs {
+        if let Some(v) = x.value() {
+            btor2_map.push(((nid, b), AssignEntry::Value(v)));
+        } else {
+            let (o, n) = circuit.outputs()[ocount];
+            btor2_map.push(((nid, b), AssignEntry::Var(o, n)));
+            ocount += 1;
+        }
+    }
+    Ok((circuit, btor2_map, state_inits))
+}
+
+/// Converts BTOR2 logic to Gate circuit.
+///
+/// `input` is read stream with BTOR2 logic. Function supports bit-vector sorts and
+/// all bit-vector operators (arrays are not supported). Bit-vectors are bit-blasted into
+/// single bits - first bit is lowest bit.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`, where state are next states
+/// and outputs are outputs and properties (`bad`, `constraint`, `fair`, `justice`) in
+/// order of definition. States without next state are treated as inputs. Initial states
+/// are ignored - use `from_btor2_with_options` to get them.
+///
+/// Function returns Gate circuit with its mapping. Mapping in form: key - node id of
+/// BTOR2 state, input, next, output or property and its bit index,
+/// value - assignment in circuit. Arguments of `justice` property are concatenated,
+/// hence bit index of its output is index of bit in that concatenation.
+pub fn from_btor2(input: impl Read) -> Result<(Circuit<usize>, BTOR2Map), BTOR2Error> {
+    let lines = BufReader::new(input)
+        .lines()
+        .collect::<io::Result<Vec<_>>>()?;
+    callsys(|| from_btor2_int(&lines, false)).map(|(circuit, map, _)| (circuit, map))
+}
+
+/// Converts BTOR2 logic to Gate circuit with options.
+///
+/// Function works like `from_btor2`, but it also returns initial values of states
+/// in `state_inits` of options that can be used by `to_btor2_with_options`. Initial values
+/// are given for bits of circuit state (states with next state). Initial values must be
+/// constant, otherwise function returns error.
+pub fn from_btor2_with_options(
+    input: impl Read,
+) -> Result<(Circuit<usize>, BTOR2Map, BTOR2Options), BTOR2Error> {
+    let lines = BufReader::new(input)
+        .lines()
+        .collect::<io::Result<Vec<_>>>()?;
+    let (circuit, map, state_inits) = callsys(|| from_btor2_int(&lines, true))?;
+    Ok((circuit, map, BTOR2Options { state_inits }))
+}
@@ -4,7 +4,8 @@
 use crate::gatesim::*;
 use cnfgen::writer::{CNFError, CNFWriter};
 use flussab_cnf::cnf;
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Read, Write};
 
 fn to_cnf_int(circuit: &Circuit<usize>, out: &mut impl Write) -> Result<(), CNFError> {
     use cnfgen::boolvar::*;
@@ -41,12 +42,171 @@ fn to_cnf_int(circuit: &Circuit<usize>, out: &mut impl Write) -> Result<(), CNFE
 
 /// Converts Gate circuit to DIMACS CNF (Conjuctive Normal Form) format.
 ///
-/// `circuit` is circuit to convert. `out` is an output stream.
+/// `circuit` is circuit to convert. `out` is an output stream. Circuit must have
+/// only one output. To convert circuit with many outputs use `to_cnf_with_options`.
 pub fn to_cnf(circuit: &Circuit<usize>, mut out: impl Write) -> Result<(), CNFError> {
     use cnfgen::boolvar::*;
     callsys(|| to_cnf_int(circuit, &mut out))
 }
 
+/// Mode of circuit outputs in CNF formula.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CNFOutputMode {
+    /// All outputs are constrained to be true - formula is satisfied only if all
+    /// outputs are true.
+    #[default]
+    Constrained,
+    /// Outputs are free - formula only defines circuit wires and it is satisfied for
+    /// any assignment of circuit inputs.
+    Free,
+}
+
+/// Options for conversion from Gate circuit to CNF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CNFOptions {
+    /// Mode of circuit outputs.
+    pub output_mode: CNFOutputMode,
+    /// If true then XOR gates are written as XOR clauses.
+    pub xor_clauses: bool,
+}
+
+// write clause or XOR clause (if xor is true) with sorted literals
+fn write_cnf_clause(out: &mut impl Write, xor: bool, lits: &[isize]) -> io::Result<()> {
+    let mut lits = lits.to_vec();
+    lits.sort_by_key(|l| (l.unsigned_abs(), *l));
+    if xor {
+        out.write_all(b"x")?;
+    } else {
+        lits.dedup();
+    }
+    for l in lits {
+        write!(out, "{} ", l)?;
+    }
+    out.write_all(b"0\n")
+}
+
+/// Converts Gate circuit to DIMACS CNF (Conjuctive Normal Form) format with options.
+///
+/// `circuit` is circuit to convert. `options` are conversion options. `out` is an output
+/// stream. Circuit can have any number of outputs. Function uses Tseitin encoding: every
+/// circuit input and every gate output gets own variable. If output mode is `Constrained`
+/// then every circuit output is asserted by unit clause, if output mode is `Free` then
+/// outputs are left free.
+///
+/// If `xor_clauses` is set then XOR gates are written as XOR clauses (extended DIMACS
+/// format used by CryptoMiniSat: `x` lines). XOR chains are joined into single
+/// XOR clause and inner gates of chains don't get variables.
+///
+/// Function returns mapping in form: index - circuit wire index, value - variable in CNF
+/// logic (starts from 1) or None if wire doesn't have variable.
+pub fn to_cnf_with_options(
+    circuit: &Circuit<usize>,
+    options: CNFOptions,
+    out: impl Write,
+) -> Result<Vec<Option<usize>>, CNFError> {
+    let input_len = circuit.input_len();
+    let gates = circuit.gates();
+    let wire_num = input_len + gates.len();
+    // find inner XOR gates of XOR chains: XOR gates used only once by other XOR gate.
+    let mut merged = vec![false; wire_num];
+    if options.xor_clauses {
+        let mut fanouts = vec![0; wire_num];
+        for g in gates {
+            fanouts[g.i0] += 1;
+            fanouts[g.i1] += 1;
+        }
+        for (o, _) in circuit.outputs() {
+            fanouts[*o] += 2;
+        }
+        for g in gates.iter().filter(|g| g.func == GateFunc::Xor) {
+            for i in [g.i0, g.i1] {
+                if i >= input_len && gates[i - input_len].func == GateFunc::Xor && fanouts[i] == 1 {
+                    merged[i] = true;
+                }
+            }
+        }
+    }
+    // variables: circuit inputs first, after that gate outputs
+    let mut var_count = 0;
+    let wire_map = merged
+        .iter()
+        .map(|m| {
+            if *m {
+                None
+            } else {
+                var_count += 1;
+                Some(var_count)
+            }
+        })
+        .collect::<Vec<_>>();
+    let lit = |wire: usize, neg: bool| -> isize {
+        let var = isize::try_from(wire_map[wire].unwrap()).unwrap();
+        if neg {
+            -var
+        } else {
+            var
+        }
+    };
+    // clauses: (is XOR clause, literals)
+    let mut clauses = vec![];
+    for (i, g) in gates.iter().enumerate() {
+        if merged[input_len + i] {
+            continue;
+        }
+        let o = lit(input_len + i, false);
+        let (i0, i1) = (g.i0, g.i1);
+        // Tseitin encoding of gate
+        let (a, b) = match g.func {
+            GateFunc::And => (lit(i0, false), lit(i1, false)),
+            GateFunc::Nor => (lit(i0, true), lit(i1, true)),
+            GateFunc::Nimpl => (lit(i0, false), lit(i1, true)),
+            GateFunc::Xor if options.xor_clauses => {
+                // collect leaves of XOR chain, same leaves cancel each other
+                let mut leaves = vec![];
+                let mut stack = vec![i0, i1];
+                while let Some(w) = stack.pop() {
+                    if merged[w] {
+                        let mg = &gates[w - input_len];
+                        stack.extend([mg.i0, mg.i1]);
+                    } else if let Some(p) = leaves.iter().position(|l| *l == w) {
+                        leaves.swap_remove(p);
+                    } else {
+                        leaves.push(w);
+                    }
+                }
+                // o = XOR(leaves) <=> !o ^ XOR(leaves) = 1
+                let mut lits = vec![-o];
+                lits.extend(leaves.into_iter().map(|w| lit(w, false)));
+                clauses.push((true, lits));
+                continue;
+            }
+            GateFunc::Xor => {
+                let (a, b) = (lit(i0, false), lit(i1, false));
+                clauses.push((false, vec![-o, a, b]));
+                clauses.push((false, vec![-o, -a, -b]));
+                clauses.push((false, vec![o, -a, b]));
+                clauses.push((false, vec![o, a, -b]));
+                continue;
+            }
+        };
+        clauses.push((false, vec![-o, a]));
+        clauses.push((false, vec![-o, b]));
+        clauses.push((false, vec![o, -a, -b]));
+    }
+    if options.output_mode == CNFOutputMode::Constrained {
+        for (o, n) in circuit.outputs() {
+            clauses.push((false, vec![lit(*o, *n)]));
+        }
+    }
+    let mut out = BufWriter::new(out);
+    writeln!(out, "p cnf {} {}", var_count, clauses.len())?;
+    for (xor, lits) in clauses {
+        write_cnf_clause(&mut out, xor, &lits)?;
+    }
+    out.flush()?;
+    Ok(wire_map)
+}
+
 fn from_cnf_int(
     parser: &mut cnf::Parser<isize>,
 ) -> Result<(Circuit<usize>, Vec<Option<usize>>), flussab_cnf::ParseError> {
@@ -94,3 +254,573 @@ pub fn from_cnf(
     let mut parser = cnf::Parser::<isize>::from_read(input, cnf::Config::default())?;
     callsys(|| from_cnf_int(&mut parser))
 }
+
+/// Options for conversion from CNF to Gate circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CNFReadOptions {
+    /// If true then gate definitions (Tseitin encoding of AND, OR, XOR and ITE) are
+    /// recovered from clauses and converted to gates.
+    pub recover_gates: bool,
+    /// If true then XOR clauses (extended DIMACS format used by CryptoMiniSat: `x` lines)
+    /// are accepted.
+    pub xor_clauses: bool,
+}
+
+/// Mapping entry of CNF variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CNFVarEntry {
+    /// Variable is circuit input. It holds circuit wire index or None if variable is unused.
+    Input(Option<usize>),
+    /// Variable is output of gate recovered from its definition.
+    GateOutput,
+}
+
+// gate definition of CNF variable - literals starts from 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum CNFGateDef {
+    // conjunction of literals, negated if true
+    And(Vec<isize>, bool),
+    // XOR of variables, negated if true
+    Xor(Vec<isize>, bool),
+    // if-then-else: condition, then, else
+    Ite(isize, isize, isize),
+}
+
+impl CNFGateDef {
+    fn inputs(&self) -> Vec<usize> {
+        match self {
+            CNFGateDef::And(lits, _) | CNFGateDef::Xor(lits, _) => {
+                lits.iter().map(|l| l.unsigned_abs()).collect()
+            }
+            CNFGateDef::Ite(c, t, e) => vec![c.unsigned_abs(), t.unsigned_abs(), e.unsigned_abs()],
+        }
+    }
+}
+
+// max number of XOR inputs in recovered gate
+const MAX_XOR_INPUTS: usize = 4;
+
+struct CNFGateFinder {
+    clauses: Vec<Vec<isize>>,
+    used: Vec<bool>,
+    // key - sorted clause, value - clause index
+    clause_map: HashMap<Vec<isize>, usize>,
+    // key - literal, value - clause indices
+    occurs: HashMap<isize, Vec<usize>>,
+    // XOR clauses: XOR of variables is equal to value
+    xor_clauses: Vec<(Vec<usize>, bool)>,
+    xor_used: Vec<bool>,
+    // key - variable, value - XOR clause indices
+    xor_occurs: HashMap<usize, Vec<usize>>,
+    defs: Vec<Option<CNFGateDef>>,
+    // visit stamps for depends_on: variable is visited if its stamp is equal to epoch
+    visited: Vec<u32>,
+    epoch: u32,
+}
+
+impl CNFGateFinder {
+    fn new(
+        var_count: usize,
+        clauses: Vec<Vec<isize>>,
+        xor_clauses: Vec<(Vec<usize>, bool)>,
+    ) -> Self {
+        let mut clause_map = HashMap::new();
+        let mut occurs = HashMap::<isize, Vec<usize>>::new();
+        for (i, c) in clauses.iter().enumerate() {
+            clause_map.entry(c.clone()).or_insert(i);
+            for l in c {
+                occurs.entry(*l).or_default().push(i);
+            }
+        }
+        let mut xor_occurs = HashMap::<usize, Vec<usize>>::new();
+        for (i, (vars, _)) in xor_clauses.iter().enumerate() {
+            for v in vars {
+                xor_occurs.entry(*v).or_default().push(i);
+            }
+        }
+        Self {
+            used: vec![false; clauses.len()],
+            clauses,
+            clause_map,
+            occurs,
+            xor_used: vec![false; xor_clauses.len()],
+            xor_clauses,
+            xor_occurs,
+            defs: vec![None; var_count + 1],
+            visited: vec![0; var_count + 1],
+            epoch: 0,
+        }
+    }
+
+    // returns index of unused clause with these literals
+    fn find_clause(&self, lits: &[isize]) -> Option<usize> {
+        let mut lits = lits.to_vec();
+        lits.sort_by_key(|l| (l.unsigned_abs(), *l));
+        self.clause_map
+            .get(&lits)
+            .copied()
+            .filter(|ci| !self.used[*ci])
+    }
+
+    // returns true if definition of any variable from `vars` depends on variable `out`.
+    fn depends_on(&mut self, vars: Vec<usize>, out: usize) -> bool {
+        // new epoch clears visited variables
+        self.epoch = self.epoch.wrapping_add(1);
+        if self.epoch == 0 {
+            self.visited.fill(0);
+            self.epoch = 1;
+        }
+        let mut stack = vars;
+        while let Some(v) = stack.pop() {
+            if v == out {
+                return true;
+            }
+            if self.visited[v] != self.epoch {
+                self.visited[v] = self.epoch;
+                if let Some(def) = &self.defs[v] {
+                    stack.extend(def.inputs());
+                }
+            }
+        }
+        false
+    }
+
+    fn try_define(&mut self, out: usize, def: CNFGateDef, def_clauses: Vec<usize>) -> bool {
+        if self.depends_on(def.inputs(), out) {
+            return false;
+        }
+        for ci in def_clauses {
+            self.used[ci] = true;
+        }
+        self.defs[out] = Some(def);
+        true
+    }
+
+    fn occurs(&self, lit: isize) -> Vec<usize> {
+        self.occurs
+            .get(&lit)
+            .map(|c| c.iter().copied().filter(|ci| !self.used[*ci]).collect())
+            .unwrap_or_default()
+    }
+
+    // y = AND(l0,...): clauses (-y l0) (-y l1) ... and (y -l0 -l1 ...)
+    fn find_and(&mut self, out: usize) -> bool {
+        let o = isize::try_from(out).unwrap();
+        for y in [o, -o] {
+            for ci in self.occurs(y) {
+                let lits = self.clauses[ci]
+                    .iter()
+                    .filter(|l| **l != y)
+                    .map(|l| -*l)
+                    .collect::<Vec<_>>();
+                if lits.is_empty() {
+                    continue;
+                }
+                let mut def_clauses = vec![ci];
+                for l in &lits {
+                    if let Some(bci) = self.find_clause(&[-y, *l]) {
+                        def_clauses.push(bci);
+                    } else {
+                        break;
+                    }
+                }
+                if def_clauses.len() == lits.len() + 1
+                    && self.try_define(out, CNFGateDef::And(lits, y < 0), def_clauses)
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // XOR(vars) = c - all clauses with parity of negations same as parity of c
+    fn find_xor(&mut self, out: usize) -> bool {
+        let o = isize::try_from(out).unwrap();
+        for ci in self.occurs(o).into_iter().chain(self.occurs(-o)) {
+            let clause = &self.clauses[ci];
+            if clause.len() < 3 || clause.len() > MAX_XOR_INPUTS + 1 {
+                continue;
+            }
+            let neg_parity = clause.iter().filter(|l| **l < 0).count() & 1;
+            let vars = clause
+                .iter()
+                .map(|l| isize::try_from(l.unsigned_abs()).unwrap())
+                .collect::<Vec<_>>();
+            let mut def_clauses = vec![];
+            for comb in 0usize..(1 << vars.len()) {
+                if (comb.count_ones() as usize & 1) == neg_parity {
+                    let lits = vars
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| if (comb >> i) & 1 != 0 { -*v } else { *v })
+                        .collect::<Vec<_>>();
+                    if let Some(xci) = self.find_clause(&lits) {
+                        def_clauses.push(xci);
+                    } else {
+                        break;
+                    }
+                }
+            }
+            if def_clauses.len() == 1 << (vars.len() - 1) {
+                let inputs = vars.into_iter().filter(|v| *v != o).collect::<Vec<_>>();
+                if self.try_define(out, CNFGateDef::Xor(inputs, neg_parity == 0), def_clauses) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // o = ITE(c, t, e): clauses (-c t -o) (-c -t o) (c e -o) (c -e o)
+    fn find_ite(&mut self, out: usize) -> bool {
+        let o = isize::try_from(out).unwrap();
+        for ci in self.occurs(-o) {
+            let clause = &self.clauses[ci];
+            if clause.len() != 3 {
+                continue;
+            }
+            let others = clause
+                .iter()
+                .copied()
+                .filter(|l| *l != -o)
+                .collect::<Vec<_>>();
+            for (c, t) in [(-others[0], others[1]), (-others[1], others[0])] {
+                let Some(ci1) = self.find_clause(&[-c, -t, o]) else {
+                    continue;
+                };
+                for ci2 in self.occurs(c) {
+                    let clause2 = &self.clauses[ci2];
+                    if clause2.len() != 3 || !clause2.contains(&-o) {
+                        continue;
+                    }
+                    let e = *clause2.iter().find(|l| **l != c && **l != -o).unwrap();
+                    if e.unsigned_abs() == c.unsigned_abs() {
+                        continue;
+                    }
+                    if let Some(ci3) = self.find_clause(&[c, -e, o]) {
+                        if self.try_define(out, CNFGateDef::Ite(c, t, e), vec![ci, ci1, ci2, ci3]) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    // out = XOR(vars) ^ value from XOR clause
+    fn find_xor_clause(&mut self, out: usize) -> bool {
+        let xcis = self.xor_occurs.get(&out).cloned().unwrap_or_default();
+        for xci in xcis {
+            if self.xor_used[xci] {
+                continue;
+            }
+            let (vars, value) = &self.xor_clauses[xci];
+            let inputs = vars
+                .iter()
+                .filter(|v| **v != out)
+                .map(|v| isize::try_from(*v).unwrap())
+                .collect::<Vec<_>>();
+            let def = CNFGateDef::Xor(inputs, *value);
+            if !self.depends_on(def.inputs(), out) {
+                self.xor_used[xci] = true;
+                self.defs[out] = Some(def);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn find_gates(&mut self) {
+        // Tseitin encoding usually gives greater variables to gate outputs
+        for out in (1..self.defs.len()).rev() {
+            let _ = self.find_xor_clause(out)
+                || self.find_and(out)
+                || self.find_xor(out)
+                || self.find_ite(out);
+        }
+    }
+}
+
+// returns balanced tree of XORs of expressions
+fn xor_tree(
+    mut exprs: Vec<gategen::boolvar::BoolVarSys>,
+    neg: bool,
+) -> gategen::boolvar::BoolVarSys {
+    use gategen::boolvar::*;
+    if exprs.is_empty() {
+        return BoolVarSys::from(neg);
+    }
+    while exprs.len() > 1 {
+        exprs = exprs
+            .chunks(2)
+            .map(|c| {
+                if c.len() == 2 {
+                    &c[0] ^ &c[1]
+                } else {
+                    c[0].clone()
+                }
+            })
+            .collect();
+    }
+    let e = exprs.pop().unwrap();
+    if neg {
+        !e
+    } else {
+        e
+    }
+}
+
+fn from_cnf_with_options_int(
+    var_count: usize,
+    clauses: Vec<Vec<isize>>,
+    xor_clauses: Vec<(Vec<usize>, bool)>,
+    options: CNFReadOptions,
+) -> (Circuit<usize>, Vec<CNFVarEntry>) {
+    use gategen::boolvar::*;
+    let mut finder = CNFGateFinder::new(var_count, clauses, xor_clauses);
+    if options.recover_gates {
+        finder.find_gates();
+    }
+    let mut exprs: Vec<Option<BoolVarSys>> = vec![None; var_count + 1];
+    let mut inputs = vec![];
+    for (expr, def) in exprs.iter_mut().zip(finder.defs.iter()).skip(1) {
+        if def.is_none() {
+            let var = BoolVarSys::var();
+            *expr = Some(var.clone());
+            inputs.push(var);
+        }
+    }
+    let lit_expr = |exprs: &[Option<BoolVarSys>], l: isize| {
+        let e = exprs[l.unsigned_abs()].clone().unwrap();
+        if l < 0 {
+            !e
+        } else {
+            e
+        }
+    };
+    // evaluate gate definitions in topological order
+    for v in 1..=var_count {
+        let mut stack = vec![(v, false)];
+        while let Some((v, visited)) = stack.pop() {
+            if exprs[v].is_some() {
+                continue;
+            }
+            let def = finder.defs[v].as_ref().unwrap();
+            if !visited {
+                stack.push((v, true));
+                stack.extend(def.inputs().into_iter().map(|i| (i, false)));
+                continue;
+            }
+            exprs[v] = Some(match def {
+                CNFGateDef::And(lits, neg) => {
+                    let e = lits
+                        .iter()
+                        .fold(BoolVarSys::from(true), |a, l| a & lit_expr(&exprs, *l));
+                    if *neg {
+                        !e
+                    } else {
+                        e
+                    }
+                }
+                CNFGateDef::Xor(lits, neg) => {
+                    xor_tree(lits.iter().map(|l| lit_expr(&exprs, *l)).collect(), *neg)
+                }
+                CNFGateDef::Ite(c, t, e) => bool_ite(
+                    lit_expr(&exprs, *c),
+                    lit_expr(&exprs, *t),
+                    lit_expr(&exprs, *e),
+                ),
+            });
+        }
+    }
+    let formula = finder
+        .clauses
+        .iter()
+        .zip(finder.used.iter())
+        .filter(|(_, used)| !**used)
+        .fold(BoolVarSys::from(true), |a, (c, _)| {
+            a & c
+                .iter()
+                .fold(BoolVarSys::from(false), |ca, l| ca | lit_expr(&exprs, *l))
+        });
+    // XOR clauses: XOR(vars) = value
+    let formula = finder
+        .xor_clauses
+        .iter()
+        .zip(finder.xor_used.iter())
+        .filter(|(_, used)| !**used)
+        .fold(formula, |a, ((vars, value), _)| {
+            a & xor_tree(
+                vars.iter().map(|v| exprs[*v].clone().unwrap()).collect(),
+                !*value,
+            )
+        });
+    let (circuit, input_map) = formula.to_translated_circuit_with_map(inputs.into_iter());
+    let mut input_map = input_map.into_iter();
+    let var_map = (1..=var_count)
+        .map(|v| {
+            if finder.defs[v].is_some() {
+                CNFVarEntry::GateOutput
+            } else {
+                CNFVarEntry::Input(input_map.next().unwrap())
+            }
+        })
+        .collect::<Vec<_>>();
+    (circuit, var_map)
+}
+
+fn xor_syntax_error(line: usize, msg: String) -> flussab_cnf::ParseError {
+    flussab_cnf::ParseError::from(flussab::text::SyntaxError {
+        location: flussab::text::LineColumn { line, column: 1 },
+        msg,
+    })
+}
+
+// XOR clauses in form: (line number, literals)
+type XorClauses = Vec<(usize, Vec<isize>)>;
+
+// Extracts XOR clauses (lines started by 'x') from source. XOR clauses are replaced by
+// comments and clause count in header is decreased by number of XOR clauses.
+fn extract_xor_clauses(source: &str) -> Result<(String, XorClauses), flussab_cnf::ParseError> {
+    let mut xor_clauses = vec![];
+    let mut lines = vec![];
+    let mut header_line = None;
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix('x') {
+            let mut lits = vec![];
+            let mut terminated = false;
+            for token in rest.split_whitespace() {
+                match token.parse::<isize>() {
+                    Ok(_) if terminated => {
+                        return Err(xor_syntax_error(i + 1, "expected end of line".to_string()))
+                    }
+                    Ok(0) => terminated = true,
+                    Ok(l) => lits.push(l),
+                    Err(_) => {
+                        return Err(xor_syntax_error(
+                            i + 1,
+                            format!("expected literal or terminating zero, found {:?}", token),
+                        ))
+                    }
+                }
+            }
+            if !terminated {
+                return Err(xor_syntax_error(
+                    i + 1,
+                    "expected terminating zero".to_string(),
+                ));
+            }
+            xor_clauses.push((i + 1, lits));
+            lines.push("c\n".to_string());
+        } else {
+            if header_line.is_none() && trimmed.starts_with('p') {
+                header_line = Some(i);
+            }
+            lines.push(line.to_string());
+        }
+    }
+    if let Some(header_line) = header_line {
+        let fields = lines[header_line].split_whitespace().collect::<Vec<_>>();
+        if let [p, cnf, var_count, clause_count] = fields[..] {
+            if let Ok(clause_count) = clause_count.parse::<usize>() {
+                lines[header_line] = format!(
+                    "{} {} {} {}\n",
+                    p,
+                    cnf,
+                    var_count,
+                    clause_count.saturating_sub(xor_clauses.len())
+                );
+            }
+        }
+    }
+    Ok((lines.concat(), xor_clauses))
+}
+
+/// Converts DIMACS CNF (Conjuctive Normal Form) logic to Gate circuit with options.
+///
+/// `input` is stream with logic in DIMACS CNF format. `options` are conversion options.
+/// If `recover_gates` is set then function finds gate definitions (AND, OR, XOR and ITE
+/// encoded by Tseitin encoding) and converts them to gates. Remaining clauses are
+/// constraints that gives circuit output. Variables defined by gates are not circuit inputs.
+///
+/// If `xor_clauses` is set then function accepts XOR clauses (`x` lines) that are
+/// converted to trees of XOR gates. If `recover_gates` is also set then XOR clause
+/// can define a variable.
+///
+/// If header is missing or gives zero variable count then variable count is given by
+/// greatest variable in clauses.
+///
+/// Function returns Gate circuit with its mapping. Mapping in form: index - original
+/// variable in CNF logic (starts from 0), value - mapping entry of variable.
+pub fn from_cnf_with_options(
+    input: impl Read,
+    options: CNFReadOptions,
+) -> Result<(Circuit<usize>, Vec<CNFVarEntry>), flussab_cnf::ParseError> {
+    use gategen::boolvar::*;
+    let mut input = input;
+    let mut source = vec![];
+    input.read_to_end(&mut source)?;
+    let (source, xor_clauses) = if options.xor_clauses {
+        let (source, xor_clauses) = extract_xor_clauses(&String::from_utf8_lossy(&source))?;
+        (source.into_bytes(), xor_clauses)
+    } else {
+        (source, vec![])
+    };
+    let mut parser = cnf::Parser::<isize>::from_read(source.as_slice(), cnf::Config::default())?;
+    // parser checks literals only if header gives nonzero variable count. Otherwise
+    // variable count is given by greatest variable.
+    let header_var_count = parser.header().map_or(0, |h| h.var_count);
+    let mut var_count = header_var_count;
+    let mut clauses = vec![];
+    while let Some(clause) = parser.next_clause()? {
+        let mut clause = clause.to_vec();
+        clause.sort_by_key(|l| (l.unsigned_abs(), *l));
+        clause.dedup();
+        var_count = clause
+            .iter()
+            .map(|l| l.unsigned_abs())
+            .fold(var_count, usize::max);
+        // skip tautologies
+        if clause.windows(2).all(|w| w[0] != -w[1]) {
+            clauses.push(clause);
+        }
+    }
+    // normalize XOR clauses: remove pairs of this same variable
+    let mut norm_xor_clauses = vec![];
+    for (line, lits) in xor_clauses {
+        let mut vars = vec![];
+        let mut value = true;
+        for l in lits {
+            let v = l.unsigned_abs();
+            if header_var_count == 0 {
+                var_count = var_count.max(v);
+            } else if v > var_count {
+                return Err(xor_syntax_error(
+                    line,
+                    format!("variable {} out of range", v),
+                ));
+            }
+            if l < 0 {
+                value = !value;
+            }
+            if let Some(p) = vars.iter().position(|x| *x == v) {
+                vars.swap_remove(p);
+            } else {
+                vars.push(v);
+            }
+        }
+        vars.sort();
+        norm_xor_clauses.push((vars, value));
+    }
+    Ok(callsys(|| {
+        from_cnf_with_options_int(
+            var_count,
+            clauses.clone(),
+            norm_xor_clauses.clone(),
+            options,
+        )
+    }))
+}
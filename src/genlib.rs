@@ -0,0 +1,456 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to read cell libraries in the genlib format.
+//!
+//! A genlib library describes cells (gates and latches) with their logic functions.
+//! It is used to read and write technology-mapped BLIF logic (`.gate` and `.mlatch`).
+
+use std::io::{self, Read};
+
+/// Logic function of genlib cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenlibExpr {
+    /// Constant value (CONST0 or CONST1).
+    Value(bool),
+    /// Cell input given by index of cell input.
+    Input(usize),
+    /// Negation.
+    Not(Box<GenlibExpr>),
+    /// Conjunction.
+    And(Box<GenlibExpr>, Box<GenlibExpr>),
+    /// Disjunction.
+    Or(Box<GenlibExpr>, Box<GenlibExpr>),
+    /// Exclusive disjunction.
+    Xor(Box<GenlibExpr>, Box<GenlibExpr>),
+}
+
+impl GenlibExpr {
+    /// Evaluates function. `inputs` are values of cell inputs.
+    pub fn eval(&self, inputs: &[bool]) -> bool {
+        match self {
+            GenlibExpr::Value(v) => *v,
+            GenlibExpr::Input(i) => inputs[*i],
+            GenlibExpr::Not(a) => !a.eval(inputs),
+            GenlibExpr::And(a, b) => a.eval(inputs) && b.eval(inputs),
+            GenlibExpr::Or(a, b) => a.eval(inputs) || b.eval(inputs),
+            GenlibExpr::Xor(a, b) => a.eval(inputs) ^ b.eval(inputs),
+        }
+    }
+}
+
+/// Sequential part of genlib latch cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenlibSeq {
+    /// Type of latch (for example: `RISING_EDGE`, `ACTIVE_HIGH`).
+    pub seq_type: String,
+    /// Name of control (clock) pin.
+    pub control: Option<String>,
+}
+
+/// Cell of genlib library.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenlibCell {
+    /// Name of cell.
+    pub name: String,
+    /// Area of cell.
+    pub area: f64,
+    /// Name of output pin.
+    pub output: String,
+    /// Names of input pins. In order of `PIN` statements, next in order of first
+    /// occurrence in function. For latch, it can contain output pin if function uses it.
+    pub inputs: Vec<String>,
+    /// Logic function. For latch it is function of next state.
+    pub function: GenlibExpr,
+    /// Sequential part if cell is latch.
+    pub seq: Option<GenlibSeq>,
+}
+
+impl GenlibCell {
+    /// Returns true if cell is latch.
+    pub fn is_latch(&self) -> bool {
+        self.seq.is_some()
+    }
+}
+
+/// Genlib cell library.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenlibLibrary {
+    /// Cells of library.
+    pub cells: Vec<GenlibCell>,
+}
+
+impl GenlibLibrary {
+    /// Returns cell by name.
+    pub fn cell(&self, name: &str) -> Option<&GenlibCell> {
+        self.cells.iter().find(|c| c.name == name)
+    }
+}
+
+/// Error type for genlib library.
+#[derive(thiserror::Error, Debug)]
+pub enum GenlibError {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unexpected end of file.
+    #[error("Unexpected end of file")]
+    UnexpectedEOF,
+    /// If unknown statement.
+    #[error("{0}: Unknown statement {1}")]
+    UnknownStatement(usize, String),
+    /// If statement is not after cell definition.
+    #[error("{0}: Statement outside of cell")]
+    OutsideOfCell(usize),
+    /// If cell already defined.
+    #[error("{0}: Cell {1} already defined")]
+    CellAlreadyDefined(usize, String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GenlibToken {
+    Word(String),
+    Symbol(char),
+}
+
+const GENLIB_SYMBOLS: [char; 11] = ['=', ';', '(', ')', '!', '\'', '*', '&', '+', '|', '^'];
+
+fn genlib_tokens(source: &str) -> Result<Vec<(usize, GenlibToken)>, GenlibError> {
+    let mut tokens = vec![];
+    let mut line_no = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line_no += 1;
+        } else if c.is_whitespace() {
+            continue;
+        } else if c == '#' {
+            while chars.next_if(|c| *c != '\n').is_some() {}
+        } else if c == '"' {
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\n') | None => {
+                        return Err(GenlibError::SyntaxError(line_no, format!("\"{}", name)));
+                    }
+                    Some(c) => name.push(c),
+                }
+            }
+            tokens.push((line_no, GenlibToken::Word(name)));
+        } else if GENLIB_SYMBOLS.contains(&c) {
+            tokens.push((line_no, GenlibToken::Symbol(c)));
+        } else {
+            let mut name = c.to_string();
+            while let Some(c) =
+                chars.next_if(|c| !c.is_whitespace() && !GENLIB_SYMBOLS.contains(c) && *c != '"')
+            {
+                name.push(c);
+            }
+            tokens.push((line_no, GenlibToken::Word(name)));
+        }
+    }
+    Ok(tokens)
+}
+
+struct GenlibParser {
+    tokens: Vec<(usize, GenlibToken)>,
+    pos: usize,
+    // variables of function of current cell
+    vars: Vec<String>,
+}
+
+impl GenlibParser {
+    fn line_no(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(l, _)| *l)
+            .unwrap_or(1)
+    }
+
+    fn peek(&self) -> Option<&GenlibToken> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<GenlibToken, GenlibError> {
+        if let Some((_, t)) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            Ok(t.clone())
+        } else {
+            Err(GenlibError::UnexpectedEOF)
+        }
+    }
+
+    fn is_symbol(&self, sym: char) -> bool {
+        matches!(self.peek(), Some(GenlibToken::Symbol(s)) if *s == sym)
+    }
+
+    fn expect_symbol(&mut self, sym: char) -> Result<(), GenlibError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            GenlibToken::Symbol(s) if s == sym => Ok(()),
+            t => Err(GenlibError::SyntaxError(
+                line_no,
+                format!("expected '{}', found {:?}", sym, t),
+            )),
+        }
+    }
+
+    fn expect_word(&mut self) -> Result<String, GenlibError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            GenlibToken::Word(s) => Ok(s),
+            t => Err(GenlibError::SyntaxError(
+                line_no,
+                format!("expected name, found {:?}", t),
+            )),
+        }
+    }
+
+    // pin name can be '*' - all pins
+    fn expect_pin(&mut self) -> Result<Option<String>, GenlibError> {
+        if self.is_symbol('*') {
+            self.pos += 1;
+            Ok(None)
+        } else {
+            self.expect_word().map(Some)
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<f64, GenlibError> {
+        let line_no = self.line_no();
+        let word = self.expect_word()?;
+        word.parse::<f64>()
+            .map_err(|_| GenlibError::SyntaxError(line_no, word))
+    }
+
+    fn skip_words(&mut self, n: usize) -> Result<(), GenlibError> {
+        for _ in 0..n {
+            self.expect_word()?;
+        }
+        Ok(())
+    }
+
+    // expr: xor_expr {('+'|'|') xor_expr}
+    fn parse_expr(&mut self) -> Result<GenlibExpr, GenlibError> {
+        let mut expr = self.parse_xor()?;
+        while self.is_symbol('+') || self.is_symbol('|') {
+            self.pos += 1;
+            expr = GenlibExpr::Or(Box::new(expr), Box::new(self.parse_xor()?));
+        }
+        Ok(expr)
+    }
+
+    // xor_expr: and_expr {'^' and_expr}
+    fn parse_xor(&mut self) -> Result<GenlibExpr, GenlibError> {
+        let mut expr = self.parse_and()?;
+        while self.is_symbol('^') {
+            self.pos += 1;
+            expr = GenlibExpr::Xor(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    // and_expr: not_expr {['*'|'&'] not_expr}
+    fn parse_and(&mut self) -> Result<GenlibExpr, GenlibError> {
+        let mut expr = self.parse_not()?;
+        loop {
+            if self.is_symbol('*') || self.is_symbol('&') {
+                self.pos += 1;
+            } else if !matches!(
+                self.peek(),
+                Some(GenlibToken::Word(_)) | Some(GenlibToken::Symbol('(' | '!'))
+            ) {
+                break;
+            }
+            expr = GenlibExpr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    // not_expr: '!' not_expr | primary {'\''}
+    fn parse_not(&mut self) -> Result<GenlibExpr, GenlibError> {
+        if self.is_symbol('!') {
+            self.pos += 1;
+            return Ok(GenlibExpr::Not(Box::new(self.parse_not()?)));
+        }
+        let line_no = self.line_no();
+        let mut expr = match self.next()? {
+            GenlibToken::Symbol('(') => {
+                let expr = self.parse_expr()?;
+                self.expect_symbol(')')?;
+                expr
+            }
+            GenlibToken::Word(w) if w == "CONST0" => GenlibExpr::Value(false),
+            GenlibToken::Word(w) if w == "CONST1" => GenlibExpr::Value(true),
+            GenlibToken::Word(w) => {
+                if let Some(i) = self.vars.iter().position(|v| *v == w) {
+                    GenlibExpr::Input(i)
+                } else {
+                    self.vars.push(w);
+                    GenlibExpr::Input(self.vars.len() - 1)
+                }
+            }
+            t => {
+                return Err(GenlibError::SyntaxError(
+                    line_no,
+                    format!("unexpected {:?}", t),
+                ))
+            }
+        };
+        while self.is_symbol('\'') {
+            self.pos += 1;
+            expr = GenlibExpr::Not(Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn parse(&mut self) -> Result<GenlibLibrary, GenlibError> {
+        // cells with: function variables and pin names
+        let mut cells: Vec<(GenlibCell, Vec<String>, Vec<String>)> = vec![];
+        while self.pos < self.tokens.len() {
+            let line_no = self.line_no();
+            let stmt = self.expect_word()?;
+            match stmt.as_str() {
+                "GATE" | "LATCH" => {
+                    let name = self.expect_word()?;
+                    if cells.iter().any(|(c, _, _)| c.name == name) {
+                        return Err(GenlibError::CellAlreadyDefined(line_no, name));
+                    }
+                    let area = self.expect_number()?;
+                    let output = self.expect_word()?;
+                    self.expect_symbol('=')?;
+                    self.vars.clear();
+                    let function = self.parse_expr()?;
+                    self.expect_symbol(';')?;
+                    cells.push((
+                        GenlibCell {
+                            name,
+                            area,
+                            output,
+                            inputs: vec![],
+                            function,
+                            seq: if stmt == "LATCH" {
+                                Some(GenlibSeq {
+                                    seq_type: String::new(),
+                                    control: None,
+                                })
+                            } else {
+                                None
+                            },
+                        },
+                        self.vars.clone(),
+                        vec![],
+                    ));
+                }
+                "PIN" => {
+                    let pin = self.expect_pin()?;
+                    // phase, input load, max load and four delays
+                    self.skip_words(7)?;
+                    let (_, _, pins) = cells
+                        .last_mut()
+                        .ok_or(GenlibError::OutsideOfCell(line_no))?;
+                    if let Some(pin) = pin {
+                        pins.push(pin);
+                    }
+                }
+                "SEQ" => {
+                    // input, output and type
+                    self.skip_words(2)?;
+                    let seq_type = self.expect_word()?;
+                    let (cell, _, _) = cells
+                        .last_mut()
+                        .ok_or(GenlibError::OutsideOfCell(line_no))?;
+                    if let Some(seq) = cell.seq.as_mut() {
+                        seq.seq_type = seq_type;
+                    } else {
+                        return Err(GenlibError::OutsideOfCell(line_no));
+                    }
+                }
+                "CONTROL" => {
+                    let pin = self.expect_word()?;
+                    // input load, max load and four delays
+                    self.skip_words(6)?;
+                    let (cell, _, _) = cells
+                        .last_mut()
+                        .ok_or(GenlibError::OutsideOfCell(line_no))?;
+                    if let Some(seq) = cell.seq.as_mut() {
+                        seq.control = Some(pin);
+                    } else {
+                        return Err(GenlibError::OutsideOfCell(line_no));
+                    }
+                }
+                "CONSTRAINT" => {
+                    // pin, setup and hold time
+                    self.skip_words(3)?;
+                }
+                _ => return Err(GenlibError::UnknownStatement(line_no, stmt)),
+            }
+        }
+        // resolve order of inputs: first pins, next rest of function variables.
+        Ok(GenlibLibrary {
+            cells: cells
+                .into_iter()
+                .map(|(mut cell, vars, pins)| {
+                    let control = cell.seq.as_ref().and_then(|s| s.control.clone());
+                    let mut inputs = pins
+                        .into_iter()
+                        .filter(|p| *p != cell.output && Some(p) != control.as_ref())
+                        .collect::<Vec<_>>();
+                    for v in &vars {
+                        if !inputs.contains(v) {
+                            inputs.push(v.clone());
+                        }
+                    }
+                    let var_map = vars
+                        .iter()
+                        .map(|v| inputs.iter().position(|x| x == v).unwrap())
+                        .collect::<Vec<_>>();
+                    cell.function = rename_inputs(cell.function, &var_map);
+                    cell.inputs = inputs;
+                    cell
+                })
+                .collect(),
+        })
+    }
+}
+
+fn rename_inputs(expr: GenlibExpr, var_map: &[usize]) -> GenlibExpr {
+    match expr {
+        GenlibExpr::Value(v) => GenlibExpr::Value(v),
+        GenlibExpr::Input(i) => GenlibExpr::Input(var_map[i]),
+        GenlibExpr::Not(a) => GenlibExpr::Not(Box::new(rename_inputs(*a, var_map))),
+        GenlibExpr::And(a, b) => GenlibExpr::And(
+            Box::new(rename_inputs(*a, var_map)),
+            Box::new(rename_inputs(*b, var_map)),
+        ),
+        GenlibExpr::Or(a, b) => GenlibExpr::Or(
+            Box::new(rename_inputs(*a, var_map)),
+            Box::new(rename_inputs(*b, var_map)),
+        ),
+        GenlibExpr::Xor(a, b) => GenlibExpr::Xor(
+            Box::new(rename_inputs(*a, var_map)),
+            Box::new(rename_inputs(*b, var_map)),
+        ),
+    }
+}
+
+/// Reads genlib cell library.
+///
+/// Function reads `GATE` and `LATCH` cells with their functions, `PIN` statements
+/// (that determine order of cell inputs), `SEQ` and `CONTROL` statements for latches.
+/// Timing data and `CONSTRAINT` statements are ignored. Function operators are: `!` and `'`
+/// (negation), `*`, `&` or juxtaposition (conjunction), `^` (exclusive disjunction),
+/// `+` and `|` (disjunction). Constants are `CONST0` and `CONST1`.
+pub fn from_genlib(mut input: impl Read) -> Result<GenlibLibrary, GenlibError> {
+    let mut source = String::new();
+    input.read_to_string(&mut source)?;
+    let mut parser = GenlibParser {
+        tokens: genlib_tokens(&source)?,
+        pos: 0,
+        vars: vec![],
+    };
+    parser.parse()
+}
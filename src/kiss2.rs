@@ -0,0 +1,405 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to conversion from the KISS2 finite state machine format to Gate circuit.
+//!
+//! A state table is encoded into sequential Gate circuit with chosen state encoding.
+
+use crate::gatesim::*;
+use gategen::boolvar::*;
+use gategen::dynintvar::*;
+
+use std::io::{self, Read};
+
+/// Encoding of states of finite state machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KISS2Encoding {
+    /// Binary encoding: state index is code.
+    #[default]
+    Binary,
+    /// One-hot encoding: one state bit per state.
+    OneHot,
+    /// Gray encoding: Gray code of state index is code.
+    Gray,
+}
+
+/// Additional information about encoded finite state machine.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KISS2Info {
+    /// States with their codes. Code is list of values of state bits.
+    pub state_codes: Vec<(String, Vec<bool>)>,
+    /// Name of reset state.
+    pub reset_state: String,
+    /// Initial values of state bits (code of reset state).
+    pub state_inits: Vec<Option<bool>>,
+}
+
+/// Error type for KISS2 format.
+#[derive(thiserror::Error, Debug)]
+pub enum KISS2Error {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unknown directive.
+    #[error("{0}: Unknown directive {1}")]
+    UnknownDirective(usize, String),
+    /// If number of inputs or outputs is not given before state table.
+    #[error("{0}: Number of inputs or outputs is not given")]
+    NoHeader(usize),
+    /// If bad input cube in state table.
+    #[error("{0}: Bad input cube")]
+    BadInputCube(usize),
+    /// If bad output cube in state table.
+    #[error("{0}: Bad output cube")]
+    BadOutputCube(usize),
+    /// If no states in state table.
+    #[error("No states")]
+    NoStates,
+    /// If reset state is not in state table.
+    #[error("Unknown reset state {0}")]
+    UnknownResetState(String),
+    /// If FSM has no inputs and no state bits, but it has outputs. Circuit without inputs
+    /// can't hold constant outputs.
+    #[error("Constant outputs without inputs and state")]
+    ConstantOutputsWithoutInputs,
+}
+
+// row of state table: input cube, present state (None - any state),
+// next state (None - don't care), output cube. Cube values: None - don't care.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KISS2Row {
+    inputs: Vec<Option<bool>>,
+    present: Option<usize>,
+    next: Option<usize>,
+    outputs: Vec<Option<bool>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KISS2FSM {
+    pub(crate) input_len: usize,
+    pub(crate) output_len: usize,
+    pub(crate) states: Vec<String>,
+    pub(crate) reset: usize,
+    rows: Vec<KISS2Row>,
+}
+
+fn parse_cube(cube: &str) -> Option<Vec<Option<bool>>> {
+    cube.chars()
+        .map(|c| match c {
+            '0' => Some(Some(false)),
+            '1' => Some(Some(true)),
+            '-' | '~' => Some(None),
+            _ => None,
+        })
+        .collect()
+}
+
+impl KISS2FSM {
+    // parse KISS2 lines: line number and tokens.
+    pub(crate) fn parse(
+        lines: impl IntoIterator<Item = (usize, Vec<String>)>,
+    ) -> Result<Self, KISS2Error> {
+        let mut input_len = None;
+        let mut output_len = None;
+        let mut reset = None;
+        let mut states = Vec::<String>::new();
+        let mut rows = vec![];
+        let parse_num = |line_no: usize, line: &[String]| {
+            if line.len() != 2 {
+                return Err(KISS2Error::SyntaxError(line_no, line.join(" ")));
+            }
+            line[1]
+                .parse::<usize>()
+                .map_err(|_| KISS2Error::SyntaxError(line_no, line[1].clone()))
+        };
+        for (line_no, line) in lines {
+            if line[0].starts_with('.') {
+                match line[0].as_str() {
+                    ".i" => input_len = Some(parse_num(line_no, &line)?),
+                    ".o" => output_len = Some(parse_num(line_no, &line)?),
+                    ".p" | ".s" => {
+                        parse_num(line_no, &line)?;
+                    }
+                    ".r" => {
+                        if line.len() != 2 {
+                            return Err(KISS2Error::SyntaxError(line_no, line.join(" ")));
+                        }
+                        reset = Some(line[1].clone());
+                    }
+                    ".e" | ".end" => break,
+                    _ => return Err(KISS2Error::UnknownDirective(line_no, line[0].clone())),
+                }
+                continue;
+            }
+            let (Some(input_len), Some(output_len)) = (input_len, output_len) else {
+                return Err(KISS2Error::NoHeader(line_no));
+            };
+            // input cube, present state, next state, output cube
+            let token_num = usize::from(input_len != 0) + 2 + usize::from(output_len != 0);
+            if line.len() != token_num {
+                return Err(KISS2Error::SyntaxError(line_no, line.join(" ")));
+            }
+            let (inputs, line) = if input_len != 0 {
+                let inputs = parse_cube(&line[0])
+                    .filter(|c| c.len() == input_len)
+                    .ok_or(KISS2Error::BadInputCube(line_no))?;
+                (inputs, &line[1..])
+            } else {
+                (vec![], &line[..])
+            };
+            let outputs = if output_len != 0 {
+                parse_cube(&line[2])
+                    .filter(|c| c.len() == output_len)
+                    .ok_or(KISS2Error::BadOutputCube(line_no))?
+            } else {
+                vec![]
+            };
+            let mut state_index = |name: &String| {
+                if name == "*" || name == "-" {
+                    None
+                } else if let Some(i) = states.iter().position(|s| s == name) {
+                    Some(i)
+                } else {
+                    states.push(name.clone());
+                    Some(states.len() - 1)
+                }
+            };
+            let present = state_index(&line[0]);
+            let next = state_index(&line[1]);
+            rows.push(KISS2Row {
+                inputs,
+                present,
+                next,
+                outputs,
+            });
+        }
+        if states.is_empty() {
+            return Err(KISS2Error::NoStates);
+        }
+        // reset state - given or first present state
+        let reset = if let Some(reset) = reset {
+            states
+                .iter()
+                .position(|s| *s == reset)
+                .ok_or(KISS2Error::UnknownResetState(reset))?
+        } else {
+            rows.iter().find_map(|r| r.present).unwrap_or(0)
+        };
+        Ok(Self {
+            input_len: input_len.unwrap_or(0),
+            output_len: output_len.unwrap_or(0),
+            states,
+            reset,
+            rows,
+        })
+    }
+
+    // returns codes of states
+    pub(crate) fn codes(&self, encoding: KISS2Encoding) -> Vec<Vec<bool>> {
+        let state_len = self.states.len();
+        let bits = match encoding {
+            KISS2Encoding::Binary | KISS2Encoding::Gray => {
+                (usize::BITS - (state_len - 1).leading_zeros()) as usize
+            }
+            KISS2Encoding::OneHot => state_len,
+        };
+        (0..state_len)
+            .map(|i| {
+                let code = match encoding {
+                    KISS2Encoding::Binary => i,
+                    KISS2Encoding::Gray => i ^ (i >> 1),
+                    KISS2Encoding::OneHot => 0,
+                };
+                (0..bits)
+                    .map(|j| {
+                        if encoding == KISS2Encoding::OneHot {
+                            i == j
+                        } else {
+                            ((code >> j) & 1) != 0
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    // returns expressions of next state bits and outputs.
+    pub(crate) fn exprs(
+        &self,
+        encoding: KISS2Encoding,
+        codes: &[Vec<bool>],
+        inputs: &[BoolVarSys],
+        state: &[BoolVarSys],
+    ) -> (Vec<BoolVarSys>, Vec<BoolVarSys>) {
+        let mut nexts = vec![BoolVarSys::from(false); state.len()];
+        let mut outputs = vec![BoolVarSys::from(false); self.output_len];
+        for row in &self.rows {
+            let mut term = BoolVarSys::from(true);
+            for (v, x) in row.inputs.iter().zip(inputs.iter()) {
+                if let Some(v) = v {
+                    term &= if *v { x.clone() } else { !x.clone() };
+                }
+            }
+            if let Some(present) = row.present {
+                if encoding == KISS2Encoding::OneHot {
+                    term &= state[present].clone();
+                } else {
+                    for (v, s) in codes[present].iter().zip(state.iter()) {
+                        term &= if *v { s.clone() } else { !s.clone() };
+                    }
+                }
+            }
+            if let Some(next) = row.next {
+                for (v, n) in codes[next].iter().zip(nexts.iter_mut()) {
+                    if *v {
+                        *n |= term.clone();
+                    }
+                }
+            }
+            for (v, o) in row.outputs.iter().zip(outputs.iter_mut()) {
+                if *v == Some(true) {
+                    *o |= term.clone();
+                }
+            }
+        }
+        (nexts, outputs)
+    }
+}
+
+// Completes circuit generated by gategen that omits unused inputs and outputs with
+// constant values. `input_map` is map of inputs: index - input, value - circuit input.
+// `outputs` are expressions of all outputs.
+fn complete_circuit(
+    circuit: Circuit<usize>,
+    input_map: &[Option<usize>],
+    outputs: &[BoolVarSys],
+) -> Circuit<usize> {
+    let input_len = input_map.len();
+    let old_input_len = circuit.input_len();
+    let unused = (0..input_len)
+        .filter(|i| input_map[*i].is_none())
+        .collect::<Vec<_>>();
+    let have_values = outputs.iter().any(|x| x.value().is_some());
+    if (unused.is_empty() && !have_values) || input_len == 0 || outputs.is_empty() {
+        return circuit;
+    }
+    let mut trans = vec![0; old_input_len];
+    for (i, ci) in input_map.iter().enumerate() {
+        if let Some(ci) = ci {
+            trans[*ci] = i;
+        }
+    }
+    let trans_wire = |w: usize| {
+        if w < old_input_len {
+            trans[w]
+        } else {
+            w - old_input_len + input_len
+        }
+    };
+    let mut gates = circuit
+        .gates()
+        .iter()
+        .map(|g| Gate {
+            i0: trans_wire(g.i0),
+            i1: trans_wire(g.i1),
+            func: g.func,
+        })
+        .collect::<Vec<_>>();
+    // false value that uses all unused inputs
+    let first = unused.first().copied().unwrap_or(0);
+    gates.push(Gate::new_nimpl(first, first));
+    for u in unused.iter().skip(1) {
+        gates.push(Gate::new_nimpl(input_len + gates.len() - 1, *u));
+    }
+    let false_wire = input_len + gates.len() - 1;
+    let mut circ_outputs = circuit.outputs().iter();
+    let mut new_outputs = outputs
+        .iter()
+        .map(|x| {
+            if let Some(v) = x.value() {
+                (false_wire, v)
+            } else {
+                let (w, n) = circ_outputs.next().unwrap();
+                (trans_wire(*w), *n)
+            }
+        })
+        .collect::<Vec<_>>();
+    if !have_values {
+        // use false value in first output
+        let (w, n) = new_outputs[0];
+        gates.push(Gate::new_xor(w, false_wire));
+        new_outputs[0] = (input_len + gates.len() - 1, n);
+    }
+    Circuit::new(input_len, gates, new_outputs).unwrap()
+}
+
+/// Converts finite state machine in KISS2 format to Gate circuit.
+///
+/// Function reads state table in KISS2 format from `input` and encodes it with `encoding`.
+/// States are encoded as state bits. Function returns Gate circuit and information about
+/// state codes and reset state. Present state `*` means any state, next state `*` or `-`
+/// means don't care (encoded as zeroes), output `-` is encoded as zero.
+///
+/// If FSM has no inputs and its states are encoded by zero bits (single state with binary
+/// or Gray encoding) then its outputs are constant and function returns
+/// `KISS2Error::ConstantOutputsWithoutInputs`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn from_kiss2(
+    input: impl Read,
+    encoding: KISS2Encoding,
+) -> Result<(Circuit<usize>, KISS2Info), KISS2Error> {
+    let mut source = String::new();
+    let mut input = input;
+    input.read_to_string(&mut source)?;
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.split('#').next().unwrap();
+            (
+                i + 1,
+                line.split_whitespace()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .filter(|(_, line)| !line.is_empty());
+    let fsm = KISS2FSM::parse(lines)?;
+    let codes = fsm.codes(encoding);
+    let state_len = codes[0].len();
+    if state_len + fsm.input_len == 0 && fsm.output_len != 0 {
+        return Err(KISS2Error::ConstantOutputsWithoutInputs);
+    }
+    let circuit = callsys(|| {
+        let state = (0..state_len)
+            .map(|_| BoolVarSys::var())
+            .collect::<Vec<_>>();
+        let inputs = (0..fsm.input_len)
+            .map(|_| BoolVarSys::var())
+            .collect::<Vec<_>>();
+        let (nexts, outputs) = fsm.exprs(encoding, &codes, &inputs, &state);
+        let all_outputs = nexts.into_iter().chain(outputs).collect::<Vec<_>>();
+        let filtered_outputs = all_outputs
+            .iter()
+            .filter(|x| x.value().is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        let outint = if !filtered_outputs.is_empty() {
+            UDynVarSys::from_iter(filtered_outputs)
+        } else {
+            UDynVarSys::var(0)
+        };
+        let (circuit, input_map) =
+            outint.to_translated_circuit_with_map(state.into_iter().chain(inputs));
+        complete_circuit(circuit, &input_map, &all_outputs)
+    });
+    let info = KISS2Info {
+        state_inits: codes[fsm.reset].iter().map(|v| Some(*v)).collect(),
+        reset_state: fsm.states[fsm.reset].clone(),
+        state_codes: fsm.states.into_iter().zip(codes).collect(),
+    };
+    Ok((circuit, info))
+}
@@ -23,21 +23,30 @@ pub enum VNegs {
 }
 
 pub mod aiger;
+pub mod bench;
 pub mod blif;
 mod blif_pla;
+pub mod bristol;
 pub mod btor2;
 pub mod cnf;
+pub mod genlib;
+pub mod kiss2;
+pub mod lutmap;
 mod vbinopcircuit;
 mod vcircuit;
 pub mod verilog;
+mod vexpr;
 pub mod vhdl;
 mod xor_table;
+pub mod yosys_json;
 
 pub use gategen;
 pub use gateutil;
 pub use gateutil::gatesim;
 
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Debug, Display};
+use std::io;
 
 /// Generate output string from mapping. The `T` must be convertible to string.
 ///
@@ -106,3 +115,536 @@ pub fn string_assign_map_to_string(map: &[(String, AssignEntry)]) -> String {
     }
     out
 }
+
+/// Reset of state registers in sequential circuit written in HDL (Verilog or VHDL).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HDLReset {
+    /// Name of reset port (active high).
+    pub name: String,
+    /// If true then reset is asynchronous, otherwise reset is synchronous.
+    pub asynchronous: bool,
+    /// Reset values of state. If vector is shorter than state then rest of state is
+    /// reset to false.
+    pub values: Vec<bool>,
+}
+
+/// Direction of port in HDL (Verilog or VHDL).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HDLPortDir {
+    /// Input port.
+    Input,
+    /// Output port.
+    Output,
+}
+
+/// Port in layout of ports of module (or entity) written in HDL (Verilog or VHDL).
+///
+/// Port with width 1 is scalar, otherwise it is vector (bus) with bits from 0 (lowest bit)
+/// to `width-1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HDLPort {
+    /// Name of port.
+    pub name: String,
+    /// Width of port.
+    pub width: usize,
+    /// Direction of port.
+    pub dir: HDLPortDir,
+}
+
+impl HDLPort {
+    /// Creates new port.
+    pub fn new(name: impl ToString, width: usize, dir: HDLPortDir) -> Self {
+        Self {
+            name: name.to_string(),
+            width,
+            dir,
+        }
+    }
+}
+
+/// Error of layout of ports given to writer.
+#[derive(thiserror::Error, Debug)]
+pub enum PortLayoutError {
+    /// If port has zero width.
+    #[error("Port {0} has zero width")]
+    ZeroWidthPort(String),
+    /// If input ports don't partition circuit inputs.
+    #[error("Input ports have {0} bits instead of {1}")]
+    InputLenMismatch(usize, usize),
+    /// If output ports don't partition circuit outputs.
+    #[error("Output ports have {0} bits instead of {1}")]
+    OutputLenMismatch(usize, usize),
+}
+
+impl From<PortLayoutError> for io::Error {
+    fn from(err: PortLayoutError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+// check whether input ports have `input_len` bits, output ports have `output_len` bits
+// and no port has zero width.
+pub(crate) fn check_hdl_ports(
+    ports: &[HDLPort],
+    input_len: usize,
+    output_len: usize,
+) -> Result<(), PortLayoutError> {
+    if let Some(port) = ports.iter().find(|p| p.width == 0) {
+        return Err(PortLayoutError::ZeroWidthPort(port.name.clone()));
+    }
+    let port_len = |dir| {
+        ports
+            .iter()
+            .filter(|p| p.dir == dir)
+            .map(|p| p.width)
+            .sum::<usize>()
+    };
+    let port_input_len = port_len(HDLPortDir::Input);
+    if port_input_len != input_len {
+        return Err(PortLayoutError::InputLenMismatch(port_input_len, input_len));
+    }
+    let port_output_len = port_len(HDLPortDir::Output);
+    if port_output_len != output_len {
+        return Err(PortLayoutError::OutputLenMismatch(
+            port_output_len,
+            output_len,
+        ));
+    }
+    Ok(())
+}
+
+// names of control ports (clock and reset) of circuit written in HDL. Controls are used
+// only if circuit is sequential.
+pub(crate) fn hdl_controls(state_len: usize, clock: &str, reset: Option<&HDLReset>) -> Vec<String> {
+    let mut controls = vec![];
+    if state_len != 0 {
+        controls.push(clock.to_string());
+        if let Some(reset) = reset {
+            controls.push(reset.name.clone());
+        }
+    }
+    controls
+}
+
+// bit of port and index of circuit input or output.
+pub(crate) type HDLPortBits = Vec<(String, usize)>;
+
+// bits of ports in layout: bit of port (`name` if port is scalar, otherwise bit select
+// by `bit_select(name, bit)`) and index of circuit input or output. Bits of input ports
+// and bits of output ports are returned separately. Ports must be checked by
+// `check_hdl_ports`.
+pub(crate) fn hdl_port_bits(
+    ports: &[HDLPort],
+    names: &[String],
+    start: usize,
+    bit_select: impl Fn(&str, usize) -> String,
+) -> (HDLPortBits, HDLPortBits) {
+    let mut input_bits = vec![];
+    let mut output_bits = vec![];
+    for (port, name) in ports.iter().zip(names) {
+        let bits = if port.dir == HDLPortDir::Input {
+            &mut input_bits
+        } else {
+            &mut output_bits
+        };
+        for b in 0..port.width {
+            let index = start + bits.len();
+            if port.width == 1 {
+                bits.push((name.clone(), index));
+            } else {
+                bits.push((bit_select(name, b), index));
+            }
+        }
+    }
+    (input_bits, output_bits)
+}
+
+// port connected to testbench: name, direction, index of first bit in testbench inputs
+// (or outputs) and width.
+pub(crate) type HDLTestbenchPorts = Vec<(String, HDLPortDir, usize, usize)>;
+
+// get ports of circuit connected to testbench. Testbench inputs and outputs are circuit
+// inputs and outputs without state. If layout is empty then every input and output is
+// scalar port.
+pub(crate) fn hdl_testbench_ports(
+    ports: &[HDLPort],
+    port_names: &[String],
+    input_names: &[String],
+    output_names: &[String],
+) -> HDLTestbenchPorts {
+    if ports.is_empty() {
+        input_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), HDLPortDir::Input, i, 1))
+            .chain(
+                output_names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| (name.clone(), HDLPortDir::Output, i, 1)),
+            )
+            .collect()
+    } else {
+        let mut input_start = 0;
+        let mut output_start = 0;
+        ports
+            .iter()
+            .zip(port_names)
+            .map(|(port, name)| {
+                let start = if port.dir == HDLPortDir::Input {
+                    &mut input_start
+                } else {
+                    &mut output_start
+                };
+                *start += port.width;
+                (name.clone(), port.dir, *start - port.width, port.width)
+            })
+            .collect()
+    }
+}
+
+/// Maximal number of inputs (without state) for exhaustive test vectors.
+pub const MAX_EXHAUSTIVE_INPUTS: usize = 20;
+
+/// Error of testbench generation.
+#[derive(thiserror::Error, Debug)]
+pub enum HDLTestbenchError {
+    /// If circuit has too many inputs for exhaustive test vectors.
+    #[error("Too many inputs for exhaustive test vectors: {0}")]
+    TooManyInputs(usize),
+    /// If circuit is sequential and has no reset.
+    #[error("Sequential circuit without reset")]
+    NoReset,
+}
+
+impl From<HDLTestbenchError> for io::Error {
+    fn from(err: HDLTestbenchError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// Input vectors applied by HDL testbench.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HDLTestVectors {
+    /// All combinations of input values (2^inputs vectors). Number of inputs
+    /// can't be greater than `MAX_EXHAUSTIVE_INPUTS`.
+    Exhaustive,
+    /// Random input vectors generated from seed.
+    Random {
+        /// Number of input vectors.
+        count: usize,
+        /// Seed of random generator.
+        seed: u64,
+    },
+}
+
+// splitmix64 random generator.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// generate input vectors and expected outputs evaluated from circuit. Values are
+// strings of bits from highest bit to lowest bit. If circuit is sequential then
+// state is not part of vectors: it starts from `init_state` and it is updated by next
+// state after every vector.
+pub(crate) fn hdl_test_vectors(
+    circuit: &gatesim::Circuit<usize>,
+    init_state: &[bool],
+    vectors: HDLTestVectors,
+) -> Result<Vec<(String, String)>, HDLTestbenchError> {
+    let state_len = init_state.len();
+    let input_len = circuit.input_len() - state_len;
+    let inputs: Vec<Vec<bool>> = match vectors {
+        HDLTestVectors::Exhaustive => {
+            if input_len > MAX_EXHAUSTIVE_INPUTS {
+                return Err(HDLTestbenchError::TooManyInputs(input_len));
+            }
+            (0..1usize << input_len)
+                .map(|v| (0..input_len).map(|b| ((v >> b) & 1) != 0).collect())
+                .collect()
+        }
+        HDLTestVectors::Random { count, seed } => {
+            let mut state = seed;
+            (0..count)
+                .map(|_| {
+                    let mut inputs = vec![];
+                    while inputs.len() < input_len {
+                        let r = splitmix64(&mut state);
+                        let n = std::cmp::min(64, input_len - inputs.len());
+                        inputs.extend((0..n).map(|b| ((r >> b) & 1) != 0));
+                    }
+                    inputs
+                })
+                .collect()
+        }
+    };
+    let bits_to_string = |bits: &[bool]| {
+        bits.iter()
+            .rev()
+            .map(|b| if *b { '1' } else { '0' })
+            .collect::<String>()
+    };
+    let mut state = init_state.to_vec();
+    Ok(inputs
+        .into_iter()
+        .map(|inputs| {
+            let outputs = circuit.eval(state.iter().chain(inputs.iter()).copied());
+            state.copy_from_slice(&outputs[0..state_len]);
+            (
+                bits_to_string(&inputs),
+                bits_to_string(&outputs[state_len..]),
+            )
+        })
+        .collect())
+}
+
+/// Names of ports and internal wires used by writers (Verilog, VHDL and BLIF).
+///
+/// If vector of names is shorter than number of inputs (or outputs) then rest of inputs
+/// (or outputs) get default names: `i{n}` (or `o{n}`). Internal wires are named
+/// `{wire_prefix}{n}`, where `n` is index of wire in circuit. Names are escaped or
+/// validated by writer for its language.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriterNames {
+    /// Names of circuit inputs.
+    pub inputs: Vec<String>,
+    /// Names of circuit outputs.
+    pub outputs: Vec<String>,
+    /// Prefix of names of internal wires.
+    pub wire_prefix: String,
+}
+
+impl Default for WriterNames {
+    fn default() -> Self {
+        Self {
+            inputs: vec![],
+            outputs: vec![],
+            wire_prefix: "i".to_string(),
+        }
+    }
+}
+
+/// Error of names given to writer.
+#[derive(thiserror::Error, Debug)]
+pub enum NameError {
+    /// If name can't be written in language.
+    #[error("Invalid name {0}")]
+    InvalidName(String),
+    /// If two ports, wires or modules have same name.
+    #[error("Name collision: {0}")]
+    NameCollision(String),
+}
+
+impl From<NameError> for io::Error {
+    fn from(err: NameError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+// escaped names of controls (clock and reset), inputs, outputs and internal wires.
+pub(crate) struct ResolvedNames {
+    pub(crate) controls: Vec<String>,
+    pub(crate) inputs: Vec<String>,
+    pub(crate) outputs: Vec<String>,
+    wires: HashMap<usize, String>,
+}
+
+impl ResolvedNames {
+    pub(crate) fn wire(&self, wire: usize) -> &str {
+        &self.wires[&wire]
+    }
+}
+
+impl WriterNames {
+    // resolve names of controls, inputs, outputs and internal wires, escape them by `escape`
+    // and check collisions. `key` returns identity of escaped name in language.
+    pub(crate) fn resolve(
+        &self,
+        controls: &[String],
+        input_len: usize,
+        output_len: usize,
+        wires: impl IntoIterator<Item = usize>,
+        escape: impl Fn(&str) -> Result<String, NameError>,
+        key: impl Fn(&str) -> String,
+    ) -> Result<ResolvedNames, NameError> {
+        let controls = controls
+            .iter()
+            .map(|n| escape(n))
+            .collect::<Result<Vec<_>, _>>()?;
+        let inputs = (0..input_len)
+            .map(|i| match self.inputs.get(i) {
+                Some(n) => escape(n),
+                None => escape(&format!("i{}", i)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = (0..output_len)
+            .map(|i| match self.outputs.get(i) {
+                Some(n) => escape(n),
+                None => escape(&format!("o{}", i)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let wires = wires
+            .into_iter()
+            .map(|w| Ok((w, escape(&format!("{}{}", self.wire_prefix, w))?)))
+            .collect::<Result<HashMap<_, _>, NameError>>()?;
+        let mut used = HashSet::new();
+        for name in controls
+            .iter()
+            .chain(inputs.iter())
+            .chain(outputs.iter())
+            .chain(wires.values())
+        {
+            if !used.insert(key(name)) {
+                return Err(NameError::NameCollision(name.clone()));
+            }
+        }
+        Ok(ResolvedNames {
+            controls,
+            inputs,
+            outputs,
+            wires,
+        })
+    }
+}
+
+/// Hierarchical circuit: top circuit with instances of named sub-circuits.
+///
+/// Sub-circuits are combinational and they are written as separate modules (models,
+/// entities). The top circuit connects inputs, outputs and instances. The top circuit
+/// inputs are organized in form: `[inputs,instance outputs]`. The top circuit outputs are
+/// organized in form: `[outputs,instance inputs]`. Instance inputs and outputs are placed
+/// in order of instances.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HierCircuit {
+    subcircuits: Vec<(String, gatesim::Circuit<usize>)>,
+    instances: Vec<usize>,
+    circuit: gatesim::Circuit<usize>,
+}
+
+// Instance of sub-circuit in hierarchical circuit.
+pub(crate) struct HierInstance<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) input_len: usize,
+    pub(crate) output_len: usize,
+    // first top circuit output that is instance input
+    pub(crate) input_start: usize,
+    // first top circuit input that is instance output
+    pub(crate) output_start: usize,
+}
+
+impl HierCircuit {
+    /// Creates new hierarchical circuit.
+    ///
+    /// `subcircuits` are named sub-circuits, `instances` are indices of sub-circuits
+    /// for every instance, `circuit` is top circuit. Returns None if sub-circuit names
+    /// are not unique, if instance has bad index or if top circuit has too few inputs
+    /// or outputs for instances.
+    pub fn new(
+        subcircuits: impl IntoIterator<Item = (String, gatesim::Circuit<usize>)>,
+        instances: impl IntoIterator<Item = usize>,
+        circuit: gatesim::Circuit<usize>,
+    ) -> Option<Self> {
+        let subcircuits = subcircuits.into_iter().collect::<Vec<_>>();
+        let instances = instances.into_iter().collect::<Vec<_>>();
+        if subcircuits
+            .iter()
+            .enumerate()
+            .any(|(i, (name, _))| subcircuits[0..i].iter().any(|(n, _)| n == name))
+        {
+            return None;
+        }
+        if instances.iter().any(|i| *i >= subcircuits.len()) {
+            return None;
+        }
+        let (inst_input_len, inst_output_len) = instances.iter().fold((0, 0), |(il, ol), i| {
+            let sc = &subcircuits[*i].1;
+            (il + sc.input_len(), ol + sc.outputs().len())
+        });
+        if inst_output_len > circuit.input_len() || inst_input_len > circuit.outputs().len() {
+            return None;
+        }
+        Some(Self {
+            subcircuits,
+            instances,
+            circuit,
+        })
+    }
+
+    // check whether name of top module is not name of any sub-circuit. `key` returns
+    // identity of name in language.
+    pub(crate) fn check_top_name(
+        &self,
+        top_name: &str,
+        key: impl Fn(&str) -> String,
+    ) -> Result<(), NameError> {
+        let top_key = key(top_name);
+        if self
+            .subcircuits
+            .iter()
+            .any(|(name, _)| key(name) == top_key)
+        {
+            return Err(NameError::NameCollision(top_name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Returns named sub-circuits.
+    pub fn subcircuits(&self) -> &[(String, gatesim::Circuit<usize>)] {
+        &self.subcircuits
+    }
+
+    /// Returns instances: indices of sub-circuits.
+    pub fn instances(&self) -> &[usize] {
+        &self.instances
+    }
+
+    /// Returns top circuit.
+    pub fn circuit(&self) -> &gatesim::Circuit<usize> {
+        &self.circuit
+    }
+
+    /// Returns number of inputs (without instance outputs).
+    pub fn input_len(&self) -> usize {
+        self.circuit.input_len()
+            - self
+                .instances
+                .iter()
+                .map(|i| self.subcircuits[*i].1.outputs().len())
+                .sum::<usize>()
+    }
+
+    /// Returns number of outputs (without instance inputs).
+    pub fn output_len(&self) -> usize {
+        self.circuit.outputs().len()
+            - self
+                .instances
+                .iter()
+                .map(|i| self.subcircuits[*i].1.input_len())
+                .sum::<usize>()
+    }
+
+    pub(crate) fn hier_instances(&self) -> Vec<HierInstance<'_>> {
+        let mut input_start = self.output_len();
+        let mut output_start = self.input_len();
+        self.instances
+            .iter()
+            .map(|i| {
+                let (name, sc) = &self.subcircuits[*i];
+                let inst = HierInstance {
+                    name,
+                    input_len: sc.input_len(),
+                    output_len: sc.outputs().len(),
+                    input_start,
+                    output_start,
+                };
+                input_start += inst.input_len;
+                output_start += inst.output_len;
+                inst
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,235 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to technology mapping of Gate circuit into LUT (look-up table) circuit.
+//!
+//! A mapper enumerates cuts of Gate circuit and chooses cuts oriented to depth or area.
+//! LUT circuit can be written in BLIF format (`.names`) or in Verilog (`LUTk` instances).
+
+use crate::gatesim::*;
+
+use std::collections::HashMap;
+
+/// Goal of LUT mapping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LUTMapGoal {
+    /// Minimize depth (number of LUT levels), next area.
+    #[default]
+    Depth,
+    /// Minimize area (number of LUTs), next depth.
+    Area,
+}
+
+/// LUT in LUT circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LUT {
+    /// Inputs of LUT: circuit inputs or LUT outputs (wires).
+    pub inputs: Vec<usize>,
+    /// Truth table of LUT: bit of index - value of input, bit of table - value of LUT.
+    pub table: u64,
+}
+
+/// LUT circuit.
+///
+/// Wires of LUT circuit are circuit inputs and LUT outputs (in this order) like
+/// in Gate circuit. LUT inputs are always wires lower than LUT output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LUTCircuit {
+    input_len: usize,
+    luts: Vec<LUT>,
+    outputs: Vec<(usize, bool)>,
+}
+
+impl LUTCircuit {
+    /// Returns number of inputs.
+    pub fn input_len(&self) -> usize {
+        self.input_len
+    }
+
+    /// Returns LUTs.
+    pub fn luts(&self) -> &[LUT] {
+        &self.luts
+    }
+
+    /// Returns outputs: wire and negation.
+    pub fn outputs(&self) -> &[(usize, bool)] {
+        &self.outputs
+    }
+
+    /// Returns number of LUTs.
+    pub fn lut_count(&self) -> usize {
+        self.luts.len()
+    }
+
+    /// Returns depth: maximal number of LUTs in path from input to output.
+    pub fn depth(&self) -> usize {
+        let depths = self.wire_depths();
+        self.outputs
+            .iter()
+            .map(|(o, _)| depths[*o])
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn wire_depths(&self) -> Vec<usize> {
+        let mut depths = vec![0; self.input_len + self.luts.len()];
+        for (i, lut) in self.luts.iter().enumerate() {
+            depths[self.input_len + i] =
+                1 + lut.inputs.iter().map(|x| depths[*x]).max().unwrap_or(0);
+        }
+        depths
+    }
+
+    /// Evaluates LUT circuit. Returns values of outputs.
+    pub fn eval(&self, inputs: impl IntoIterator<Item = bool>) -> Vec<bool> {
+        let mut values = inputs.into_iter().collect::<Vec<_>>();
+        assert_eq!(self.input_len, values.len());
+        for lut in &self.luts {
+            let index = lut
+                .inputs
+                .iter()
+                .enumerate()
+                .fold(0, |a, (b, x)| a | (usize::from(values[*x]) << b));
+            values.push(((lut.table >> index) & 1) != 0);
+        }
+        self.outputs.iter().map(|(o, n)| values[*o] ^ *n).collect()
+    }
+}
+
+// maximal number of cuts per wire (except trivial cut).
+const MAX_CUTS: usize = 10;
+
+#[derive(Clone, Debug)]
+struct Cut {
+    leaves: Vec<usize>,
+    depth: usize,
+    area_flow: f64,
+}
+
+// values of variables in truth table for 6 variables.
+const VAR_TABLES: [u64; 6] = [
+    0xaaaa_aaaa_aaaa_aaaa,
+    0xcccc_cccc_cccc_cccc,
+    0xf0f0_f0f0_f0f0_f0f0,
+    0xff00_ff00_ff00_ff00,
+    0xffff_0000_ffff_0000,
+    0xffff_ffff_0000_0000,
+];
+
+fn cut_table(circuit: &Circuit<usize>, root: usize, leaves: &[usize]) -> u64 {
+    fn eval_wire(circuit: &Circuit<usize>, wire: usize, values: &mut HashMap<usize, u64>) -> u64 {
+        if let Some(v) = values.get(&wire) {
+            return *v;
+        }
+        let g = circuit.gates()[wire - circuit.input_len()];
+        let v0 = eval_wire(circuit, g.i0, values);
+        let v1 = eval_wire(circuit, g.i1, values);
+        let v = g.eval_args(v0, v1);
+        values.insert(wire, v);
+        v
+    }
+    let mut values = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (*l, VAR_TABLES[i]))
+        .collect::<HashMap<_, _>>();
+    let table = eval_wire(circuit, root, &mut values);
+    if leaves.len() < 6 {
+        table & ((1u64 << (1 << leaves.len())) - 1)
+    } else {
+        table
+    }
+}
+
+/// Maps Gate circuit into LUT circuit with `k`-input LUTs.
+///
+/// Function enumerates cuts with up to `k` leaves (priority cuts) and chooses best cut for
+/// every gate by `goal`. Next it covers circuit from its outputs by chosen cuts.
+/// `k` must be in range 2 to 6.
+pub fn lut_map(circuit: &Circuit<usize>, k: usize, goal: LUTMapGoal) -> LUTCircuit {
+    assert!((2..=6).contains(&k));
+    let input_len = circuit.input_len();
+    let gates = circuit.gates();
+    let wire_num = input_len + gates.len();
+    let mut fanouts = vec![0usize; wire_num];
+    for g in gates {
+        fanouts[g.i0] += 1;
+        fanouts[g.i1] += 1;
+    }
+    for (o, _) in circuit.outputs() {
+        fanouts[*o] += 1;
+    }
+    let cut_key = |c: &Cut| match goal {
+        LUTMapGoal::Depth => (c.depth as f64, c.area_flow, c.leaves.len()),
+        LUTMapGoal::Area => (c.area_flow, c.depth as f64, c.leaves.len()),
+    };
+    // best cut for wire: depth and area flow.
+    let mut best = vec![(0usize, 0.0f64); wire_num];
+    // non-trivial cuts of wires
+    let mut cuts: Vec<Vec<Cut>> = vec![vec![]; wire_num];
+    for (i, g) in gates.iter().enumerate() {
+        let wire = input_len + i;
+        // cuts of inputs: trivial cut and other cuts
+        let input_cuts =
+            |x: usize| std::iter::once(vec![x]).chain(cuts[x].iter().map(|c| c.leaves.clone()));
+        let mut new_cuts = vec![];
+        for c0 in input_cuts(g.i0) {
+            for c1 in input_cuts(g.i1) {
+                let mut leaves = c0.iter().chain(c1.iter()).copied().collect::<Vec<_>>();
+                leaves.sort();
+                leaves.dedup();
+                if leaves.len() > k || new_cuts.iter().any(|c: &Cut| c.leaves == leaves) {
+                    continue;
+                }
+                let depth = 1 + leaves.iter().map(|l| best[*l].0).max().unwrap();
+                let area_flow = 1.0 + leaves.iter().map(|l| best[*l].1).sum::<f64>();
+                new_cuts.push(Cut {
+                    leaves,
+                    depth,
+                    area_flow,
+                });
+            }
+        }
+        new_cuts.sort_by(|a, b| cut_key(a).partial_cmp(&cut_key(b)).unwrap());
+        new_cuts.truncate(MAX_CUTS);
+        best[wire] = (
+            new_cuts[0].depth,
+            new_cuts[0].area_flow / (fanouts[wire].max(1) as f64),
+        );
+        cuts[wire] = new_cuts;
+    }
+    // cover circuit from outputs
+    let mut required = vec![false; wire_num];
+    for (o, _) in circuit.outputs() {
+        required[*o] = true;
+    }
+    for wire in (input_len..wire_num).rev() {
+        if required[wire] {
+            for l in &cuts[wire][0].leaves {
+                required[*l] = true;
+            }
+        }
+    }
+    // generate LUTs
+    let mut wire_map = (0..input_len).map(Some).collect::<Vec<_>>();
+    wire_map.resize(wire_num, None);
+    let mut luts = vec![];
+    for wire in input_len..wire_num {
+        if !required[wire] {
+            continue;
+        }
+        let leaves = &cuts[wire][0].leaves;
+        luts.push(LUT {
+            inputs: leaves.iter().map(|l| wire_map[*l].unwrap()).collect(),
+            table: cut_table(circuit, wire, leaves),
+        });
+        wire_map[wire] = Some(input_len + luts.len() - 1);
+    }
+    LUTCircuit {
+        input_len,
+        luts,
+        outputs: circuit
+            .outputs()
+            .iter()
+            .map(|(o, n)| (wire_map[*o].unwrap(), *n))
+            .collect(),
+    }
+}
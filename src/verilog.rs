@@ -2,13 +2,204 @@
 //! Module to conversion between Gate circuit and the Verilog language.
 
 use crate::gatesim::*;
+use crate::{
+    check_hdl_ports, hdl_controls, hdl_port_bits, hdl_test_vectors, hdl_testbench_ports,
+    AssignEntry, HDLPort, HDLPortDir, HDLReset, HDLTestVectors, HDLTestbenchError, HierCircuit,
+    HierInstance, NameError, WriterNames,
+};
 
-use std::collections::BTreeMap;
-use std::io::{self, BufWriter, Write};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufWriter, Read, Write};
 
+use crate::lutmap::*;
 use crate::vbinopcircuit::*;
-use crate::vcircuit::VGateFunc;
-use crate::VNegs::*;
+use crate::vcircuit::{VGate, VGateFunc};
+use crate::vexpr::*;
+use crate::VNegs::{self, *};
+
+/// Options for conversion to Verilog language source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerilogOptions {
+    /// Length of state. If nonzero then circuit is sequential and state is held
+    /// in registers.
+    pub state_len: usize,
+    /// Name of clock port (used only if circuit is sequential).
+    pub clock: String,
+    /// Reset of registers (used only if circuit is sequential).
+    pub reset: Option<HDLReset>,
+    /// Names of ports and internal wires.
+    pub names: WriterNames,
+    /// Layout of ports. If empty then every circuit input and output (except state) is
+    /// scalar port. Otherwise input ports partition circuit inputs and output ports
+    /// partition circuit outputs (except state) in order. Then circuit inputs and outputs
+    /// are wires connected to bits of ports.
+    pub ports: Vec<HDLPort>,
+}
+
+impl Default for VerilogOptions {
+    fn default() -> Self {
+        Self {
+            state_len: 0,
+            clock: "clk".to_string(),
+            reset: None,
+            names: WriterNames::default(),
+            ports: vec![],
+        }
+    }
+}
+
+const VERILOG_KEYWORDS: [&str; 124] = [
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+// escape name as Verilog identifier: simple identifier or escaped identifier if name
+// is not simple identifier or it is keyword.
+fn verilog_escape(name: &str) -> Result<String, NameError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(NameError::InvalidName(name.to_string()));
+    }
+    let mut chars = name.chars();
+    let first = chars.next().unwrap();
+    if (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !VERILOG_KEYWORDS.contains(&name)
+    {
+        Ok(name.to_string())
+    } else {
+        Ok(format!("\\{} ", name))
+    }
+}
+
+// escaped identifier is same as simple identifier with same name.
+fn verilog_name_key(name: &str) -> String {
+    name.strip_prefix('\\')
+        .map(|n| n.trim_end())
+        .unwrap_or(name)
+        .to_string()
+}
 
 /// Converts circuit to Verilog language source.
 ///
@@ -21,9 +212,279 @@ pub fn to_verilog(
     optimize_negs: bool,
     out: impl Write,
 ) -> io::Result<()> {
-    let input_len = circuit.input_len();
-    let output_len = circuit.outputs().len();
+    to_verilog_with_options(
+        circuit,
+        module_name,
+        optimize_negs,
+        &VerilogOptions::default(),
+        out,
+    )
+}
+
+/// Converts circuit to Verilog language source with options.
+///
+/// Function works like `to_verilog`, but it also uses `options`. If state length is nonzero
+/// then state is held in registers (`reg`) that are updated at rising edge of clock
+/// in `always` block. Module gets clock port and reset port (if reset is given) before
+/// other ports.
+///
+/// Names of ports and wires are given by `names`. Names that are not simple identifiers
+/// or are keywords are written as escaped identifiers. If names collide or name can't be
+/// written then function returns error of kind `InvalidInput` that holds `NameError`.
+/// If layout of ports doesn't partition circuit inputs and outputs or has port with
+/// zero width then function returns error of kind `InvalidInput` that holds
+/// `PortLayoutError`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_verilog_with_options(
+    circuit: Circuit<usize>,
+    module_name: &str,
+    optimize_negs: bool,
+    options: &VerilogOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write_verilog_module(circuit, module_name, optimize_negs, options, &[], &mut out)
+}
+
+/// Converts hierarchical circuit to Verilog language source.
+///
+/// Function works like `to_verilog`, but it writes hierarchical circuit. Sub-circuits are
+/// written as modules with names of sub-circuits. The top module includes instances
+/// of these modules.
+///
+/// If name of sub-circuit is same as name of top module then function returns error of kind
+/// `InvalidInput` that holds `NameError`.
+///
+/// The top circuit inputs are organized in form: `[inputs,instance outputs]`.
+/// The top circuit outputs are organized in form: `[outputs,instance inputs]`.
+pub fn to_verilog_hier(
+    hcircuit: &HierCircuit,
+    module_name: &str,
+    optimize_negs: bool,
+    out: impl Write,
+) -> io::Result<()> {
+    hcircuit.check_top_name(module_name, verilog_name_key)?;
+    let mut out = BufWriter::new(out);
+    for (name, sc) in hcircuit.subcircuits() {
+        write_verilog_module(
+            sc.clone(),
+            name,
+            optimize_negs,
+            &VerilogOptions::default(),
+            &[],
+            &mut out,
+        )?;
+    }
+    write_verilog_module(
+        hcircuit.circuit().clone(),
+        module_name,
+        optimize_negs,
+        &VerilogOptions::default(),
+        &hcircuit.hier_instances(),
+        &mut out,
+    )
+}
+
+/// Converts LUT circuit to Verilog language source.
+///
+/// Function writes LUT circuit in Verilog language to `out`. Every LUT is written as
+/// instance of `LUTk` primitive (where `k` is number of LUT inputs) with `INIT` parameter
+/// that holds truth table of LUT. `module_name` is name of top module.
+pub fn to_verilog_lut(circuit: &LUTCircuit, module_name: &str, out: impl Write) -> io::Result<()> {
+    to_verilog_lut_with_options(circuit, module_name, &VerilogOptions::default(), out)
+}
+
+/// Converts LUT circuit to Verilog language source with options.
+///
+/// Function works like `to_verilog_lut`, but it also uses `options` in this same way as
+/// `to_verilog_with_options`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_verilog_lut_with_options(
+    circuit: &LUTCircuit,
+    module_name: &str,
+    options: &VerilogOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write_verilog_logic(
+        circuit.input_len(),
+        circuit.outputs(),
+        VerilogLogic::LUTs(circuit.luts()),
+        module_name,
+        options,
+        &[],
+        &mut out,
+    )
+}
+
+/// Writes self-checking Verilog testbench for circuit.
+///
+/// Function writes testbench module `{module_name}_tb` to `out`. The testbench instantiates
+/// module `module_name` written by `to_verilog_with_options` with same `options`, applies
+/// input vectors given by `vectors` and compares module outputs with outputs evaluated
+/// from `circuit`. Every mismatch is displayed. At end testbench displays `PASSED` or
+/// `FAILED` and finishes simulation.
+///
+/// If state length is nonzero then testbench resets module at start and applies rising
+/// edge of clock after every checked vector. State is not part of vectors and it is
+/// evaluated from `circuit` starting from reset values. Hence sequential circuit must have
+/// reset. If circuit is sequential without reset or if `vectors` are exhaustive and number
+/// of inputs (without state) is greater than `MAX_EXHAUSTIVE_INPUTS` then function returns
+/// error of kind `InvalidInput` that holds `HDLTestbenchError`. Errors of names and layout
+/// of ports are returned like in `to_verilog_with_options`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_verilog_testbench(
+    circuit: &Circuit<usize>,
+    module_name: &str,
+    options: &VerilogOptions,
+    vectors: HDLTestVectors,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let state_len = options.state_len;
+    assert!(state_len <= circuit.input_len());
+    assert!(state_len <= circuit.outputs().len());
+    let input_len = circuit.input_len() - state_len;
+    let output_len = circuit.outputs().len() - state_len;
+    let reset = if state_len != 0 {
+        Some(options.reset.as_ref().ok_or(HDLTestbenchError::NoReset)?)
+    } else {
+        None
+    };
+    let init_state = (0..state_len)
+        .map(|i| {
+            reset
+                .and_then(|r| r.values.get(i).copied())
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    let tests = hdl_test_vectors(circuit, &init_state, vectors)?;
+    // names of ports of module
+    let mut controls = hdl_controls(state_len, &options.clock, reset);
+    let control_len = controls.len();
+    controls.extend(options.ports.iter().map(|p| p.name.clone()));
+    let names = options.names.resolve(
+        &controls,
+        circuit.input_len(),
+        circuit.outputs().len(),
+        [],
+        verilog_escape,
+        verilog_name_key,
+    )?;
+    if !options.ports.is_empty() {
+        check_hdl_ports(&options.ports, input_len, output_len)?;
+    }
+    let ports = hdl_testbench_ports(
+        &options.ports,
+        &names.controls[control_len..],
+        &names.inputs[state_len..],
+        &names.outputs[state_len..],
+    );
+    writeln!(out, "module {}_tb;", module_name)?;
+    if state_len != 0 {
+        out.write_all(b"    reg clock;\n    reg reset;\n")?;
+    }
+    if input_len != 0 {
+        writeln!(out, "    reg [{}:0] inputs;", input_len - 1)?;
+    }
+    if output_len != 0 {
+        writeln!(out, "    wire [{}:0] outputs;", output_len - 1)?;
+    }
+    out.write_all(b"    integer errors;\n")?;
+    let conns = names.controls[0..control_len]
+        .iter()
+        .zip(["clock", "reset"])
+        .map(|(name, signal)| format!(".{}({})", name, signal))
+        .chain(ports.iter().map(|(name, dir, start, width)| {
+            let signal = if *dir == HDLPortDir::Input {
+                "inputs"
+            } else {
+                "outputs"
+            };
+            if *width == 1 {
+                format!(".{}({}[{}])", name, signal, start)
+            } else {
+                format!(".{}({}[{}:{}])", name, signal, start + width - 1, start)
+            }
+        }))
+        .collect::<Vec<_>>();
+    writeln!(out, "    {} dut ({});", module_name, conns.join(", "))?;
+    out.write_all(b"    initial begin\n        errors = 0;\n")?;
+    if state_len != 0 {
+        out.write_all(
+            b"        clock = 0;\n        reset = 1;\n        #1;\n        clock = 1;\n        \
+            #1;\n        clock = 0;\n        reset = 0;\n",
+        )?;
+    }
+    for (inputs, outputs) in tests {
+        if input_len != 0 {
+            writeln!(out, "        inputs = {}'b{};", input_len, inputs)?;
+        }
+        out.write_all(b"        #1;\n")?;
+        if output_len != 0 {
+            writeln!(
+                out,
+                "        if (outputs !== {}'b{}) begin",
+                output_len, outputs
+            )?;
+            writeln!(
+                out,
+                "            $display(\"Error: inputs %b: outputs %b, expected %b\", {}, \
+                outputs, {}'b{});",
+                if input_len != 0 { "inputs" } else { "0" },
+                output_len,
+                outputs
+            )?;
+            out.write_all(b"            errors = errors + 1;\n        end\n")?;
+        }
+        if state_len != 0 {
+            out.write_all(b"        clock = 1;\n        #1;\n        clock = 0;\n")?;
+        }
+    }
+    out.write_all(
+        br#"        if (errors == 0)
+            $display("PASSED");
+        else
+            $display("FAILED: %0d errors", errors);
+        $finish;
+    end
+endmodule
+"#,
+    )?;
+    Ok(())
+}
+
+// logic of module: gates with negations or LUTs. Outputs of gates or LUTs are next wires
+// after circuit inputs.
+enum VerilogLogic<'a> {
+    Gates(&'a [(VGate<usize>, VNegs)]),
+    LUTs(&'a [LUT]),
+}
+
+impl VerilogLogic<'_> {
+    fn len(&self) -> usize {
+        match self {
+            VerilogLogic::Gates(gates) => gates.len(),
+            VerilogLogic::LUTs(luts) => luts.len(),
+        }
+    }
+}
 
+// write module with instances of sub-circuits.
+fn write_verilog_module(
+    circuit: Circuit<usize>,
+    module_name: &str,
+    optimize_negs: bool,
+    options: &VerilogOptions,
+    instances: &[HierInstance],
+    out: &mut impl Write,
+) -> io::Result<()> {
     let circuit = {
         let mut circuit = VBinOpCircuit::from(circuit);
         if optimize_negs {
@@ -31,11 +492,38 @@ pub fn to_verilog(
         }
         circuit
     };
+    write_verilog_logic(
+        circuit.input_len,
+        &circuit.outputs,
+        VerilogLogic::Gates(&circuit.gates),
+        module_name,
+        options,
+        instances,
+        out,
+    )
+}
+
+// write module with logic and instances of sub-circuits. Top circuit inputs that are
+// instance outputs and top circuit outputs that are instance inputs are wires.
+fn write_verilog_logic(
+    input_len: usize,
+    outputs: &[(usize, bool)],
+    logic: VerilogLogic,
+    module_name: &str,
+    options: &VerilogOptions,
+    instances: &[HierInstance],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let output_len = outputs.len();
+    let port_input_len = input_len - instances.iter().map(|i| i.output_len).sum::<usize>();
+    let port_output_len = output_len - instances.iter().map(|i| i.input_len).sum::<usize>();
+    let state_len = options.state_len;
+    assert!(state_len <= port_input_len);
+    assert!(state_len <= port_output_len);
 
-    let mut out = BufWriter::new(out);
     let mut wire_out_map = BTreeMap::new();
     let mut dup_map = vec![];
-    for (oi, (o, n)) in circuit.outputs.iter().enumerate() {
+    for (oi, (o, n)) in outputs.iter().enumerate() {
         if let Some((old_oi, _)) = wire_out_map.get(&(*o, *n)) {
             // resolve duplicate
             dup_map.push((oi, *old_oi));
@@ -43,77 +531,871 @@ pub fn to_verilog(
             wire_out_map.insert((*o, *n), (oi, *n));
         }
     }
+    // names of ports and wires
+    let mut controls = hdl_controls(state_len, &options.clock, options.reset.as_ref());
+    let control_len = controls.len();
+    controls.extend(options.ports.iter().map(|p| p.name.clone()));
+    let names = options.names.resolve(
+        &controls,
+        input_len,
+        output_len,
+        (input_len..input_len + logic.len()).filter(|wi| !wire_out_map.contains_key(&(*wi, false))),
+        verilog_escape,
+        verilog_name_key,
+    )?;
+    // instance inputs that are top circuit inputs - they are connected directly
+    let direct_input = |oi: usize| {
+        let (o, n) = outputs[oi];
+        oi >= port_output_len && !n && o < input_len
+    };
+    let instance_input = |oi: usize| {
+        if direct_input(oi) {
+            &names.inputs[outputs[oi].0]
+        } else {
+            &names.outputs[oi]
+        }
+    };
     let resolve_name = |i| {
-        if let Some((oi, _)) = wire_out_map.get(&(i, false)) {
-            format!("o{}", oi)
+        if let Some((oi, _)) = wire_out_map.get(&(i, false)).filter(|_| i >= input_len) {
+            &names.outputs[*oi]
+        } else if i < input_len {
+            &names.inputs[i]
         } else {
-            format!("i{}", i)
+            names.wire(i)
         }
     };
+    // bits of ports from layout
+    if !options.ports.is_empty() {
+        check_hdl_ports(
+            &options.ports,
+            port_input_len - state_len,
+            port_output_len - state_len,
+        )?;
+    }
+    let (input_bits, output_bits) = hdl_port_bits(
+        &options.ports,
+        &names.controls[control_len..],
+        state_len,
+        |name, b| format!("{}[{}]", name, b),
+    );
+    // ports: clock and reset (if circuit is sequential), inputs and outputs
+    let ports = if options.ports.is_empty() {
+        names
+            .controls
+            .iter()
+            .chain(&names.inputs[state_len..port_input_len])
+            .chain(&names.outputs[state_len..port_output_len])
+            .collect::<Vec<_>>()
+    } else {
+        names.controls.iter().collect::<Vec<_>>()
+    };
     // module declaration
     writeln!(out, "module {} (", module_name)?;
-    for i in 0..input_len + output_len {
-        if i < input_len {
-            write!(out, "    i{}", i)?;
+    for (i, port) in ports.iter().enumerate() {
+        write!(out, "    {}", port)?;
+        if i + 1 < ports.len() {
+            out.write_all(b",")?;
         } else {
-            write!(out, "    o{}", i - input_len)?;
+            out.write_all(b");")?;
         }
-        if i + 1 < input_len + output_len {
-            out.write(b",")?;
-        } else {
-            out.write(b");")?;
-        }
-        out.write(b"\n")?;
+        out.write_all(b"\n")?;
     }
-    if input_len + output_len == 0 {
-        out.write(b"    );\n")?;
+    if ports.is_empty() {
+        out.write_all(b"    );\n")?;
     }
     // input and output definitions
-    for i in 0..input_len {
-        writeln!(out, "    input i{};", i)?;
+    if options.ports.is_empty() {
+        for name in names
+            .controls
+            .iter()
+            .chain(&names.inputs[state_len..port_input_len])
+        {
+            writeln!(out, "    input {};", name)?;
+        }
+        for name in &names.outputs[state_len..port_output_len] {
+            writeln!(out, "    output {};", name)?;
+        }
+    } else {
+        for name in &names.controls[0..control_len] {
+            writeln!(out, "    input {};", name)?;
+        }
+        for (port, name) in options.ports.iter().zip(&names.controls[control_len..]) {
+            let dir = if port.dir == HDLPortDir::Input {
+                "input"
+            } else {
+                "output"
+            };
+            if port.width == 1 {
+                writeln!(out, "    {} {};", dir, name)?;
+            } else {
+                writeln!(out, "    {} [{}:0] {};", dir, port.width - 1, name)?;
+            }
+        }
+        // circuit inputs and outputs connected to bits of ports
+        for name in names.inputs[state_len..port_input_len]
+            .iter()
+            .chain(&names.outputs[state_len..port_output_len])
+        {
+            writeln!(out, "    wire {};", name)?;
+        }
     }
-    for i in 0..output_len {
-        writeln!(out, "    output o{};", i)?;
+    // state registers and next state definitions
+    for name in &names.inputs[0..state_len] {
+        writeln!(out, "    reg {};", name)?;
+    }
+    for name in &names.outputs[0..state_len] {
+        writeln!(out, "    wire {};", name)?;
+    }
+    // instance outputs and inputs definitions
+    for name in &names.inputs[port_input_len..input_len] {
+        writeln!(out, "    wire {};", name)?;
+    }
+    for i in (port_output_len..output_len).filter(|i| !direct_input(*i)) {
+        writeln!(out, "    wire {};", names.outputs[i])?;
     }
     // wires definitions
-    for i in 0..circuit.gates.len() {
+    for i in 0..logic.len() {
         let wi = input_len + i;
         if wire_out_map.contains_key(&(wi, false)) {
             continue;
         }
-        writeln!(out, "    wire i{};", wi)?;
+        writeln!(out, "    wire {};", names.wire(wi))?;
+    }
+    // connect bits of input ports
+    for (bit, i) in &input_bits {
+        writeln!(out, "    assign {} = {};", names.inputs[*i], bit)?;
     }
-    // gates assignments
-    for (i, (g, n)) in circuit.gates.iter().enumerate() {
-        let op = match g.func {
-            VGateFunc::And => "&",
-            VGateFunc::Or => "|",
-            VGateFunc::Xor => "^",
-            _ => {
-                panic!("Unexpected!");
+    match logic {
+        VerilogLogic::Gates(gates) => {
+            // gates assignments
+            for (i, (g, n)) in gates.iter().enumerate() {
+                let op = match g.func {
+                    VGateFunc::And => "&",
+                    VGateFunc::Or => "|",
+                    VGateFunc::Xor => "^",
+                    _ => {
+                        panic!("Unexpected!");
+                    }
+                };
+                writeln!(
+                    out,
+                    "    assign {} = {}({} {} {}{});",
+                    resolve_name(i + input_len),
+                    if *n == NegOutput { "~" } else { "" },
+                    resolve_name(g.i0),
+                    op,
+                    if *n == NegInput1 { "~" } else { "" },
+                    resolve_name(g.i1)
+                )?;
             }
-        };
-        writeln!(
-            out,
-            "    assign {} = {}({} {} {}{});",
-            resolve_name(i + input_len),
-            if *n == NegOutput { "~" } else { "" },
-            resolve_name(g.i0),
-            op,
-            if *n == NegInput1 { "~" } else { "" },
-            resolve_name(g.i1)
-        )?;
+        }
+        VerilogLogic::LUTs(luts) => {
+            // LUT instances
+            for (i, lut) in luts.iter().enumerate() {
+                let lut_len = lut.inputs.len();
+                write!(
+                    out,
+                    "    LUT{} #(.INIT({}'h{:0width$x})) lut{} (.O({})",
+                    lut_len,
+                    1 << lut_len,
+                    lut.table,
+                    i,
+                    resolve_name(input_len + i),
+                    width = (1usize << lut_len).div_ceil(4)
+                )?;
+                for (j, x) in lut.inputs.iter().enumerate() {
+                    write!(out, ", .I{}({})", j, resolve_name(*x))?;
+                }
+                out.write_all(b");\n")?;
+            }
+        }
     }
-    // generate negations
+    // generate negations and outputs that are inputs
     for ((o, _), (oi, n)) in &wire_out_map {
         if *n {
-            writeln!(out, "    assign o{} = ~{};", *oi, resolve_name(*o))?;
+            writeln!(
+                out,
+                "    assign {} = ~{};",
+                names.outputs[*oi],
+                resolve_name(*o)
+            )?;
+        } else if *o < input_len && !direct_input(*oi) {
+            writeln!(
+                out,
+                "    assign {} = {};",
+                names.outputs[*oi],
+                resolve_name(*o)
+            )?;
         }
     }
     // generate output duplicates
-    for (oi, old_oi) in dup_map {
-        writeln!(out, "    assign o{} = o{};", oi, old_oi)?;
+    for (oi, old_oi) in dup_map.into_iter().filter(|(oi, _)| !direct_input(*oi)) {
+        writeln!(
+            out,
+            "    assign {} = {};",
+            names.outputs[oi], names.outputs[old_oi]
+        )?;
+    }
+    // connect bits of output ports
+    for (bit, oi) in &output_bits {
+        writeln!(out, "    assign {} = {};", bit, names.outputs[*oi])?;
+    }
+    // generate state registers updates
+    if state_len != 0 {
+        let clock = &names.controls[0];
+        let reset = options.reset.as_ref().map(|r| (&names.controls[1], r));
+        if let Some((reset_name, _)) = reset.filter(|(_, r)| r.asynchronous) {
+            writeln!(
+                out,
+                "    always @(posedge {} or posedge {}) begin",
+                clock, reset_name
+            )?;
+        } else {
+            writeln!(out, "    always @(posedge {}) begin", clock)?;
+        }
+        if let Some((reset_name, reset)) = reset {
+            writeln!(out, "        if ({}) begin", reset_name)?;
+            for i in 0..state_len {
+                writeln!(
+                    out,
+                    "            {} <= 1'b{};",
+                    names.inputs[i],
+                    u8::from(reset.values.get(i).copied().unwrap_or(false))
+                )?;
+            }
+            out.write_all(b"        end else begin\n")?;
+            for i in 0..state_len {
+                writeln!(
+                    out,
+                    "            {} <= {};",
+                    names.inputs[i], names.outputs[i]
+                )?;
+            }
+            out.write_all(b"        end\n")?;
+        } else {
+            for i in 0..state_len {
+                writeln!(out, "        {} <= {};", names.inputs[i], names.outputs[i])?;
+            }
+        }
+        out.write_all(b"    end\n")?;
+    }
+    // generate instances of sub-circuits
+    for (k, inst) in instances.iter().enumerate() {
+        write!(out, "    {} inst{} (", inst.name, k)?;
+        let ports = (0..inst.input_len)
+            .map(|i| format!(".i{}({})", i, instance_input(inst.input_start + i)))
+            .chain(
+                (0..inst.output_len)
+                    .map(|i| format!(".o{}({})", i, names.inputs[inst.output_start + i])),
+            )
+            .collect::<Vec<_>>();
+        writeln!(out, "{});", ports.join(", "))?;
     }
-    out.write(b"endmodule\n")?;
+    out.write_all(b"endmodule\n")?;
     Ok(())
 }
+
+/// Verilog error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum VerilogError {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unexpected end of file.
+    #[error("Unexpected end of file")]
+    UnexpectedEOF,
+    /// If no module in source.
+    #[error("No module")]
+    NoModule,
+    /// If more than one module in source.
+    #[error("{0}: Only one module is supported")]
+    MultipleModules(usize),
+    /// If unsupported construction.
+    #[error("{0}: Unsupported {1}")]
+    Unsupported(usize, String),
+    /// If port is not declared as input or output.
+    #[error("Port {0} without direction")]
+    PortWithoutDirection(String),
+    /// If net is already declared.
+    #[error("{0}: Net {1} already declared")]
+    NetAlreadyDeclared(usize, String),
+    /// If net is already driven.
+    #[error("{0}: Net {1} already driven")]
+    NetAlreadyDriven(usize, String),
+    /// If net is driven by nothing.
+    #[error("Net {0} is undriven")]
+    UndrivenNet(String),
+    /// If bit select is out of range.
+    #[error("{0}: Bit select {1} out of range")]
+    BitSelectOutOfRange(usize, String),
+    /// If vector is used without bit select.
+    #[error("{0}: Vector {1} without bit select")]
+    VectorWithoutBitSelect(usize, String),
+    /// If module have cycle.
+    #[error("Cycle in module caused by {0}")]
+    CycleInModule(String),
+}
+
+// mapping: key - name of port bit, value - assignment in circuit
+type VerilogMap = Vec<(String, AssignEntry)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum VToken {
+    Ident(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+const VERILOG_SYMBOLS: [&str; 15] = [
+    "~^", "^~", "(", ")", "[", "]", ",", ";", ":", "=", "~", "&", "|", "^", "?",
+];
+
+fn verilog_tokens(source: &str) -> Result<Vec<(usize, VToken)>, VerilogError> {
+    let mut tokens = vec![];
+    let mut line_no = 1;
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        if c == '\n' {
+            line_no += 1;
+        } else if c.is_whitespace() {
+            continue;
+        } else if source[pos..].starts_with("//") {
+            while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+        } else if source[pos..].starts_with("/*") {
+            chars.next();
+            let mut prev = ' ';
+            loop {
+                match chars.next() {
+                    Some((_, '/')) if prev == '*' => break,
+                    Some((_, c)) => {
+                        if c == '\n' {
+                            line_no += 1;
+                        }
+                        prev = c;
+                    }
+                    None => return Err(VerilogError::UnexpectedEOF),
+                }
+            }
+        } else if c == '\\' {
+            // escaped identifier - ends at whitespace
+            let mut name = String::new();
+            while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace()) {
+                name.push(c);
+            }
+            if name.is_empty() {
+                return Err(VerilogError::SyntaxError(line_no, "\\".to_string()));
+            }
+            tokens.push((line_no, VToken::Ident(name)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = c.to_string();
+            while let Some((_, c)) =
+                chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_' || *c == '$')
+            {
+                name.push(c);
+            }
+            tokens.push((line_no, VToken::Ident(name)));
+        } else if c.is_ascii_digit() || c == '\'' {
+            let mut num = c.to_string();
+            while let Some((_, c)) =
+                chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '\'' || *c == '_')
+            {
+                num.push(c);
+            }
+            tokens.push((line_no, VToken::Number(num)));
+        } else if let Some(sym) = VERILOG_SYMBOLS
+            .iter()
+            .find(|s| source[pos..].starts_with(**s))
+        {
+            if sym.len() > 1 {
+                chars.next();
+            }
+            tokens.push((line_no, VToken::Symbol(sym)));
+        } else {
+            return Err(VerilogError::SyntaxError(line_no, c.to_string()));
+        }
+    }
+    Ok(tokens)
+}
+
+// parse number (decimal or sized literal) - returns value and width
+fn parse_verilog_number(num: &str) -> Option<(u64, Option<usize>)> {
+    let num = num.replace('_', "");
+    if let Some(p) = num.find('\'') {
+        let width = if p != 0 {
+            Some(num[0..p].parse::<usize>().ok()?)
+        } else {
+            None
+        };
+        let rest = num[p + 1..].trim_start_matches(['s', 'S']);
+        let radix = match rest.chars().next()? {
+            'b' | 'B' => 2,
+            'o' | 'O' => 8,
+            'd' | 'D' => 10,
+            'h' | 'H' => 16,
+            _ => return None,
+        };
+        Some((u64::from_str_radix(&rest[1..], radix).ok()?, width))
+    } else {
+        Some((num.parse::<u64>().ok()?, None))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VPortDir {
+    Input,
+    Output,
+}
+
+struct VerilogParser {
+    tokens: Vec<(usize, VToken)>,
+    pos: usize,
+    // nets: key - name, value - range (msb, lsb) if vector
+    nets: HashMap<String, Option<(usize, usize)>>,
+    ports: Vec<String>,
+    port_dirs: HashMap<String, VPortDir>,
+    // drivers: key - net bit name
+    drivers: HashMap<String, VExpr>,
+}
+
+impl VerilogParser {
+    fn line_no(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(l, _)| *l)
+            .unwrap_or(1)
+    }
+
+    fn peek(&self) -> Option<&VToken> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<VToken, VerilogError> {
+        if let Some((_, t)) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            Ok(t.clone())
+        } else {
+            Err(VerilogError::UnexpectedEOF)
+        }
+    }
+
+    fn is_symbol(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(VToken::Symbol(s)) if *s == sym)
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(VToken::Ident(s)) if s == name)
+    }
+
+    fn expect_symbol(&mut self, sym: &str) -> Result<(), VerilogError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VToken::Symbol(s) if s == sym => Ok(()),
+            t => Err(VerilogError::SyntaxError(
+                line_no,
+                format!("expected '{}', found {:?}", sym, t),
+            )),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, VerilogError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VToken::Ident(s) => Ok(s),
+            t => Err(VerilogError::SyntaxError(
+                line_no,
+                format!("expected identifier, found {:?}", t),
+            )),
+        }
+    }
+
+    fn expect_index(&mut self) -> Result<usize, VerilogError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VToken::Number(n) => parse_verilog_number(&n)
+                .and_then(|(v, _)| usize::try_from(v).ok())
+                .ok_or(VerilogError::SyntaxError(line_no, n)),
+            t => Err(VerilogError::SyntaxError(
+                line_no,
+                format!("expected number, found {:?}", t),
+            )),
+        }
+    }
+
+    // parse optional range [msb:lsb]
+    fn parse_range(&mut self) -> Result<Option<(usize, usize)>, VerilogError> {
+        if self.is_symbol("[") {
+            self.next()?;
+            let msb = self.expect_index()?;
+            self.expect_symbol(":")?;
+            let lsb = self.expect_index()?;
+            self.expect_symbol("]")?;
+            Ok(Some((msb, lsb)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn declare(
+        &mut self,
+        line_no: usize,
+        name: String,
+        range: Option<(usize, usize)>,
+        dir: Option<VPortDir>,
+    ) -> Result<(), VerilogError> {
+        if let Some(dir) = dir {
+            if self.port_dirs.insert(name.clone(), dir).is_some() {
+                return Err(VerilogError::NetAlreadyDeclared(line_no, name));
+            }
+            self.nets.insert(name, range);
+        } else if let Some(old_range) = self.nets.get(&name) {
+            // wire declaration of port is allowed if it have same range
+            if !self.port_dirs.contains_key(&name) || *old_range != range {
+                return Err(VerilogError::NetAlreadyDeclared(line_no, name));
+            }
+        } else {
+            self.nets.insert(name, range);
+        }
+        Ok(())
+    }
+
+    // parse declaration after input/output/wire keyword
+    fn parse_decl(&mut self, dir: Option<VPortDir>, in_header: bool) -> Result<(), VerilogError> {
+        if self.is_ident("wire") {
+            self.next()?;
+        }
+        if self.is_ident("reg") {
+            return Err(VerilogError::Unsupported(self.line_no(), "reg".to_string()));
+        }
+        let range = self.parse_range()?;
+        loop {
+            let line_no = self.line_no();
+            let name = self.expect_ident()?;
+            if in_header {
+                self.ports.push(name.clone());
+            }
+            self.declare(line_no, name, range, dir)?;
+            // in header next declaration can start after comma
+            if self.is_symbol(",") {
+                self.next()?;
+                if in_header
+                    && (self.is_ident("input") || self.is_ident("output") || self.is_ident("inout"))
+                {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+        if !in_header {
+            self.expect_symbol(";")?;
+        }
+        Ok(())
+    }
+
+    // parse net reference with optional bit select. Returns bit name.
+    fn parse_net_ref(&mut self) -> Result<String, VerilogError> {
+        let line_no = self.line_no();
+        let name = self.expect_ident()?;
+        let range = self.nets.get(&name).copied().flatten();
+        if self.is_symbol("[") {
+            self.next()?;
+            let idx = self.expect_index()?;
+            self.expect_symbol("]")?;
+            if let Some((msb, lsb)) = range {
+                if idx < msb.min(lsb) || idx > msb.max(lsb) {
+                    return Err(VerilogError::BitSelectOutOfRange(
+                        line_no,
+                        format!("{}[{}]", name, idx),
+                    ));
+                }
+                Ok(format!("{}[{}]", name, idx))
+            } else {
+                Err(VerilogError::BitSelectOutOfRange(
+                    line_no,
+                    format!("{}[{}]", name, idx),
+                ))
+            }
+        } else if range.is_some() {
+            Err(VerilogError::VectorWithoutBitSelect(line_no, name))
+        } else {
+            Ok(name)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<VExpr, VerilogError> {
+        let line_no = self.line_no();
+        if self.is_symbol("(") {
+            self.next()?;
+            let e = self.parse_expr()?;
+            self.expect_symbol(")")?;
+            Ok(e)
+        } else if self.is_symbol("~") {
+            self.next()?;
+            Ok(VExpr::Not(Box::new(self.parse_primary()?)))
+        } else if let Some(VToken::Number(n)) = self.peek().cloned() {
+            self.next()?;
+            match parse_verilog_number(&n) {
+                Some((v, width)) if v < 2 && width.unwrap_or(1) == 1 => Ok(VExpr::Value(v != 0)),
+                _ => Err(VerilogError::Unsupported(
+                    line_no,
+                    format!("constant {}", n),
+                )),
+            }
+        } else {
+            Ok(VExpr::Net(self.parse_net_ref()?))
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<VExpr, VerilogError> {
+        // operator levels: 0 - '|', 1 - '^' and '~^', 2 - '&'.
+        if level == 3 {
+            return self.parse_primary();
+        }
+        let mut e = self.parse_binary(level + 1)?;
+        loop {
+            let op = match (level, self.peek()) {
+                (0, Some(VToken::Symbol("|"))) => "|",
+                (1, Some(VToken::Symbol("^"))) => "^",
+                (1, Some(VToken::Symbol("~^"))) | (1, Some(VToken::Symbol("^~"))) => "~^",
+                (2, Some(VToken::Symbol("&"))) => "&",
+                _ => break,
+            };
+            self.next()?;
+            let e2 = self.parse_binary(level + 1)?;
+            e = match op {
+                "|" => VExpr::Or(vec![e, e2]),
+                "^" => VExpr::Xor(vec![e, e2]),
+                "~^" => VExpr::Not(Box::new(VExpr::Xor(vec![e, e2]))),
+                _ => VExpr::And(vec![e, e2]),
+            };
+        }
+        Ok(e)
+    }
+
+    fn parse_expr(&mut self) -> Result<VExpr, VerilogError> {
+        let c = self.parse_binary(0)?;
+        if self.is_symbol("?") {
+            self.next()?;
+            let t = self.parse_expr()?;
+            self.expect_symbol(":")?;
+            let e = self.parse_expr()?;
+            Ok(VExpr::Ite(Box::new(c), Box::new(t), Box::new(e)))
+        } else {
+            Ok(c)
+        }
+    }
+
+    fn drive(&mut self, line_no: usize, name: String, expr: VExpr) -> Result<(), VerilogError> {
+        if matches!(self.port_dirs.get(&name), Some(VPortDir::Input)) {
+            return Err(VerilogError::NetAlreadyDriven(line_no, name));
+        }
+        if self.drivers.insert(name.clone(), expr).is_some() {
+            return Err(VerilogError::NetAlreadyDriven(line_no, name));
+        }
+        Ok(())
+    }
+
+    fn parse_gate_instance(&mut self, gate: &str) -> Result<(), VerilogError> {
+        loop {
+            let line_no = self.line_no();
+            // optional instance name
+            if !self.is_symbol("(") {
+                self.expect_ident()?;
+            }
+            self.expect_symbol("(")?;
+            let mut terms = vec![];
+            loop {
+                terms.push(self.parse_expr()?);
+                if self.is_symbol(",") {
+                    self.next()?;
+                } else {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+            if terms.len() < 2 {
+                return Err(VerilogError::SyntaxError(
+                    line_no,
+                    format!("too few terminals in {}", gate),
+                ));
+            }
+            let term_name = |e: VExpr| {
+                if let VExpr::Net(n) = e {
+                    Ok(n)
+                } else {
+                    Err(VerilogError::SyntaxError(
+                        line_no,
+                        "expected net as output".to_string(),
+                    ))
+                }
+            };
+            if gate == "buf" || gate == "not" {
+                // last terminal is input, rest are outputs
+                let input = terms.pop().unwrap();
+                for t in terms {
+                    let expr = if gate == "not" {
+                        VExpr::Not(Box::new(input.clone()))
+                    } else {
+                        input.clone()
+                    };
+                    self.drive(line_no, term_name(t)?, expr)?;
+                }
+            } else {
+                let inputs = terms.split_off(1);
+                let expr = match gate {
+                    "and" => VExpr::And(inputs),
+                    "nand" => VExpr::Not(Box::new(VExpr::And(inputs))),
+                    "or" => VExpr::Or(inputs),
+                    "nor" => VExpr::Not(Box::new(VExpr::Or(inputs))),
+                    "xor" => VExpr::Xor(inputs),
+                    _ => VExpr::Not(Box::new(VExpr::Xor(inputs))),
+                };
+                let output = terms.pop().unwrap();
+                self.drive(line_no, term_name(output)?, expr)?;
+            }
+            if self.is_symbol(",") {
+                self.next()?;
+            } else {
+                break;
+            }
+        }
+        self.expect_symbol(";")
+    }
+
+    fn parse_module(&mut self) -> Result<(), VerilogError> {
+        if !self.is_ident("module") {
+            return if self.peek().is_none() {
+                Err(VerilogError::NoModule)
+            } else {
+                Err(VerilogError::SyntaxError(
+                    self.line_no(),
+                    "expected module".to_string(),
+                ))
+            };
+        }
+        self.next()?;
+        self.expect_ident()?;
+        // module header
+        if self.is_symbol("(") {
+            self.next()?;
+            while !self.is_symbol(")") {
+                let line_no = self.line_no();
+                if self.is_ident("input") {
+                    self.next()?;
+                    self.parse_decl(Some(VPortDir::Input), true)?;
+                } else if self.is_ident("output") {
+                    self.next()?;
+                    self.parse_decl(Some(VPortDir::Output), true)?;
+                } else if self.is_ident("inout") {
+                    return Err(VerilogError::Unsupported(line_no, "inout".to_string()));
+                } else {
+                    let name = self.expect_ident()?;
+                    self.ports.push(name);
+                    if self.is_symbol(",") {
+                        self.next()?;
+                    }
+                }
+            }
+            self.next()?;
+        }
+        self.expect_symbol(";")?;
+        // module items
+        loop {
+            let line_no = self.line_no();
+            let item = self.expect_ident()?;
+            match item.as_str() {
+                "endmodule" => break,
+                "input" => self.parse_decl(Some(VPortDir::Input), false)?,
+                "output" => self.parse_decl(Some(VPortDir::Output), false)?,
+                "wire" => self.parse_decl(None, false)?,
+                "assign" => {
+                    loop {
+                        let line_no = self.line_no();
+                        let name = self.parse_net_ref()?;
+                        self.expect_symbol("=")?;
+                        let expr = self.parse_expr()?;
+                        self.drive(line_no, name, expr)?;
+                        if self.is_symbol(",") {
+                            self.next()?;
+                        } else {
+                            break;
+                        }
+                    }
+                    self.expect_symbol(";")?;
+                }
+                "and" | "nand" | "or" | "nor" | "xor" | "xnor" | "not" | "buf" => {
+                    self.parse_gate_instance(&item)?
+                }
+                _ => return Err(VerilogError::Unsupported(line_no, item)),
+            }
+        }
+        if self.peek().is_some() {
+            return Err(VerilogError::MultipleModules(self.line_no()));
+        }
+        Ok(())
+    }
+
+    // returns names of bits of net
+    fn net_bits(&self, name: &str) -> Vec<String> {
+        if let Some(Some((msb, lsb))) = self.nets.get(name) {
+            (*msb.min(lsb)..=*msb.max(lsb))
+                .map(|i| format!("{}[{}]", name, i))
+                .collect()
+        } else {
+            vec![name.to_string()]
+        }
+    }
+}
+
+fn from_verilog_int(parser: &VerilogParser) -> Result<(Circuit<usize>, VerilogMap), VerilogError> {
+    for port in &parser.ports {
+        if !parser.port_dirs.contains_key(port) {
+            return Err(VerilogError::PortWithoutDirection(port.clone()));
+        }
+    }
+    let port_bits = |dir: VPortDir| {
+        parser
+            .ports
+            .iter()
+            .filter(|p| parser.port_dirs[*p] == dir)
+            .flat_map(|p| parser.net_bits(p))
+            .collect::<Vec<_>>()
+    };
+    let inputs = port_bits(VPortDir::Input);
+    let outputs = port_bits(VPortDir::Output);
+    vexprs_to_circuit(inputs, outputs, &parser.drivers).map_err(|e| match e {
+        VNetError::UndrivenNet(name) => VerilogError::UndrivenNet(name),
+        VNetError::Cycle(name) => VerilogError::CycleInModule(name),
+    })
+}
+
+/// Converts structural Verilog source to Gate circuit.
+///
+/// `input` is read stream with Verilog source that contains single gate-level module.
+/// Supported module items are: `input`, `output` and `wire` declarations (scalars or
+/// vectors), continuous assignments (`assign`) with operators `~`, `&`, `|`, `^`, `~^`, `?:`
+/// and instances of primitives `and`, `or`, `xor`, `nand`, `nor`, `xnor`, `not` and `buf`.
+/// Vector nets must be used with bit select. Bits of vector are named in form `name[index]`.
+///
+/// Circuit inputs are module inputs and circuit outputs are module outputs in order of ports.
+/// Function returns Gate circuit with its mapping. Mapping in form: key - name of module
+/// input or output, value - assignment in circuit.
+pub fn from_verilog(input: impl Read) -> Result<(Circuit<usize>, VerilogMap), VerilogError> {
+    let mut source = String::new();
+    let mut input = input;
+    input.read_to_string(&mut source)?;
+    let mut parser = VerilogParser {
+        tokens: verilog_tokens(&source)?,
+        pos: 0,
+        nets: HashMap::new(),
+        ports: vec![],
+        port_dirs: HashMap::new(),
+        drivers: HashMap::new(),
+    };
+    parser.parse_module()?;
+    from_verilog_int(&parser)
+}
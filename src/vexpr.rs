@@ -0,0 +1,167 @@
+// vexpr.rs - expressions of HDL netlists
+
+use crate::gatesim::*;
+use crate::AssignEntry;
+use gategen::boolvar::*;
+use gategen::dynintvar::*;
+
+use std::collections::{HashMap, HashSet};
+
+// mapping: key - name of net, value - assignment in circuit
+pub(crate) type VNetMap = Vec<(String, AssignEntry)>;
+
+/// VExpr - boolean expression that drives net in HDL netlist. Nets are identified by
+/// names of single bits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VExpr {
+    Value(bool),
+    Net(String),
+    Not(Box<VExpr>),
+    And(Vec<VExpr>),
+    Or(Vec<VExpr>),
+    Xor(Vec<VExpr>),
+    Ite(Box<VExpr>, Box<VExpr>, Box<VExpr>),
+}
+
+impl VExpr {
+    pub(crate) fn nets<'a>(&'a self, out: &mut Vec<&'a String>) {
+        match self {
+            VExpr::Value(_) => (),
+            VExpr::Net(n) => out.push(n),
+            VExpr::Not(e) => e.nets(out),
+            VExpr::And(es) | VExpr::Or(es) | VExpr::Xor(es) => {
+                for e in es {
+                    e.nets(out);
+                }
+            }
+            VExpr::Ite(c, t, e) => {
+                c.nets(out);
+                t.nets(out);
+                e.nets(out);
+            }
+        }
+    }
+
+    pub(crate) fn eval(&self, values: &HashMap<String, BoolVarSys>) -> BoolVarSys {
+        match self {
+            VExpr::Value(v) => BoolVarSys::from(*v),
+            VExpr::Net(n) => values[n].clone(),
+            VExpr::Not(e) => !e.eval(values),
+            VExpr::And(es) => es
+                .iter()
+                .fold(BoolVarSys::from(true), |a, e| a & e.eval(values)),
+            VExpr::Or(es) => es
+                .iter()
+                .fold(BoolVarSys::from(false), |a, e| a | e.eval(values)),
+            VExpr::Xor(es) => es
+                .iter()
+                .fold(BoolVarSys::from(false), |a, e| a ^ e.eval(values)),
+            VExpr::Ite(c, t, e) => bool_opt_ite(c.eval(values), t.eval(values), e.eval(values)),
+        }
+    }
+}
+
+/// Errors of evaluation of netlist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum VNetError {
+    UndrivenNet(String),
+    Cycle(String),
+}
+
+fn vexprs_to_circuit_int(
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    drivers: &HashMap<String, VExpr>,
+) -> Result<(Circuit<usize>, VNetMap), VNetError> {
+    let mut values = HashMap::<String, BoolVarSys>::from_iter(
+        inputs.iter().map(|name| (name.clone(), BoolVarSys::var())),
+    );
+    let input_vars = inputs
+        .iter()
+        .map(|name| values[name].clone())
+        .collect::<Vec<_>>();
+    // evaluate nets - stack entry: (name, children pushed)
+    let mut path_visited = HashSet::<String>::new();
+    let mut stack = vec![];
+    for output in &outputs {
+        stack.push((output.clone(), false));
+        while let Some((name, expanded)) = stack.pop() {
+            if values.contains_key(&name) {
+                continue;
+            }
+            let expr = drivers
+                .get(&name)
+                .ok_or_else(|| VNetError::UndrivenNet(name.clone()))?;
+            if expanded {
+                let value = expr.eval(&values);
+                values.insert(name.clone(), value);
+                path_visited.remove(&name);
+            } else {
+                path_visited.insert(name.clone());
+                stack.push((name.clone(), true));
+                let mut nets = vec![];
+                expr.nets(&mut nets);
+                for net in nets {
+                    if path_visited.contains(net) {
+                        return Err(VNetError::Cycle(net.clone()));
+                    }
+                    if !values.contains_key(net) {
+                        stack.push((net.clone(), false));
+                    }
+                }
+            }
+        }
+    }
+    let output_values = outputs
+        .iter()
+        .map(|name| values[name].clone())
+        .collect::<Vec<_>>();
+    let filtered_outputs = output_values
+        .iter()
+        .filter(|x| x.value().is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    let outint = if !filtered_outputs.is_empty() {
+        UDynVarSys::from_iter(filtered_outputs)
+    } else {
+        UDynVarSys::var(0)
+    };
+    let (circuit, assign_map) = outint.to_translated_circuit_with_map(input_vars.into_iter());
+    let mut out_count = 0;
+    let mapping = inputs
+        .into_iter()
+        .zip(assign_map)
+        .map(|(name, idx)| {
+            (
+                name,
+                if let Some(idx) = idx {
+                    AssignEntry::Var(idx, false)
+                } else {
+                    AssignEntry::NoMap
+                },
+            )
+        })
+        .chain(outputs.into_iter().zip(output_values).map(|(name, v)| {
+            if let Some(v) = v.value() {
+                (name, AssignEntry::Value(v))
+            } else {
+                let (o, n) = circuit.outputs()[out_count];
+                out_count += 1;
+                (name, AssignEntry::Var(o, n))
+            }
+        }))
+        .collect::<Vec<_>>();
+    Ok((circuit, mapping))
+}
+
+/// Generates circuit from netlist. `drivers` are expressions that drive nets (keys are net
+/// names). Circuit inputs are `inputs` and circuit outputs are `outputs`.
+/// Returns circuit with mapping in form: key - input or output name,
+/// value - assignment in circuit.
+pub(crate) fn vexprs_to_circuit(
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    drivers: &HashMap<String, VExpr>,
+) -> Result<(Circuit<usize>, VNetMap), VNetError> {
+    callsys(|| vexprs_to_circuit_int(inputs.clone(), outputs.clone(), drivers))
+}
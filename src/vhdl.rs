@@ -2,14 +2,178 @@
 //! Module to conversion between Gate circuit and the VHDL language.
 
 use crate::gatesim::*;
+use crate::{
+    check_hdl_ports, hdl_controls, hdl_port_bits, hdl_test_vectors, hdl_testbench_ports,
+    AssignEntry, HDLPort, HDLPortDir, HDLReset, HDLTestVectors, HDLTestbenchError, HierCircuit,
+    HierInstance, NameError, WriterNames,
+};
 
-use std::collections::BTreeMap;
-use std::io::{self, BufWriter, Write};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufWriter, Read, Write};
 
 use crate::vbinopcircuit::*;
 use crate::vcircuit::VGateFunc;
+use crate::vexpr::*;
 use crate::VNegs::*;
 
+/// Options for conversion to VHDL language source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VHDLOptions {
+    /// Length of state. If nonzero then circuit is sequential and state is held
+    /// in registers.
+    pub state_len: usize,
+    /// Name of clock port (used only if circuit is sequential).
+    pub clock: String,
+    /// Reset of registers (used only if circuit is sequential).
+    pub reset: Option<HDLReset>,
+    /// Names of ports and internal signals.
+    pub names: WriterNames,
+    /// Layout of ports. If empty then every circuit input and output (except state) is
+    /// scalar port. Otherwise input ports partition circuit inputs and output ports
+    /// partition circuit outputs (except state) in order. Then circuit inputs and outputs
+    /// are signals connected to bits of ports.
+    pub ports: Vec<HDLPort>,
+}
+
+impl Default for VHDLOptions {
+    fn default() -> Self {
+        Self {
+            state_len: 0,
+            clock: "clk".to_string(),
+            reset: None,
+            names: WriterNames::default(),
+            ports: vec![],
+        }
+    }
+}
+
+const VHDL_RESERVED_WORDS: [&str; 97] = [
+    "abs",
+    "access",
+    "after",
+    "alias",
+    "all",
+    "and",
+    "architecture",
+    "array",
+    "assert",
+    "attribute",
+    "begin",
+    "block",
+    "body",
+    "buffer",
+    "bus",
+    "case",
+    "component",
+    "configuration",
+    "constant",
+    "disconnect",
+    "downto",
+    "else",
+    "elsif",
+    "end",
+    "entity",
+    "exit",
+    "file",
+    "for",
+    "function",
+    "generate",
+    "generic",
+    "group",
+    "guarded",
+    "if",
+    "impure",
+    "in",
+    "inertial",
+    "inout",
+    "is",
+    "label",
+    "library",
+    "linkage",
+    "literal",
+    "loop",
+    "map",
+    "mod",
+    "nand",
+    "new",
+    "next",
+    "nor",
+    "not",
+    "null",
+    "of",
+    "on",
+    "open",
+    "or",
+    "others",
+    "out",
+    "package",
+    "port",
+    "postponed",
+    "procedure",
+    "process",
+    "pure",
+    "range",
+    "record",
+    "register",
+    "reject",
+    "rem",
+    "report",
+    "return",
+    "rol",
+    "ror",
+    "select",
+    "severity",
+    "shared",
+    "signal",
+    "sla",
+    "sll",
+    "sra",
+    "srl",
+    "subtype",
+    "then",
+    "to",
+    "transport",
+    "type",
+    "unaffected",
+    "units",
+    "until",
+    "use",
+    "variable",
+    "wait",
+    "when",
+    "while",
+    "with",
+    "xnor",
+    "xor",
+];
+
+// escape name as VHDL identifier: basic identifier or extended identifier if name
+// is not basic identifier or it is reserved word.
+fn vhdl_escape(name: &str) -> Result<String, NameError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return Err(NameError::InvalidName(name.to_string()));
+    }
+    if name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.contains("__")
+        && !name.ends_with('_')
+        && !VHDL_RESERVED_WORDS.contains(&name.to_ascii_lowercase().as_str())
+    {
+        Ok(name.to_string())
+    } else {
+        Ok(format!("\\{}\\", name.replace('\\', "\\\\")))
+    }
+}
+
+// basic identifiers are case insensitive, extended identifiers are case sensitive.
+fn vhdl_name_key(name: &str) -> String {
+    if name.starts_with('\\') {
+        name.to_string()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
 /// Converts circuit to Verilog language source.
 ///
 /// Function writes Gate circuit logic in Verilog language to `out`. `circuit` is circuit
@@ -22,9 +186,292 @@ pub fn to_vhdl(
     arch_name: &str,
     optimize_negs: bool,
     out: impl Write,
+) -> io::Result<()> {
+    to_vhdl_with_options(
+        circuit,
+        entity_name,
+        arch_name,
+        optimize_negs,
+        &VHDLOptions::default(),
+        out,
+    )
+}
+
+/// Converts circuit to VHDL language source with options.
+///
+/// Function works like `to_vhdl`, but it also uses `options`. If state length is nonzero
+/// then state is held in internal signals that are registered in process at rising edge
+/// of clock. Entity gets clock port and reset port (if reset is given) before other ports.
+///
+/// Names of ports and signals are given by `names`. Names that are not basic identifiers
+/// or are reserved words are written as extended identifiers. Basic identifiers are
+/// compared without case. If names collide or name can't be written then function returns
+/// error of kind `InvalidInput` that holds `NameError`.
+/// If layout of ports doesn't partition circuit inputs and outputs or has port with
+/// zero width then function returns error of kind `InvalidInput` that holds
+/// `PortLayoutError`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_vhdl_with_options(
+    circuit: Circuit<usize>,
+    entity_name: &str,
+    arch_name: &str,
+    optimize_negs: bool,
+    options: &VHDLOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    write_vhdl_entity(
+        circuit,
+        entity_name,
+        arch_name,
+        optimize_negs,
+        options,
+        &[],
+        &mut out,
+    )
+}
+
+/// Converts hierarchical circuit to VHDL language source.
+///
+/// Function works like `to_vhdl`, but it writes hierarchical circuit. Sub-circuits are
+/// written as entities with names of sub-circuits. The top entity includes instances
+/// of these entities as components.
+///
+/// If name of sub-circuit is same as name of top entity then function returns error of kind
+/// `InvalidInput` that holds `NameError`.
+///
+/// The top circuit inputs are organized in form: `[inputs,instance outputs]`.
+/// The top circuit outputs are organized in form: `[outputs,instance inputs]`.
+pub fn to_vhdl_hier(
+    hcircuit: &HierCircuit,
+    entity_name: &str,
+    arch_name: &str,
+    optimize_negs: bool,
+    out: impl Write,
+) -> io::Result<()> {
+    hcircuit.check_top_name(entity_name, vhdl_name_key)?;
+    let mut out = BufWriter::new(out);
+    for (name, sc) in hcircuit.subcircuits() {
+        write_vhdl_entity(
+            sc.clone(),
+            name,
+            arch_name,
+            optimize_negs,
+            &VHDLOptions::default(),
+            &[],
+            &mut out,
+        )?;
+    }
+    write_vhdl_entity(
+        hcircuit.circuit().clone(),
+        entity_name,
+        arch_name,
+        optimize_negs,
+        &VHDLOptions::default(),
+        &hcircuit.hier_instances(),
+        &mut out,
+    )
+}
+
+/// Writes self-checking VHDL testbench for circuit.
+///
+/// Function writes testbench entity `{entity_name}_tb` to `out`. The testbench instantiates
+/// entity `entity_name` written by `to_vhdl_with_options` with same `options`, applies
+/// input vectors given by `vectors` and compares entity outputs with outputs evaluated
+/// from `circuit`. Every mismatch is reported with severity error. At end testbench reports
+/// `PASSED` or `FAILED` (with severity failure).
+///
+/// If state length is nonzero then testbench resets entity at start and applies rising
+/// edge of clock after every checked vector. State is not part of vectors and it is
+/// evaluated from `circuit` starting from reset values. Hence sequential circuit must have
+/// reset. If circuit is sequential without reset or if `vectors` are exhaustive and number
+/// of inputs (without state) is greater than `MAX_EXHAUSTIVE_INPUTS` then function returns
+/// error of kind `InvalidInput` that holds `HDLTestbenchError`. Errors of names and layout
+/// of ports are returned like in `to_vhdl_with_options`.
+///
+/// The circuit inputs are organized in form: `[state,inputs]`.
+/// The circuit outputs are organized in form: `[state,outputs]`.
+pub fn to_vhdl_testbench(
+    circuit: &Circuit<usize>,
+    entity_name: &str,
+    arch_name: &str,
+    options: &VHDLOptions,
+    vectors: HDLTestVectors,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let state_len = options.state_len;
+    assert!(state_len <= circuit.input_len());
+    assert!(state_len <= circuit.outputs().len());
+    let input_len = circuit.input_len() - state_len;
+    let output_len = circuit.outputs().len() - state_len;
+    let reset = if state_len != 0 {
+        Some(options.reset.as_ref().ok_or(HDLTestbenchError::NoReset)?)
+    } else {
+        None
+    };
+    let init_state = (0..state_len)
+        .map(|i| {
+            reset
+                .and_then(|r| r.values.get(i).copied())
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    let tests = hdl_test_vectors(circuit, &init_state, vectors)?;
+    // names of ports of entity
+    let mut controls = hdl_controls(state_len, &options.clock, reset);
+    let control_len = controls.len();
+    controls.extend(options.ports.iter().map(|p| p.name.clone()));
+    let names = options.names.resolve(
+        &controls,
+        circuit.input_len(),
+        circuit.outputs().len(),
+        [],
+        vhdl_escape,
+        vhdl_name_key,
+    )?;
+    if !options.ports.is_empty() {
+        check_hdl_ports(&options.ports, input_len, output_len)?;
+    }
+    let ports = hdl_testbench_ports(
+        &options.ports,
+        &names.controls[control_len..],
+        &names.inputs[state_len..],
+        &names.outputs[state_len..],
+    );
+    out.write_all(b"library ieee;\nuse ieee.std_logic_1164.all;\n")?;
+    writeln!(out, "entity {0}_tb is\nend {0}_tb;", entity_name)?;
+    writeln!(out, "architecture {} of {}_tb is", arch_name, entity_name)?;
+    if state_len != 0 {
+        out.write_all(b"    signal clock : std_logic;\n    signal reset : std_logic;\n")?;
+    }
+    if input_len != 0 {
+        writeln!(
+            out,
+            "    signal inputs : std_logic_vector({} downto 0);",
+            input_len - 1
+        )?;
+    }
+    if output_len != 0 {
+        writeln!(
+            out,
+            "    signal outputs : std_logic_vector({} downto 0);",
+            output_len - 1
+        )?;
+    }
+    out.write_all(b"begin\n")?;
+    let conns = names.controls[0..control_len]
+        .iter()
+        .zip(["clock", "reset"])
+        .map(|(name, signal)| format!("{} => {}", name, signal))
+        .chain(ports.iter().map(|(name, dir, start, width)| {
+            let signal = if *dir == HDLPortDir::Input {
+                "inputs"
+            } else {
+                "outputs"
+            };
+            if *width == 1 {
+                format!("{} => {}({})", name, signal, start)
+            } else {
+                format!(
+                    "{} => {}({} downto {})",
+                    name,
+                    signal,
+                    start + width - 1,
+                    start
+                )
+            }
+        }))
+        .collect::<Vec<_>>();
+    if conns.is_empty() {
+        writeln!(out, "    dut: entity work.{};", entity_name)?;
+    } else {
+        writeln!(
+            out,
+            "    dut: entity work.{} port map ({});",
+            entity_name,
+            conns.join(", ")
+        )?;
+    }
+    out.write_all(b"    process\n        variable errors : natural := 0;\n    begin\n")?;
+    if state_len != 0 {
+        out.write_all(
+            b"        clock <= '0';\n        reset <= '1';\n        wait for 1 ns;\n        \
+            clock <= '1';\n        wait for 1 ns;\n        clock <= '0';\n        \
+            reset <= '0';\n",
+        )?;
+    }
+    for (inputs, outputs) in tests {
+        if input_len != 0 {
+            writeln!(out, "        inputs <= \"{}\";", inputs)?;
+        }
+        out.write_all(b"        wait for 1 ns;\n")?;
+        if output_len != 0 {
+            writeln!(out, "        if outputs /= \"{}\" then", outputs)?;
+            writeln!(
+                out,
+                "            report \"Error: inputs {}: expected outputs {}\" severity error;",
+                inputs, outputs
+            )?;
+            out.write_all(b"            errors := errors + 1;\n        end if;\n")?;
+        }
+        if state_len != 0 {
+            out.write_all(
+                b"        clock <= '1';\n        wait for 1 ns;\n        clock <= '0';\n",
+            )?;
+        }
+    }
+    out.write_all(
+        br#"        if errors = 0 then
+            report "PASSED";
+        else
+            report "FAILED: " & integer'image(errors) & " errors" severity failure;
+        end if;
+        wait;
+    end process;
+"#,
+    )?;
+    writeln!(out, "end {};", arch_name)?;
+    Ok(())
+}
+
+// write ports of entity or component: declarations of ports.
+fn write_vhdl_ports(ports: &[String], out: &mut impl Write) -> io::Result<()> {
+    out.write_all(b"    port(\n")?;
+    for (i, port) in ports.iter().enumerate() {
+        write!(out, "        {}", port)?;
+        if i + 1 < ports.len() {
+            out.write_all(b";")?;
+        }
+        out.write_all(b"\n")?;
+    }
+    out.write_all(b"    );\n")?;
+    Ok(())
+}
+
+// write entity with instances of sub-circuits. Top circuit inputs that are instance outputs
+// and top circuit outputs that are instance inputs are signals.
+fn write_vhdl_entity(
+    circuit: Circuit<usize>,
+    entity_name: &str,
+    arch_name: &str,
+    optimize_negs: bool,
+    options: &VHDLOptions,
+    instances: &[HierInstance],
+    out: &mut impl Write,
 ) -> io::Result<()> {
     let input_len = circuit.input_len();
     let output_len = circuit.outputs().len();
+    let port_input_len = input_len - instances.iter().map(|i| i.output_len).sum::<usize>();
+    let port_output_len = output_len - instances.iter().map(|i| i.input_len).sum::<usize>();
+    let state_len = options.state_len;
+    assert!(state_len <= port_input_len);
+    assert!(state_len <= port_output_len);
+    let mut controls = hdl_controls(state_len, &options.clock, options.reset.as_ref());
+    let control_len = controls.len();
+    controls.extend(options.ports.iter().map(|p| p.name.clone()));
 
     let circuit = {
         let mut circuit = VBinOpCircuit::from(circuit);
@@ -34,7 +481,6 @@ pub fn to_vhdl(
         circuit
     };
 
-    let mut out = BufWriter::new(out);
     let mut wire_out_map = BTreeMap::new();
     let mut dup_map = vec![];
     for (oi, (o, n)) in circuit.outputs.iter().enumerate() {
@@ -45,42 +491,144 @@ pub fn to_vhdl(
             wire_out_map.insert((*o, *n), (oi, *n));
         }
     }
+    // names of ports and signals
+    let names = options.names.resolve(
+        &controls,
+        input_len,
+        output_len,
+        (input_len..input_len + circuit.gates.len())
+            .filter(|wi| !wire_out_map.contains_key(&(*wi, false))),
+        vhdl_escape,
+        vhdl_name_key,
+    )?;
+    // instance inputs that are top circuit inputs - they are connected directly
+    let direct_input = |oi: usize| {
+        let (o, n) = circuit.outputs[oi];
+        oi >= port_output_len && !n && o < input_len
+    };
+    let instance_input = |oi: usize| {
+        if direct_input(oi) {
+            &names.inputs[circuit.outputs[oi].0]
+        } else {
+            &names.outputs[oi]
+        }
+    };
     let resolve_name = |i| {
-        if let Some((oi, _)) = wire_out_map.get(&(i, false)) {
-            format!("o{}", oi)
+        if let Some((oi, _)) = wire_out_map.get(&(i, false)).filter(|_| i >= input_len) {
+            &names.outputs[*oi]
+        } else if i < input_len {
+            &names.inputs[i]
         } else {
-            format!("i{}", i)
+            names.wire(i)
         }
     };
-    out.write(b"library ieee;\nuse ieee.std_logic_1164.all;\n")?;
+    // bits of ports from layout
+    if !options.ports.is_empty() {
+        check_hdl_ports(
+            &options.ports,
+            port_input_len - state_len,
+            port_output_len - state_len,
+        )?;
+    }
+    let (input_bits, output_bits) = hdl_port_bits(
+        &options.ports,
+        &names.controls[control_len..],
+        state_len,
+        |name, b| format!("{}({})", name, b),
+    );
+    out.write_all(b"library ieee;\nuse ieee.std_logic_1164.all;\n")?;
     // module declaration
     writeln!(out, "entity {} is", entity_name)?;
-    out.write(b"    port(\n")?;
     // input and output definitions
-    for i in 0..input_len + output_len {
-        if i < input_len {
-            write!(out, "        i{} : in std_logic", i)?;
-        } else {
-            write!(out, "        o{} : out std_logic", i - input_len)?;
-        }
-        if i + 1 < input_len + output_len {
-            out.write(b";")?;
+    let mut ports = names.controls[0..control_len]
+        .iter()
+        .map(|c| format!("{} : in std_logic", c))
+        .collect::<Vec<_>>();
+    if options.ports.is_empty() {
+        ports.extend(
+            names.inputs[state_len..port_input_len]
+                .iter()
+                .map(|i| format!("{} : in std_logic", i)),
+        );
+        ports.extend(
+            names.outputs[state_len..port_output_len]
+                .iter()
+                .map(|o| format!("{} : out std_logic", o)),
+        );
+    } else {
+        for (port, name) in options.ports.iter().zip(&names.controls[control_len..]) {
+            let dir = if port.dir == HDLPortDir::Input {
+                "in"
+            } else {
+                "out"
+            };
+            if port.width == 1 {
+                ports.push(format!("{} : {} std_logic", name, dir));
+            } else {
+                ports.push(format!(
+                    "{} : {} std_logic_vector({} downto 0)",
+                    name,
+                    dir,
+                    port.width - 1
+                ));
+            }
         }
-        out.write(b"\n")?;
     }
-    out.write(b"    );\n")?;
+    write_vhdl_ports(&ports, out)?;
     writeln!(out, "end {};", entity_name)?;
     // architecture definition
     writeln!(out, "architecture {} of {} is", arch_name, entity_name)?;
+    // components of sub-circuits
+    for (k, inst) in instances.iter().enumerate() {
+        if instances[0..k].iter().any(|i| i.name == inst.name) {
+            continue;
+        }
+        writeln!(out, "    component {}", inst.name)?;
+        write_vhdl_ports(
+            &(0..inst.input_len)
+                .map(|i| format!("i{} : in std_logic", i))
+                .chain((0..inst.output_len).map(|i| format!("o{} : out std_logic", i)))
+                .collect::<Vec<_>>(),
+            out,
+        )?;
+        out.write_all(b"    end component;\n")?;
+    }
+    // circuit inputs and outputs connected to bits of ports
+    if !options.ports.is_empty() {
+        for name in names.inputs[state_len..port_input_len]
+            .iter()
+            .chain(&names.outputs[state_len..port_output_len])
+        {
+            writeln!(out, "    signal {} : std_logic;", name)?;
+        }
+    }
+    // state registers and next state definitions
+    for name in names.inputs[0..state_len]
+        .iter()
+        .chain(&names.outputs[0..state_len])
+    {
+        writeln!(out, "    signal {} : std_logic;", name)?;
+    }
+    // instance outputs and inputs definitions
+    for name in &names.inputs[port_input_len..input_len] {
+        writeln!(out, "    signal {} : std_logic;", name)?;
+    }
+    for i in (port_output_len..output_len).filter(|i| !direct_input(*i)) {
+        writeln!(out, "    signal {} : std_logic;", names.outputs[i])?;
+    }
     // wires definitions
     for i in 0..circuit.gates.len() {
         let wi = input_len + i;
         if wire_out_map.contains_key(&(wi, false)) {
             continue;
         }
-        writeln!(out, "    signal i{} : std_logic;", wi)?;
+        writeln!(out, "    signal {} : std_logic;", names.wire(wi))?;
+    }
+    out.write_all(b"begin\n")?;
+    // connect bits of input ports
+    for (bit, i) in &input_bits {
+        writeln!(out, "    {} <= {};", names.inputs[*i], bit)?;
     }
-    out.write(b"begin\n")?;
     // gates assignments
     for (i, (g, n)) in circuit.gates.iter().enumerate() {
         let op = match g.func {
@@ -119,16 +667,703 @@ pub fn to_vhdl(
             resolve_name(g.i1),
         )?;
     }
-    // generate negations
+    // generate negations and outputs that are inputs
     for ((o, _), (oi, n)) in &wire_out_map {
         if *n {
-            writeln!(out, "    o{} <= not {};", *oi, resolve_name(*o))?;
+            writeln!(
+                out,
+                "    {} <= not {};",
+                names.outputs[*oi],
+                resolve_name(*o)
+            )?;
+        } else if *o < input_len && !direct_input(*oi) {
+            writeln!(out, "    {} <= {};", names.outputs[*oi], resolve_name(*o))?;
         }
     }
     // generate output duplicates
-    for (oi, old_oi) in dup_map {
-        writeln!(out, "    o{} <= o{};", oi, old_oi)?;
+    for (oi, old_oi) in dup_map.into_iter().filter(|(oi, _)| !direct_input(*oi)) {
+        writeln!(
+            out,
+            "    {} <= {};",
+            names.outputs[oi], names.outputs[old_oi]
+        )?;
+    }
+    // connect bits of output ports
+    for (bit, oi) in &output_bits {
+        writeln!(out, "    {} <= {};", bit, names.outputs[*oi])?;
+    }
+    // generate state registers process
+    if state_len != 0 {
+        let clock = &names.controls[0];
+        let write_assigns = |out: &mut dyn Write, indent: &str, reset: Option<&HDLReset>| {
+            for i in 0..state_len {
+                if let Some(reset) = reset {
+                    let value = reset.values.get(i).copied().unwrap_or(false);
+                    writeln!(
+                        out,
+                        "{}{} <= '{}';",
+                        indent,
+                        names.inputs[i],
+                        u8::from(value)
+                    )?;
+                } else {
+                    writeln!(
+                        out,
+                        "{}{} <= {};",
+                        indent, names.inputs[i], names.outputs[i]
+                    )?;
+                }
+            }
+            Ok::<_, io::Error>(())
+        };
+        match &options.reset {
+            Some(reset) if reset.asynchronous => {
+                let reset_name = &names.controls[1];
+                writeln!(out, "    process({}, {})\n    begin", clock, reset_name)?;
+                writeln!(out, "        if {} = '1' then", reset_name)?;
+                write_assigns(out, "            ", Some(reset))?;
+                writeln!(out, "        elsif rising_edge({}) then", clock)?;
+                write_assigns(out, "            ", None)?;
+                out.write_all(b"        end if;\n")?;
+            }
+            Some(reset) => {
+                writeln!(out, "    process({})\n    begin", clock)?;
+                writeln!(out, "        if rising_edge({}) then", clock)?;
+                writeln!(out, "            if {} = '1' then", names.controls[1])?;
+                write_assigns(out, "                ", Some(reset))?;
+                out.write_all(b"            else\n")?;
+                write_assigns(out, "                ", None)?;
+                out.write_all(b"            end if;\n")?;
+                out.write_all(b"        end if;\n")?;
+            }
+            None => {
+                writeln!(out, "    process({})\n    begin", clock)?;
+                writeln!(out, "        if rising_edge({}) then", clock)?;
+                write_assigns(out, "            ", None)?;
+                out.write_all(b"        end if;\n")?;
+            }
+        }
+        out.write_all(b"    end process;\n")?;
+    }
+    // generate instances of sub-circuits
+    for (k, inst) in instances.iter().enumerate() {
+        let ports = (0..inst.input_len)
+            .map(|i| format!("i{} => {}", i, instance_input(inst.input_start + i)))
+            .chain(
+                (0..inst.output_len)
+                    .map(|i| format!("o{} => {}", i, names.inputs[inst.output_start + i])),
+            )
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "    inst{}: {} port map ({});",
+            k,
+            inst.name,
+            ports.join(", ")
+        )?;
     }
     writeln!(out, "end {};", arch_name)?;
     Ok(())
 }
+
+/// VHDL error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum VHDLError {
+    /// If IO error.
+    #[error("IO error: {0}")]
+    IOError(#[from] io::Error),
+    /// If syntax error.
+    #[error("{0}: Syntax error: {1}")]
+    SyntaxError(usize, String),
+    /// If unexpected end of file.
+    #[error("Unexpected end of file")]
+    UnexpectedEOF,
+    /// If no entity in source.
+    #[error("No entity")]
+    NoEntity,
+    /// If no architecture in source.
+    #[error("No architecture")]
+    NoArchitecture,
+    /// If more than one entity or architecture in source.
+    #[error("{0}: Only one entity and one architecture are supported")]
+    MultipleDesignUnits(usize),
+    /// If architecture is defined for other entity.
+    #[error("{0}: Architecture of unknown entity {1}")]
+    UnknownEntity(usize, String),
+    /// If unsupported construction.
+    #[error("{0}: Unsupported {1}")]
+    Unsupported(usize, String),
+    /// If signal is already declared.
+    #[error("{0}: Signal {1} already declared")]
+    SignalAlreadyDeclared(usize, String),
+    /// If signal is not declared.
+    #[error("{0}: Signal {1} is undeclared")]
+    UndeclaredSignal(usize, String),
+    /// If signal is already driven.
+    #[error("{0}: Signal {1} already driven")]
+    SignalAlreadyDriven(usize, String),
+    /// If signal is driven by nothing.
+    #[error("Signal {0} is undriven")]
+    UndrivenSignal(String),
+    /// If index is out of range.
+    #[error("{0}: Index {1} out of range")]
+    IndexOutOfRange(usize, String),
+    /// If vector is used without index.
+    #[error("{0}: Vector {1} without index")]
+    VectorWithoutIndex(usize, String),
+    /// If architecture have cycle.
+    #[error("Cycle in architecture caused by {0}")]
+    CycleInArchitecture(String),
+}
+
+// mapping: key - name of port bit, value - assignment in circuit
+type VHDLMap = Vec<(String, AssignEntry)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum VHDLToken {
+    // basic identifiers are converted to lowercase
+    Ident(String),
+    Number(usize),
+    Char(char),
+    Symbol(&'static str),
+}
+
+const VHDL_SYMBOLS: [&str; 10] = ["<=", "/=", ":=", "(", ")", ";", ":", ",", "=", "."];
+
+fn vhdl_tokens(source: &str) -> Result<Vec<(usize, VHDLToken)>, VHDLError> {
+    let mut tokens = vec![];
+    let mut line_no = 1;
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        if c == '\n' {
+            line_no += 1;
+        } else if c.is_whitespace() {
+            continue;
+        } else if source[pos..].starts_with("--") {
+            while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+        } else if c == '\\' {
+            // extended identifier - case sensitive, doubled backslash is backslash
+            let mut name = c.to_string();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => {
+                        name.push('\\');
+                        if chars.next_if(|(_, c)| *c == '\\').is_none() {
+                            break;
+                        }
+                    }
+                    Some((_, '\n')) | None => {
+                        return Err(VHDLError::SyntaxError(line_no, name));
+                    }
+                    Some((_, c)) => name.push(c),
+                }
+            }
+            tokens.push((line_no, VHDLToken::Ident(name)));
+        } else if c.is_ascii_alphabetic() {
+            let mut name = c.to_ascii_lowercase().to_string();
+            while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                name.push(c.to_ascii_lowercase());
+            }
+            tokens.push((line_no, VHDLToken::Ident(name)));
+        } else if c.is_ascii_digit() {
+            let mut num = c.to_string();
+            while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_digit() || *c == '_') {
+                if c != '_' {
+                    num.push(c);
+                }
+            }
+            let num = num
+                .parse::<usize>()
+                .map_err(|_| VHDLError::SyntaxError(line_no, num.clone()))?;
+            tokens.push((line_no, VHDLToken::Number(num)));
+        } else if c == '\'' {
+            match (chars.next(), chars.next()) {
+                (Some((_, ch)), Some((_, '\''))) => tokens.push((line_no, VHDLToken::Char(ch))),
+                _ => return Err(VHDLError::SyntaxError(line_no, "'".to_string())),
+            }
+        } else if let Some(sym) = VHDL_SYMBOLS.iter().find(|s| source[pos..].starts_with(**s)) {
+            if sym.len() > 1 {
+                chars.next();
+            }
+            tokens.push((line_no, VHDLToken::Symbol(sym)));
+        } else {
+            return Err(VHDLError::SyntaxError(line_no, c.to_string()));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VHDLPortDir {
+    Input,
+    Output,
+}
+
+struct VHDLParser {
+    tokens: Vec<(usize, VHDLToken)>,
+    pos: usize,
+    entity: Option<String>,
+    have_arch: bool,
+    // signals: key - name, value - range (low, high) if vector
+    signals: HashMap<String, Option<(usize, usize)>>,
+    ports: Vec<(String, VHDLPortDir)>,
+    // drivers: key - signal bit name
+    drivers: HashMap<String, VExpr>,
+}
+
+impl VHDLParser {
+    fn line_no(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map(|(l, _)| *l)
+            .unwrap_or(1)
+    }
+
+    fn peek(&self) -> Option<&VHDLToken> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next(&mut self) -> Result<VHDLToken, VHDLError> {
+        if let Some((_, t)) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            Ok(t.clone())
+        } else {
+            Err(VHDLError::UnexpectedEOF)
+        }
+    }
+
+    fn is_symbol(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(VHDLToken::Symbol(s)) if *s == sym)
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(VHDLToken::Ident(s)) if s == name)
+    }
+
+    fn expect_symbol(&mut self, sym: &str) -> Result<(), VHDLError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VHDLToken::Symbol(s) if s == sym => Ok(()),
+            t => Err(VHDLError::SyntaxError(
+                line_no,
+                format!("expected '{}', found {:?}", sym, t),
+            )),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, VHDLError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VHDLToken::Ident(s) => Ok(s),
+            t => Err(VHDLError::SyntaxError(
+                line_no,
+                format!("expected identifier, found {:?}", t),
+            )),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), VHDLError> {
+        let line_no = self.line_no();
+        if self.expect_ident()? == keyword {
+            Ok(())
+        } else {
+            Err(VHDLError::SyntaxError(
+                line_no,
+                format!("expected {}", keyword),
+            ))
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<usize, VHDLError> {
+        let line_no = self.line_no();
+        match self.next()? {
+            VHDLToken::Number(n) => Ok(n),
+            t => Err(VHDLError::SyntaxError(
+                line_no,
+                format!("expected number, found {:?}", t),
+            )),
+        }
+    }
+
+    // skip until semicolon
+    fn skip_clause(&mut self) -> Result<(), VHDLError> {
+        while !self.is_symbol(";") {
+            self.next()?;
+        }
+        self.next()?;
+        Ok(())
+    }
+
+    // parse 'end [keyword] [name];'
+    fn parse_end(&mut self, keyword: &str, name: &str) -> Result<(), VHDLError> {
+        self.expect_keyword("end")?;
+        if self.is_ident(keyword) {
+            self.next()?;
+        }
+        if !self.is_symbol(";") {
+            let line_no = self.line_no();
+            if self.expect_ident()? != name {
+                return Err(VHDLError::SyntaxError(
+                    line_no,
+                    format!("expected end of {}", name),
+                ));
+            }
+        }
+        self.expect_symbol(";")
+    }
+
+    // parse type: returns range if vector
+    fn parse_type(&mut self) -> Result<Option<(usize, usize)>, VHDLError> {
+        let line_no = self.line_no();
+        let type_name = self.expect_ident()?;
+        match type_name.as_str() {
+            "std_logic" | "std_ulogic" | "bit" => Ok(None),
+            "std_logic_vector" | "std_ulogic_vector" | "bit_vector" => {
+                self.expect_symbol("(")?;
+                let left = self.expect_number()?;
+                let dir_line_no = self.line_no();
+                let dir = self.expect_ident()?;
+                let right = self.expect_number()?;
+                self.expect_symbol(")")?;
+                match dir.as_str() {
+                    "downto" if left >= right => Ok(Some((right, left))),
+                    "to" if left <= right => Ok(Some((left, right))),
+                    _ => Err(VHDLError::SyntaxError(dir_line_no, dir)),
+                }
+            }
+            _ => Err(VHDLError::Unsupported(line_no, type_name)),
+        }
+    }
+
+    fn declare(
+        &mut self,
+        line_no: usize,
+        name: String,
+        range: Option<(usize, usize)>,
+    ) -> Result<(), VHDLError> {
+        if self.signals.insert(name.clone(), range).is_some() {
+            Err(VHDLError::SignalAlreadyDeclared(line_no, name))
+        } else {
+            Ok(())
+        }
+    }
+
+    // parse list of names with colon
+    fn parse_names(&mut self) -> Result<Vec<(usize, String)>, VHDLError> {
+        let mut names = vec![];
+        loop {
+            names.push((self.line_no(), self.expect_ident()?));
+            if self.is_symbol(",") {
+                self.next()?;
+            } else {
+                break;
+            }
+        }
+        self.expect_symbol(":")?;
+        Ok(names)
+    }
+
+    fn parse_entity(&mut self) -> Result<(), VHDLError> {
+        let name = self.expect_ident()?;
+        self.expect_keyword("is")?;
+        if self.is_ident("generic") {
+            return Err(VHDLError::Unsupported(
+                self.line_no(),
+                "generic".to_string(),
+            ));
+        }
+        if self.is_ident("port") {
+            self.next()?;
+            self.expect_symbol("(")?;
+            while !self.is_symbol(")") {
+                let names = self.parse_names()?;
+                let line_no = self.line_no();
+                let dir = match self.expect_ident()?.as_str() {
+                    "in" => VHDLPortDir::Input,
+                    "out" | "buffer" => VHDLPortDir::Output,
+                    mode => return Err(VHDLError::Unsupported(line_no, mode.to_string())),
+                };
+                let range = self.parse_type()?;
+                for (line_no, name) in names {
+                    self.declare(line_no, name.clone(), range)?;
+                    self.ports.push((name, dir));
+                }
+                if self.is_symbol(";") {
+                    self.next()?;
+                } else {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+            self.expect_symbol(";")?;
+        }
+        self.parse_end("entity", &name)?;
+        self.entity = Some(name);
+        Ok(())
+    }
+
+    // parse signal name with optional index. Returns bit name.
+    fn parse_signal_ref(&mut self) -> Result<String, VHDLError> {
+        let line_no = self.line_no();
+        let name = self.expect_ident()?;
+        let range = if let Some(range) = self.signals.get(&name) {
+            *range
+        } else {
+            return Err(VHDLError::UndeclaredSignal(line_no, name));
+        };
+        if self.is_symbol("(") {
+            self.next()?;
+            let idx = self.expect_number()?;
+            self.expect_symbol(")")?;
+            let bit_name = format!("{}({})", name, idx);
+            match range {
+                Some((low, high)) if low <= idx && idx <= high => Ok(bit_name),
+                _ => Err(VHDLError::IndexOutOfRange(line_no, bit_name)),
+            }
+        } else if range.is_some() {
+            Err(VHDLError::VectorWithoutIndex(line_no, name))
+        } else {
+            Ok(name)
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<VExpr, VHDLError> {
+        let line_no = self.line_no();
+        if self.is_symbol("(") {
+            self.next()?;
+            let e = self.parse_expr()?;
+            self.expect_symbol(")")?;
+            Ok(e)
+        } else if self.is_ident("not") {
+            self.next()?;
+            Ok(VExpr::Not(Box::new(self.parse_primary()?)))
+        } else if let Some(VHDLToken::Char(c)) = self.peek().cloned() {
+            self.next()?;
+            match c {
+                '0' => Ok(VExpr::Value(false)),
+                '1' => Ok(VExpr::Value(true)),
+                _ => Err(VHDLError::Unsupported(line_no, format!("'{}'", c))),
+            }
+        } else {
+            Ok(VExpr::Net(self.parse_signal_ref()?))
+        }
+    }
+
+    fn parse_relation(&mut self) -> Result<VExpr, VHDLError> {
+        let e = self.parse_primary()?;
+        if self.is_symbol("=") || self.is_symbol("/=") {
+            let neq = self.is_symbol("/=");
+            self.next()?;
+            let e2 = self.parse_primary()?;
+            let xor = VExpr::Xor(vec![e, e2]);
+            Ok(if neq { xor } else { VExpr::Not(Box::new(xor)) })
+        } else {
+            Ok(e)
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<VExpr, VHDLError> {
+        let mut e = self.parse_relation()?;
+        let mut last_op: Option<String> = None;
+        while let Some(VHDLToken::Ident(op)) = self.peek().cloned() {
+            if !matches!(op.as_str(), "and" | "or" | "xor" | "nand" | "nor" | "xnor") {
+                break;
+            }
+            let line_no = self.line_no();
+            // operators can't be mixed and nand and nor can't be chained
+            if let Some(last_op) = last_op {
+                if last_op != op || op == "nand" || op == "nor" {
+                    return Err(VHDLError::SyntaxError(
+                        line_no,
+                        format!("{} after {} without parentheses", op, last_op),
+                    ));
+                }
+            }
+            self.next()?;
+            let e2 = self.parse_relation()?;
+            e = match op.as_str() {
+                "and" => VExpr::And(vec![e, e2]),
+                "or" => VExpr::Or(vec![e, e2]),
+                "xor" => VExpr::Xor(vec![e, e2]),
+                "nand" => VExpr::Not(Box::new(VExpr::And(vec![e, e2]))),
+                "nor" => VExpr::Not(Box::new(VExpr::Or(vec![e, e2]))),
+                _ => VExpr::Not(Box::new(VExpr::Xor(vec![e, e2]))),
+            };
+            last_op = Some(op);
+        }
+        Ok(e)
+    }
+
+    // parse expression with optional 'when cond else'
+    fn parse_waveform(&mut self) -> Result<VExpr, VHDLError> {
+        let e = self.parse_expr()?;
+        if self.is_ident("when") {
+            self.next()?;
+            let cond = self.parse_expr()?;
+            self.expect_keyword("else")?;
+            let e2 = self.parse_waveform()?;
+            Ok(VExpr::Ite(Box::new(cond), Box::new(e), Box::new(e2)))
+        } else {
+            Ok(e)
+        }
+    }
+
+    fn parse_architecture(&mut self) -> Result<(), VHDLError> {
+        let name = self.expect_ident()?;
+        self.expect_keyword("of")?;
+        let line_no = self.line_no();
+        let entity = self.expect_ident()?;
+        if self.entity.as_ref() != Some(&entity) {
+            return Err(VHDLError::UnknownEntity(line_no, entity));
+        }
+        self.expect_keyword("is")?;
+        // declarations
+        loop {
+            let line_no = self.line_no();
+            let item = self.expect_ident()?;
+            match item.as_str() {
+                "begin" => break,
+                "signal" => {
+                    let names = self.parse_names()?;
+                    let range = self.parse_type()?;
+                    if !self.is_symbol(";") {
+                        return Err(VHDLError::Unsupported(
+                            self.line_no(),
+                            "signal initial value".to_string(),
+                        ));
+                    }
+                    self.next()?;
+                    for (line_no, name) in names {
+                        self.declare(line_no, name, range)?;
+                    }
+                }
+                _ => return Err(VHDLError::Unsupported(line_no, item)),
+            }
+        }
+        // statements
+        while !self.is_ident("end") {
+            let line_no = self.line_no();
+            // skip optional label
+            if matches!(
+                self.tokens.get(self.pos + 1),
+                Some((_, VHDLToken::Symbol(":")))
+            ) {
+                self.next()?;
+                self.next()?;
+            }
+            if let Some(VHDLToken::Ident(s)) = self.peek() {
+                if matches!(s.as_str(), "process" | "with" | "block" | "generate") {
+                    return Err(VHDLError::Unsupported(line_no, s.clone()));
+                }
+            }
+            let target = self.parse_signal_ref()?;
+            if self
+                .ports
+                .iter()
+                .any(|(p, dir)| *p == target && *dir == VHDLPortDir::Input)
+            {
+                return Err(VHDLError::SignalAlreadyDriven(line_no, target));
+            }
+            self.expect_symbol("<=")?;
+            let expr = self.parse_waveform()?;
+            self.expect_symbol(";")?;
+            if self.drivers.insert(target.clone(), expr).is_some() {
+                return Err(VHDLError::SignalAlreadyDriven(line_no, target));
+            }
+        }
+        self.parse_end("architecture", &name)?;
+        self.have_arch = true;
+        Ok(())
+    }
+
+    fn parse_design_file(&mut self) -> Result<(), VHDLError> {
+        while let Some(t) = self.peek().cloned() {
+            let line_no = self.line_no();
+            match t {
+                VHDLToken::Ident(s) if s == "library" || s == "use" => self.skip_clause()?,
+                VHDLToken::Ident(s) if s == "entity" => {
+                    if self.entity.is_some() {
+                        return Err(VHDLError::MultipleDesignUnits(line_no));
+                    }
+                    self.next()?;
+                    self.parse_entity()?;
+                }
+                VHDLToken::Ident(s) if s == "architecture" => {
+                    if self.have_arch {
+                        return Err(VHDLError::MultipleDesignUnits(line_no));
+                    }
+                    self.next()?;
+                    self.parse_architecture()?;
+                }
+                VHDLToken::Ident(s) => return Err(VHDLError::Unsupported(line_no, s)),
+                t => {
+                    return Err(VHDLError::SyntaxError(
+                        line_no,
+                        format!("unexpected {:?}", t),
+                    ))
+                }
+            }
+        }
+        if self.entity.is_none() {
+            Err(VHDLError::NoEntity)
+        } else if !self.have_arch {
+            Err(VHDLError::NoArchitecture)
+        } else {
+            Ok(())
+        }
+    }
+
+    // returns names of bits of port
+    fn port_bits(&self, dir: VHDLPortDir) -> Vec<String> {
+        self.ports
+            .iter()
+            .filter(|(_, d)| *d == dir)
+            .flat_map(|(name, _)| {
+                if let Some(Some((low, high))) = self.signals.get(name) {
+                    (*low..=*high)
+                        .map(|i| format!("{}({})", name, i))
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![name.clone()]
+                }
+            })
+            .collect()
+    }
+}
+
+/// Converts VHDL source to Gate circuit.
+///
+/// `input` is read stream with VHDL source that contains single entity and its architecture
+/// in dataflow form (subset emitted by `to_vhdl`). Ports and signals can have types
+/// `std_logic`, `std_ulogic`, `bit` or vectors of them. Supported concurrent statements are
+/// signal assignments (`<=`) with operators `not`, `and`, `or`, `xor`, `nand`, `nor`,
+/// `xnor`, `=`, `/=` and conditional assignments (`when ... else`). Vector signals must be
+/// used with index. Bits of vector are named in form `name(index)`. Basic identifiers
+/// are converted to lowercase.
+///
+/// Circuit inputs are entity inputs and circuit outputs are entity outputs in order of ports.
+/// Function returns Gate circuit with its mapping. Mapping in form: key - name of entity port,
+/// value - assignment in circuit.
+pub fn from_vhdl(input: impl Read) -> Result<(Circuit<usize>, VHDLMap), VHDLError> {
+    let mut source = String::new();
+    let mut input = input;
+    input.read_to_string(&mut source)?;
+    let mut parser = VHDLParser {
+        tokens: vhdl_tokens(&source)?,
+        pos: 0,
+        entity: None,
+        have_arch: false,
+        signals: HashMap::new(),
+        ports: vec![],
+        drivers: HashMap::new(),
+    };
+    parser.parse_design_file()?;
+    let inputs = parser.port_bits(VHDLPortDir::Input);
+    let outputs = parser.port_bits(VHDLPortDir::Output);
+    vexprs_to_circuit(inputs, outputs, &parser.drivers).map_err(|e| match e {
+        VNetError::UndrivenNet(name) => VHDLError::UndrivenSignal(name),
+        VNetError::Cycle(name) => VHDLError::CycleInArchitecture(name),
+    })
+}
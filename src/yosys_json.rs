@@ -0,0 +1,582 @@
+#![cfg_attr(docsrs, feature(doc_cfg))]
+//! Module to conversion between Gate circuit and the Yosys JSON netlist format.
+//!
+//! Yosys JSON netlist (written by `write_json` command) describes modules with ports, cells
+//! and net names. Nets are identified by bit numbers. Only fine-grained cells
+//! (`$_AND_`, `$_OR_`, `$_XOR_`, `$_NOT_`, `$_NAND_`, `$_NOR_`, `$_XNOR_`, `$_ANDNOT_`,
+//! `$_ORNOT_`, `$_MUX_` and D flip-flops) are supported.
+
+use crate::gatesim::*;
+use crate::{check_hdl_ports, AssignEntry, HDLPort, HDLPortDir, NameError};
+
+use serde_json::{json, Map, Value};
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufWriter, Read, Write};
+
+use crate::vexpr::*;
+
+/// Options for Yosys JSON writer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YosysJSONOptions {
+    /// Length of state. If nonzero then circuit is sequential and state is held
+    /// in D flip-flops (`$_DFF_P_` cells).
+    pub state_len: usize,
+    /// Name of clock port (used only if circuit is sequential).
+    pub clock: String,
+    /// Layout of ports. If empty then every circuit input and output (except state) is
+    /// port with width 1. Otherwise input ports partition circuit inputs and output ports
+    /// partition circuit outputs (except state) in order.
+    pub ports: Vec<HDLPort>,
+}
+
+impl Default for YosysJSONOptions {
+    fn default() -> Self {
+        Self {
+            state_len: 0,
+            clock: "clk".to_string(),
+            ports: vec![],
+        }
+    }
+}
+
+// cell in form: name, type, connections (port, direction and bit).
+type YosysCell = (
+    String,
+    &'static str,
+    Vec<(&'static str, &'static str, usize)>,
+);
+
+/// Converts circuit to Yosys JSON netlist.
+///
+/// Function writes Gate circuit as Yosys JSON netlist to `out`. `circuit` is circuit
+/// to convert. `module_name` is name of the module. Every gate is written as one cell:
+/// `$_AND_`, `$_NOR_`, `$_ANDNOT_` or `$_XOR_`. Negated outputs are written by `$_NOT_` cells.
+/// Circuit inputs are ports `i{n}` and circuit outputs are ports `o{n}`.
+pub fn to_yosys_json(
+    circuit: &Circuit<usize>,
+    module_name: &str,
+    out: impl Write,
+) -> io::Result<()> {
+    to_yosys_json_with_options(circuit, module_name, &YosysJSONOptions::default(), out)
+}
+
+/// Converts circuit to Yosys JSON netlist with options.
+///
+/// Function works like `to_yosys_json`, but it also can write sequential circuit and
+/// ports given by layout in `options`. If circuit is sequential then the circuit inputs are
+/// organized in form: `[state,inputs]` and the circuit outputs are organized in form:
+/// `[state,outputs]`. State is held in `$_DFF_P_` cells clocked by clock port. Bits of state
+/// and next state are named as `state` and `next_state`.
+///
+/// If names of ports collide then function returns error of kind `InvalidInput` that
+/// holds `NameError`. If layout of ports doesn't partition circuit inputs and outputs or has
+/// port with zero width then function returns error of kind `InvalidInput` that holds
+/// `PortLayoutError`.
+pub fn to_yosys_json_with_options(
+    circuit: &Circuit<usize>,
+    module_name: &str,
+    options: &YosysJSONOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let input_len = circuit.input_len();
+    let output_len = circuit.outputs().len();
+    let state_len = options.state_len;
+    assert!(state_len <= input_len);
+    assert!(state_len <= output_len);
+    let ports = if options.ports.is_empty() {
+        (state_len..input_len)
+            .map(|i| HDLPort::new(format!("i{}", i), 1, HDLPortDir::Input))
+            .chain(
+                (state_len..output_len)
+                    .map(|i| HDLPort::new(format!("o{}", i), 1, HDLPortDir::Output)),
+            )
+            .collect::<Vec<_>>()
+    } else {
+        options.ports.clone()
+    };
+    check_hdl_ports(&ports, input_len - state_len, output_len - state_len)?;
+    // check names
+    let mut names = HashSet::new();
+    let controls = if state_len != 0 {
+        vec![options.clock.as_str(), "state", "next_state"]
+    } else {
+        vec![]
+    };
+    for name in controls
+        .into_iter()
+        .chain(ports.iter().map(|p| p.name.as_str()))
+    {
+        if !names.insert(name) {
+            return Err(NameError::NameCollision(name.to_string()).into());
+        }
+    }
+
+    // bits: 0 and 1 are constants
+    let clock_bit = 2;
+    let first_bit = if state_len != 0 { 3 } else { 2 };
+    let wire_bit = |w: usize| first_bit + w;
+    let mut next_bit = first_bit + input_len + circuit.len();
+    let mut cells: Vec<YosysCell> = vec![];
+    for (i, g) in circuit.gates().iter().enumerate() {
+        let cell_type = match g.func {
+            GateFunc::And => "$_AND_",
+            GateFunc::Nor => "$_NOR_",
+            GateFunc::Nimpl => "$_ANDNOT_",
+            GateFunc::Xor => "$_XOR_",
+        };
+        cells.push((
+            format!("$g{}", i + input_len),
+            cell_type,
+            vec![
+                ("A", "input", wire_bit(g.i0)),
+                ("B", "input", wire_bit(g.i1)),
+                ("Y", "output", wire_bit(i + input_len)),
+            ],
+        ));
+    }
+    // negations of outputs
+    let mut neg_bits = HashMap::new();
+    let output_bits = circuit
+        .outputs()
+        .iter()
+        .map(|(o, n)| {
+            if *n {
+                *neg_bits.entry(*o).or_insert_with(|| {
+                    next_bit += 1;
+                    cells.push((
+                        format!("$n{}", o),
+                        "$_NOT_",
+                        vec![("A", "input", wire_bit(*o)), ("Y", "output", next_bit - 1)],
+                    ));
+                    next_bit - 1
+                })
+            } else {
+                wire_bit(*o)
+            }
+        })
+        .collect::<Vec<_>>();
+    for (i, d) in output_bits[0..state_len].iter().enumerate() {
+        cells.push((
+            format!("$dff{}", i),
+            "$_DFF_P_",
+            vec![
+                ("C", "input", clock_bit),
+                ("D", "input", *d),
+                ("Q", "output", wire_bit(i)),
+            ],
+        ));
+    }
+
+    // ports and net names. Net names of state are first to be names of flip-flops nets.
+    let mut json_ports = Map::new();
+    let mut json_netnames = Map::new();
+    let mut add_netname = |name: &str, bits: &[usize]| {
+        json_netnames.insert(
+            name.to_string(),
+            json!({ "hide_name": 0, "bits": bits, "attributes": {} }),
+        );
+    };
+    let mut port_bits = vec![];
+    if state_len != 0 {
+        let state_bits = (0..state_len).map(wire_bit).collect::<Vec<_>>();
+        add_netname("state", &state_bits);
+        add_netname("next_state", &output_bits[0..state_len]);
+        port_bits.push((options.clock.as_str(), "input", vec![clock_bit]));
+    }
+    let mut input_pos = state_len;
+    let mut output_pos = state_len;
+    for port in &ports {
+        if port.dir == HDLPortDir::Input {
+            let bits = (input_pos..input_pos + port.width)
+                .map(wire_bit)
+                .collect::<Vec<_>>();
+            port_bits.push((port.name.as_str(), "input", bits));
+            input_pos += port.width;
+        } else {
+            let bits = output_bits[output_pos..output_pos + port.width].to_vec();
+            port_bits.push((port.name.as_str(), "output", bits));
+            output_pos += port.width;
+        }
+    }
+    for (name, dir, bits) in port_bits {
+        add_netname(name, &bits);
+        json_ports.insert(name.to_string(), json!({ "direction": dir, "bits": bits }));
+    }
+    let json_cells = cells
+        .into_iter()
+        .map(|(name, cell_type, conns)| {
+            let port_directions = conns
+                .iter()
+                .map(|(port, dir, _)| (port.to_string(), json!(dir)))
+                .collect::<Map<_, _>>();
+            let connections = conns
+                .iter()
+                .map(|(port, _, bit)| (port.to_string(), json!([bit])))
+                .collect::<Map<_, _>>();
+            (
+                name,
+                json!({
+                    "hide_name": 1,
+                    "type": cell_type,
+                    "parameters": {},
+                    "attributes": {},
+                    "port_directions": port_directions,
+                    "connections": connections,
+                }),
+            )
+        })
+        .collect::<Map<_, _>>();
+    let mut json_modules = Map::new();
+    json_modules.insert(
+        module_name.to_string(),
+        json!({
+            "attributes": { "top": "00000000000000000000000000000001" },
+            "ports": json_ports,
+            "cells": json_cells,
+            "netnames": json_netnames,
+        }),
+    );
+    let netlist = json!({ "creator": "gateconvert", "modules": json_modules });
+    serde_json::to_writer_pretty(&mut out, &netlist)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Yosys JSON error enumeration.
+#[derive(thiserror::Error, Debug)]
+pub enum YosysJSONError {
+    /// If JSON error.
+    #[error("JSON error: {0}")]
+    JSONError(#[from] serde_json::Error),
+    /// If netlist has wrong structure.
+    #[error("Bad format: {0}")]
+    BadFormat(String),
+    /// If top module can't be determined.
+    #[error("No top module")]
+    NoTopModule,
+    /// If module not found.
+    #[error("Module {0} not found")]
+    ModuleNotFound(String),
+    /// If port has unsupported direction.
+    #[error("Unsupported direction of port {0}")]
+    UnsupportedPortDirection(String),
+    /// If cell has unknown type.
+    #[error("Unknown type {1} of cell {0}")]
+    UnknownCell(String, String),
+    /// If undefined bit (`x` or `z`) is used.
+    #[error("Undefined bit in {0}")]
+    UndefinedBit(String),
+    /// If net is already driven.
+    #[error("Net {0} already driven")]
+    NetAlreadyDriven(usize),
+    /// If net is driven by nothing.
+    #[error("Net {0} is undriven")]
+    UndrivenNet(usize),
+    /// If circuit have cycle.
+    #[error("Cycle in circuit caused by net {0}")]
+    CycleInCircuit(usize),
+}
+
+// mapping: key - name of port bit or net, value - assignment in circuit
+type YosysJSONMap = Vec<(String, AssignEntry)>;
+
+fn bad_format(what: &str) -> YosysJSONError {
+    YosysJSONError::BadFormat(what.to_string())
+}
+
+fn get_object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>, YosysJSONError> {
+    value.as_object().ok_or_else(|| bad_format(what))
+}
+
+// get names of bits - net names are bit numbers, constants are "0" and "1".
+fn get_bits(value: &Value, what: &str) -> Result<Vec<String>, YosysJSONError> {
+    value
+        .as_array()
+        .ok_or_else(|| bad_format(what))?
+        .iter()
+        .map(|bit| match bit {
+            Value::Number(n) => n
+                .as_u64()
+                .map(|n| n.to_string())
+                .ok_or_else(|| bad_format(what)),
+            Value::String(s) if s == "0" || s == "1" => Ok(s.clone()),
+            Value::String(s) if s == "x" || s == "z" => {
+                Err(YosysJSONError::UndefinedBit(what.to_string()))
+            }
+            _ => Err(bad_format(what)),
+        })
+        .collect()
+}
+
+// D flip-flop: returns polarity of reset and reset value and polarity of enable.
+// Format of type: `$_DFF_C_`, `$_DFF_CRV_`, `$_DFFE_CE_`, `$_DFFE_CRVE_`,
+// where C - clock polarity, R - reset polarity, V - reset value, E - enable polarity.
+#[allow(clippy::type_complexity)]
+fn parse_dff_type(cell_type: &str) -> Option<(Option<(bool, bool)>, Option<bool>)> {
+    let (enable, params) = if let Some(params) = cell_type.strip_prefix("$_DFFE_") {
+        (true, params)
+    } else {
+        (false, cell_type.strip_prefix("$_DFF_")?)
+    };
+    let params = params.strip_suffix('_')?.as_bytes();
+    if !params
+        .iter()
+        .all(|c| matches!(c, b'N' | b'P' | b'0' | b'1'))
+    {
+        return None;
+    }
+    let reset_len = match params.len().checked_sub(usize::from(enable)) {
+        Some(1) => 0,
+        Some(3) => 2,
+        _ => {
+            return None;
+        }
+    };
+    let pol = |c: u8| match c {
+        b'P' => Some(true),
+        b'N' => Some(false),
+        _ => None,
+    };
+    let value = |c: u8| match c {
+        b'1' => Some(true),
+        b'0' => Some(false),
+        _ => None,
+    };
+    pol(params[0])?;
+    let reset = if reset_len != 0 {
+        Some((pol(params[1])?, value(params[2])?))
+    } else {
+        None
+    };
+    let enable = if enable {
+        Some(pol(params[1 + reset_len])?)
+    } else {
+        None
+    };
+    Some((reset, enable))
+}
+
+/// Converts Yosys JSON netlist to Gate circuit.
+///
+/// `input` is read stream with Yosys JSON netlist. `top` is name of module to convert.
+/// If `top` is None then module with `top` attribute or single module is converted.
+/// Supported cells are: `$_AND_`, `$_OR_`, `$_XOR_`, `$_NOT_`, `$_NAND_`, `$_NOR_`,
+/// `$_XNOR_`, `$_ANDNOT_`, `$_ORNOT_`, `$_MUX_`, `$_BUF_` and D flip-flops:
+/// `$_DFF_*` (with optional reset) and `$_DFFE_*` (with enable).
+///
+/// D flip-flops are states of the circuit. Polarities of clocks are ignored, reset is
+/// converted to synchronous reset and clock inputs are not used by circuit.
+/// The circuit inputs are organized in form: `[state,inputs]`. The circuit outputs
+/// are organized in form: `[state,outputs]`, where state are next states (inputs of
+/// D flip-flops). States are in order of cells, inputs and outputs in order of ports.
+///
+/// Function returns Gate circuit with its mapping. Mapping in form: key - name of D
+/// flip-flop output, input port bit, D flip-flop input or output port bit (in this order),
+/// value - assignment in circuit. Bits of port with width greater than 1 are named as
+/// `name[bit]`. Names of D flip-flops outputs and inputs are names of their nets
+/// (or bit numbers if nets don't have names). Next state of D flip-flop with reset or enable
+/// is named as `{name of output}:next`.
+pub fn from_yosys_json(
+    input: impl Read,
+    top: Option<&str>,
+) -> Result<(Circuit<usize>, YosysJSONMap), YosysJSONError> {
+    let netlist: Value = serde_json::from_reader(input)?;
+    let modules = get_object(
+        get_object(&netlist, "netlist")?
+            .get("modules")
+            .ok_or_else(|| bad_format("modules"))?,
+        "modules",
+    )?;
+    let module = if let Some(top) = top {
+        modules
+            .get(top)
+            .ok_or_else(|| YosysJSONError::ModuleNotFound(top.to_string()))?
+    } else if modules.len() == 1 {
+        modules.values().next().unwrap()
+    } else {
+        let is_top = |module: &Value| {
+            module
+                .get("attributes")
+                .and_then(|attrs| attrs.get("top"))
+                .is_some_and(|top| match top {
+                    Value::String(s) => s.contains('1'),
+                    Value::Number(n) => n.as_u64() != Some(0),
+                    _ => false,
+                })
+        };
+        let mut tops = modules.values().filter(|m| is_top(m));
+        match (tops.next(), tops.next()) {
+            (Some(module), None) => module,
+            _ => {
+                return Err(YosysJSONError::NoTopModule);
+            }
+        }
+    };
+    let empty = Map::new();
+    let get_section = |name: &str| {
+        module
+            .get(name)
+            .map_or(Ok(&empty), |section| get_object(section, name))
+    };
+    let ports = get_section("ports")?;
+    let cells = get_section("cells")?;
+    let netnames = get_section("netnames")?;
+
+    // port bit name in form: name if width is 1, otherwise name[bit]
+    let bit_names = |name: &str, bits: &[String]| {
+        bits.iter()
+            .enumerate()
+            .map(|(i, bit)| {
+                let bit_name = if bits.len() == 1 {
+                    name.to_string()
+                } else {
+                    format!("{}[{}]", name, i)
+                };
+                (bit_name, bit.clone())
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    for (name, port) in ports {
+        let bits = get_bits(port.get("bits").ok_or_else(|| bad_format(name))?, name)?;
+        match port.get("direction").and_then(|d| d.as_str()) {
+            Some("input") => inputs.extend(bit_names(name, &bits)),
+            Some("output") => outputs.extend(bit_names(name, &bits)),
+            _ => {
+                return Err(YosysJSONError::UnsupportedPortDirection(name.clone()));
+            }
+        }
+    }
+    // names of nets
+    let mut net_names = HashMap::new();
+    for (name, netname) in netnames {
+        let bits = get_bits(netname.get("bits").ok_or_else(|| bad_format(name))?, name)?;
+        for (bit_name, bit) in bit_names(name, &bits) {
+            net_names.entry(bit).or_insert(bit_name);
+        }
+    }
+
+    let net = |bit: &String| VExpr::Net(bit.clone());
+    let not = |expr: VExpr| VExpr::Not(Box::new(expr));
+    let mut drivers = HashMap::from([
+        ("0".to_string(), VExpr::Value(false)),
+        ("1".to_string(), VExpr::Value(true)),
+    ]);
+    let mut driven = inputs
+        .iter()
+        .map(|(_, bit)| bit.clone())
+        .collect::<HashSet<_>>();
+    let mut dffs = vec![];
+    for (name, cell) in cells {
+        let cell_type = cell
+            .get("type")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| bad_format(name))?;
+        let connections = get_object(
+            cell.get("connections").ok_or_else(|| bad_format(name))?,
+            name,
+        )?;
+        let conn = |port: &str| -> Result<String, YosysJSONError> {
+            let bits = get_bits(connections.get(port).ok_or_else(|| bad_format(name))?, name)?;
+            if bits.len() != 1 {
+                return Err(bad_format(name));
+            }
+            Ok(bits[0].clone())
+        };
+        let unknown_cell = || YosysJSONError::UnknownCell(name.clone(), cell_type.to_string());
+        let (output, expr) = if cell_type.starts_with("$_DFF") {
+            let (reset, enable) = parse_dff_type(cell_type).ok_or_else(unknown_cell)?;
+            let q = conn("Q")?;
+            let mut next = net(&conn("D")?);
+            if let Some(en_pol) = enable {
+                let en = net(&conn("E")?);
+                let en = if en_pol { en } else { not(en) };
+                next = VExpr::Ite(Box::new(en), Box::new(next), Box::new(net(&q)));
+            }
+            if let Some((rst_pol, rst_value)) = reset {
+                let rst = net(&conn("R")?);
+                let rst = if rst_pol { rst } else { not(rst) };
+                next = VExpr::Ite(
+                    Box::new(rst),
+                    Box::new(VExpr::Value(rst_value)),
+                    Box::new(next),
+                );
+            }
+            // next state with enable or reset is driven by additional net
+            let d = if let VExpr::Net(d) = next {
+                d
+            } else {
+                let d = format!("{}:next", q);
+                drivers.insert(d.clone(), next);
+                d
+            };
+            dffs.push((q.clone(), d));
+            (q, None)
+        } else {
+            let (a, b) = (net(&conn("A")?), || conn("B").map(|b| net(&b)));
+            let expr = match cell_type {
+                "$_BUF_" => a,
+                "$_NOT_" => not(a),
+                "$_AND_" => VExpr::And(vec![a, b()?]),
+                "$_OR_" => VExpr::Or(vec![a, b()?]),
+                "$_XOR_" => VExpr::Xor(vec![a, b()?]),
+                "$_NAND_" => not(VExpr::And(vec![a, b()?])),
+                "$_NOR_" => not(VExpr::Or(vec![a, b()?])),
+                "$_XNOR_" => not(VExpr::Xor(vec![a, b()?])),
+                "$_ANDNOT_" => VExpr::And(vec![a, not(b()?)]),
+                "$_ORNOT_" => VExpr::Or(vec![a, not(b()?)]),
+                "$_MUX_" => VExpr::Ite(Box::new(net(&conn("S")?)), Box::new(b()?), Box::new(a)),
+                _ => {
+                    return Err(unknown_cell());
+                }
+            };
+            (conn("Y")?, Some(expr))
+        };
+        if output == "0" || output == "1" || !driven.insert(output.clone()) {
+            return Err(YosysJSONError::NetAlreadyDriven(output.parse().unwrap()));
+        }
+        if let Some(expr) = expr {
+            drivers.insert(output, expr);
+        }
+    }
+
+    let net_name = |bit: &String| net_names.get(bit).unwrap_or(bit).clone();
+    let circuit_inputs = dffs
+        .iter()
+        .map(|(q, _)| q.clone())
+        .chain(inputs.iter().map(|(_, bit)| bit.clone()))
+        .collect::<Vec<_>>();
+    let circuit_outputs = dffs
+        .iter()
+        .map(|(_, d)| d.clone())
+        .chain(outputs.iter().map(|(_, bit)| bit.clone()))
+        .collect::<Vec<_>>();
+    let names = dffs
+        .iter()
+        .map(|(q, _)| net_name(q))
+        .chain(inputs.into_iter().map(|(name, _)| name))
+        .chain(dffs.iter().map(|(q, d)| {
+            if d.ends_with(":next") {
+                format!("{}:next", net_name(q))
+            } else {
+                net_name(d)
+            }
+        }))
+        .chain(outputs.into_iter().map(|(name, _)| name))
+        .collect::<Vec<_>>();
+    let (circuit, map) =
+        vexprs_to_circuit(circuit_inputs, circuit_outputs, &drivers).map_err(|e| match e {
+            VNetError::UndrivenNet(bit) => YosysJSONError::UndrivenNet(bit.parse().unwrap()),
+            VNetError::Cycle(bit) => YosysJSONError::CycleInCircuit(bit.parse().unwrap()),
+        })?;
+    Ok((
+        circuit,
+        names
+            .into_iter()
+            .zip(map.into_iter().map(|(_, a)| a))
+            .collect(),
+    ))
+}
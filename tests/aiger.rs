@@ -1477,3 +1477,165 @@ fn test_from_aiger_bin() {
         ]),
     );
 }
+
+#[test]
+fn test_aiger_with_info() {
+    let circuit = Circuit::new(
+        3,
+        [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+        [(4, false), (3, true), (0, false)],
+    )
+    .unwrap();
+    let info = aiger::AIGERInfo {
+        input_names: vec![Some("a".to_string()), None],
+        latch_names: vec![Some("s".to_string())],
+        latch_inits: vec![Some(true)],
+        output_names: vec![None, Some("y".to_string())],
+        output_roles: vec![aiger::AIGEROutputRole::Output; 2],
+        comment: Some("test circuit\nsecond line".to_string()),
+    };
+    let aiger_map = vec![
+        (6, AssignEntry::Var(0, false)),
+        (2, AssignEntry::Var(1, false)),
+        (4, AssignEntry::Var(2, false)),
+        (14, AssignEntry::Var(4, false)),
+        (9, AssignEntry::Var(3, true)),
+        (6, AssignEntry::Var(0, false)),
+    ];
+    // ascii
+    let mut out = vec![];
+    aiger::to_aiger_with_info(&circuit, 1, &mut out, false, &info).unwrap();
+    assert_eq!(
+        concat!(
+            "aag 7 2 1 2 4\n2\n4\n6 14 1\n9\n6\n8 6 2\n10 8 4\n12 9 5\n14 11 13\n",
+            "i0 a\nl0 s\no1 y\nc\ntest circuit\nsecond line\n"
+        ),
+        String::from_utf8(out.clone()).unwrap()
+    );
+    assert_eq!(
+        (circuit.clone(), aiger_map.clone(), info.clone()),
+        aiger::from_aiger_with_info(out.as_slice(), false).unwrap()
+    );
+    // binary
+    let mut out = vec![];
+    aiger::to_aiger_with_info(&circuit, 1, &mut out, true, &info).unwrap();
+    assert_eq!(
+        concat!(
+            "aig 7 2 1 2 4\n14 1\n9\n6\n\x02\x04\x02\x04\x03\x04\x01\x02",
+            "i0 a\nl0 s\no1 y\nc\ntest circuit\nsecond line\n"
+        )
+        .as_bytes(),
+        out.as_slice()
+    );
+    assert_eq!(
+        (circuit, aiger_map, info),
+        aiger::from_aiger_with_info(out.as_slice(), true).unwrap()
+    );
+}
+
+#[test]
+fn test_aiger_properties() {
+    use aiger::AIGEROutputRole::*;
+    let circuit = Circuit::new(
+        3,
+        [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+        [
+            (4, false),
+            (3, true),
+            (0, false),
+            (1, true),
+            (2, false),
+            (3, false),
+            (4, true),
+            (1, false),
+        ],
+    )
+    .unwrap();
+    let info = aiger::AIGERInfo {
+        input_names: vec![None, None],
+        latch_names: vec![None],
+        latch_inits: vec![None],
+        output_names: vec![Some("y".to_string()), None, None, None, None, None, None],
+        output_roles: vec![
+            Output,
+            BadState,
+            InvariantConstraint,
+            Justice(0),
+            Justice(1),
+            Justice(1),
+            FairnessConstraint,
+        ],
+        comment: None,
+    };
+    let mut out = vec![];
+    aiger::to_aiger_with_info(&circuit, 1, &mut out, false, &info).unwrap();
+    assert_eq!(
+        concat!(
+            "aag 7 2 1 1 4 1 1 2 1\n2\n4\n6 14 6\n9\n6\n3\n1\n2\n4\n8\n15\n2\n",
+            "8 6 2\n10 8 4\n12 9 5\n14 11 13\no0 y\n"
+        ),
+        String::from_utf8(out.clone()).unwrap()
+    );
+    assert_eq!(
+        (
+            circuit,
+            vec![
+                (6, AssignEntry::Var(0, false)),
+                (2, AssignEntry::Var(1, false)),
+                (4, AssignEntry::Var(2, false)),
+                (14, AssignEntry::Var(4, false)),
+                (9, AssignEntry::Var(3, true)),
+                (6, AssignEntry::Var(0, false)),
+                (3, AssignEntry::Var(1, true)),
+                (4, AssignEntry::Var(2, false)),
+                (8, AssignEntry::Var(3, false)),
+                (15, AssignEntry::Var(4, true)),
+                (2, AssignEntry::Var(1, false)),
+            ],
+            info
+        ),
+        aiger::from_aiger_with_info(out.as_slice(), false).unwrap()
+    ); // properties are ignored without info
+    assert_eq!(
+        (
+            Circuit::new(
+                3,
+                [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+                [(4, false), (3, true)],
+            )
+            .unwrap(),
+            vec![
+                (6, AssignEntry::Var(0, false)),
+                (2, AssignEntry::Var(1, false)),
+                (4, AssignEntry::Var(2, false)),
+                (14, AssignEntry::Var(4, false)),
+                (9, AssignEntry::Var(3, true)),
+            ]
+        ),
+        aiger::from_aiger(out.as_slice(), false).unwrap()
+    );
+}
+
+#[test]
+fn test_aiger_symbol_bad_index() {
+    // parser checks indices of symbols only against number of latches
+    for (input, symbol) in [
+        ("aag 4 0 3 0 0 1\n2 2\n4 4\n6 6\n2\n", "b2"),
+        ("aag 4 0 3 0 0 1\n2 2\n4 4\n6 6\n2\n", "b1"),
+        ("aag 4 0 3 0 0 0 0 1\n2 2\n4 4\n6 6\n1\n2\n", "j2"),
+        ("aag 4 0 3 0 0 0 0 1\n2 2\n4 4\n6 6\n1\n2\n", "j1"),
+    ] {
+        let input = format!("{}{} foo\n", input, symbol);
+        assert_eq!(
+            "Symbol foo bad index",
+            aiger::from_aiger_with_info(input.as_bytes(), false)
+                .unwrap_err()
+                .to_string(),
+            "{}",
+            symbol
+        );
+    }
+    let input = "aag 4 0 3 0 0 1\n2 2\n4 4\n6 6\n2\nb0 foo\n";
+    let (_, _, info) = aiger::from_aiger_with_info(input.as_bytes(), false).unwrap();
+    assert_eq!(vec![Some("foo".to_string())], info.output_names);
+}
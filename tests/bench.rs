@@ -0,0 +1,253 @@
+use gateconvert::bench;
+use gateconvert::AssignEntry;
+use gateutil::gatesim::*;
+
+fn to_bench_helper(circuit: Circuit<usize>, state_len: usize) -> String {
+    let mut out = vec![];
+    bench::to_bench(&circuit, state_len, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_to_bench() {
+    assert_eq!(
+        "",
+        to_bench_helper(Circuit::new(0, [], []).unwrap(), 0).as_str()
+    );
+    assert_eq!(
+        r##"INPUT(i0)
+INPUT(i1)
+INPUT(i2)
+OUTPUT(o0)
+OUTPUT(o1)
+OUTPUT(o2)
+OUTPUT(o3)
+OUTPUT(o4)
+o1 = NAND(i0, i2)
+i4 = OR(i1, i2)
+o0 = XOR(o1, i4)
+n0 = NOT(i0)
+o2 = AND(o0, n0)
+o4 = NOT(i1)
+o3 = BUFF(o2)
+"##,
+        to_bench_helper(
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 2),
+                    Gate::new_nor(1, 2),
+                    Gate::new_xor(3, 4),
+                    Gate::new_nimpl(5, 0),
+                ],
+                [(5, false), (3, true), (6, false), (6, false), (1, true)],
+            )
+            .unwrap(),
+            0
+        )
+    );
+    assert_eq!(
+        r##"INPUT(i2)
+OUTPUT(o2)
+OUTPUT(o3)
+i0 = DFF(o0)
+i1 = DFF(o1)
+o0 = XOR(i0, i2)
+o2 = NAND(i0, i2)
+o1 = AND(i1, o2)
+o3 = BUFF(i1)
+"##,
+        to_bench_helper(
+            Circuit::new(
+                3,
+                [
+                    Gate::new_xor(0, 2),
+                    Gate::new_and(0, 2),
+                    Gate::new_nimpl(1, 4)
+                ],
+                [(3, false), (5, false), (4, true), (1, false)],
+            )
+            .unwrap(),
+            2
+        )
+    );
+}
+
+type BenchResult = (Circuit<usize>, Vec<(String, AssignEntry)>);
+
+fn from_bench_helper(text: &str) -> Result<BenchResult, String> {
+    bench::from_bench(text.as_bytes()).map_err(|e| e.to_string())
+}
+
+fn strs_to_assign_map<'a>(
+    iter: impl IntoIterator<Item = (&'a str, AssignEntry)>,
+) -> Vec<(String, AssignEntry)> {
+    iter.into_iter().map(|(s, a)| (s.to_string(), a)).collect()
+}
+
+#[test]
+fn test_from_bench() {
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                2,
+                [
+                    Gate::new_and(0, 1),
+                    Gate::new_nor(0, 1),
+                    Gate::new_xor(0, 1),
+                ],
+                [(2, false), (3, false), (4, false)],
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("a", AssignEntry::Var(0, false)),
+                ("b", AssignEntry::Var(1, false)),
+                ("x", AssignEntry::Var(2, false)),
+                ("y", AssignEntry::Var(3, false)),
+                ("z", AssignEntry::Var(4, false)),
+            ])
+        )),
+        from_bench_helper(
+            r##"INPUT(a)
+INPUT(b)
+OUTPUT(x)
+OUTPUT(y)
+OUTPUT(z)
+x = AND(a, b)
+y = NOR(a, b)
+z = XOR(a, b)
+"##
+        )
+    );
+    // output of to_bench
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_xor(0, 2),
+                    Gate::new_and(0, 2),
+                    Gate::new_nimpl(1, 4)
+                ],
+                [(3, false), (5, false), (4, true), (1, false)],
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("i0", AssignEntry::Var(0, false)),
+                ("i1", AssignEntry::Var(1, false)),
+                ("i2", AssignEntry::Var(2, false)),
+                ("o0", AssignEntry::Var(3, false)),
+                ("o1", AssignEntry::Var(5, false)),
+                ("o2", AssignEntry::Var(4, true)),
+                ("o3", AssignEntry::Var(1, false)),
+            ])
+        )),
+        from_bench_helper(
+            r##"INPUT(i2)
+OUTPUT(o2)
+OUTPUT(o3)
+i0 = DFF(o0)
+i1 = DFF(o1)
+o0 = XOR(i0, i2)
+o2 = NAND(i0, i2)
+o1 = AND(i1, o2)
+o3 = BUFF(i1)
+"##
+        )
+    );
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_xor(0, 1),
+                    Gate::new_xor(3, 2),
+                    Gate::new_nor(2, 0),
+                    Gate::new_nimpl(5, 1),
+                ],
+                [(4, true), (6, true)],
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("a", AssignEntry::Var(0, false)),
+                ("b", AssignEntry::Var(1, false)),
+                ("c", AssignEntry::Var(2, false)),
+                ("x", AssignEntry::Var(4, true)),
+                ("y", AssignEntry::Var(6, true)),
+            ])
+        )),
+        from_bench_helper(
+            r##"INPUT(a)
+INPUT(b)
+INPUT(c)
+OUTPUT(x)
+OUTPUT(y)
+x = xnor(a, b, c)
+y = OR(c, a, b)
+"##
+        )
+    );
+    // ISCAS-89 style with comments
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(1, 0),
+                    Gate::new_and(3, 2),
+                    Gate::new_and(4, 1),
+                ],
+                [(5, false), (4, true)],
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("G5", AssignEntry::Var(0, false)),
+                ("G0", AssignEntry::Var(1, false)),
+                ("G1", AssignEntry::Var(2, false)),
+                ("G10", AssignEntry::Var(5, false)),
+                ("G17", AssignEntry::Var(4, true)),
+            ])
+        )),
+        from_bench_helper(
+            r##"# sample
+INPUT(G0)
+INPUT(G1)
+
+OUTPUT(G17)
+G5 = DFF(G10)
+G10 = NOR(G14, G11)
+G11 = NAND(G0, G5, G1)
+G14 = not(G0)
+G17 = BUFF(G11) # output
+"##
+        )
+    );
+    assert_eq!(
+        Err("3: Unknown gate FOO".to_string()),
+        from_bench_helper("INPUT(a)\nOUTPUT(x)\nx = FOO(a)\n")
+    );
+    assert_eq!(
+        Err("Net y is undriven".to_string()),
+        from_bench_helper("INPUT(a)\nOUTPUT(x)\nx = AND(a, y)\n")
+    );
+    assert_eq!(
+        Err("4: Net x already driven".to_string()),
+        from_bench_helper("INPUT(a)\nOUTPUT(x)\nx = NOT(a)\nx = BUFF(a)\n")
+    );
+    assert_eq!(
+        Err("4: Wrong number of arguments of NOT".to_string()),
+        from_bench_helper("INPUT(a)\nINPUT(b)\nOUTPUT(x)\nx = NOT(a, b)\n")
+    );
+    assert_eq!(
+        Err("3: Syntax error: AND(a".to_string()),
+        from_bench_helper("INPUT(a)\nOUTPUT(x)\nx = AND(a\n")
+    );
+    assert_eq!(
+        Err("2: Syntax error: x = INPUT(a)".to_string()),
+        from_bench_helper("INPUT(a)\nx = INPUT(a)\n")
+    );
+    assert_eq!(
+        Err("Cycle in circuit caused by x".to_string()),
+        from_bench_helper("INPUT(a)\nOUTPUT(x)\nx = AND(a, y)\ny = OR(a, x)\n")
+    );
+}
@@ -1,8 +1,15 @@
 use gateconvert::blif;
-use gateconvert::AssignEntry;
+use gateconvert::genlib;
+use gateconvert::kiss2;
+use gateconvert::lutmap;
+use gateconvert::{AssignEntry, WriterNames};
 use gateutil::gatesim::*;
 
+use std::collections::HashMap;
 use std::fs;
+use std::io;
+
+mod common;
 
 fn to_blif_helper(circuit: Circuit<usize>, state_len: usize, clock_num: usize) -> String {
     let mut out = vec![];
@@ -413,18 +420,22 @@ fn test_to_blif() {
 .outputs o17
 .outputs o18
 .outputs o19
-.names o0 i1 o4
+.names i0 i1 o4
 11 1
-.names o2 o4 o10
+.names i2 o4 o10
 00 1
 .names i3 o10 o6
 10 1
 .names i4 o6 o8
 10 1
 01 1
-.names o0 o3
+.names i0 o0
+1 1
+.names i0 o3
 0 1
-.names o2 o1
+.names i2 o2
+1 1
+.names i2 o1
 0 1
 .names o4 o11
 0 1
@@ -540,6 +551,377 @@ fn test_to_blif() {
     );
 }
 
+#[test]
+fn test_to_blif_with_options() {
+    let mut out = vec![];
+    blif::to_blif_with_options(
+        &Circuit::new(
+            4,
+            [Gate::new_and(0, 3), Gate::new_xor(1, 2)],
+            [(4, false), (5, true), (0, false), (2, true)],
+        )
+        .unwrap(),
+        3,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            latch_inits: vec![Some(true), None],
+            library: None,
+            max_names_inputs: 0,
+            names: WriterNames::default(),
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.inputs i3
+.outputs o0
+.outputs o1
+.outputs o2
+.outputs o3
+.latch o0 i0 1
+.latch o1 i1 3
+.latch o2 i2
+.names i0 i3 o0
+11 1
+.names i1 i2 i5
+10 1
+01 1
+.names i0 o2
+1 1
+.names i2 o3
+0 1
+.names i5 o1
+0 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[test]
+fn test_to_blif_collapse() {
+    let circuit = Circuit::new(
+        6,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_xor(6, 2),
+            Gate::new_and(7, 3),
+            Gate::new_nor(8, 4),
+            Gate::new_nimpl(9, 5),
+            Gate::new_and(0, 7),
+        ],
+        [(10, true), (11, false)],
+    )
+    .unwrap();
+    let mut out = vec![];
+    blif::to_blif_with_options(
+        &circuit,
+        0,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            max_names_inputs: 4,
+            ..blif::BLIFOptions::default()
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.inputs i3
+.inputs i4
+.inputs i5
+.outputs o0
+.outputs o1
+.names i0 i1 i6
+10 1
+01 1
+.names i2 i6 i7
+10 1
+01 1
+.names i3 i4 i5 i7 o0
+-000 0
+000- 0
+.names i0 i7 o1
+11 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let mut out = vec![];
+    blif::to_blif_with_options(
+        &circuit,
+        0,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            max_names_inputs: 3,
+            ..blif::BLIFOptions::default()
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.inputs i3
+.inputs i4
+.inputs i5
+.outputs o0
+.outputs o1
+.names i0 i1 i6
+10 1
+01 1
+.names i2 i6 i7
+10 1
+01 1
+.names i3 i7 i8
+11 1
+.names i4 i5 i8 o0
+000 0
+.names i0 i7 o1
+11 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let to_blif_collapse = |circuit: &Circuit<usize>, max_names_inputs| {
+        let mut out = vec![];
+        blif::to_blif_with_options(
+            circuit,
+            0,
+            0,
+            "top",
+            &blif::BLIFOptions {
+                max_names_inputs,
+                ..blif::BLIFOptions::default()
+            },
+            &mut out,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    // too big maximal number of inputs is limited
+    assert_eq!(
+        to_blif_collapse(&circuit, 6),
+        to_blif_collapse(&circuit, usize::MAX)
+    );
+    // parity chain is not collapsed, because its cover is bigger than its gates
+    let parity = Circuit::new(
+        16,
+        (0..15).map(|i| Gate::new_xor(if i == 0 { 0 } else { 15 + i }, i + 1)),
+        [(30, false)],
+    )
+    .unwrap();
+    let parity_blif = to_blif_collapse(&parity, usize::MAX);
+    assert_eq!(to_blif_collapse(&parity, 3), parity_blif);
+    assert_eq!(15, parity_blif.matches(".names").count());
+    // and chain is collapsed into single cube with up to 6 inputs
+    let and_chain = Circuit::new(
+        8,
+        (0..7).map(|i| Gate::new_and(if i == 0 { 0 } else { 7 + i }, i + 1)),
+        [(14, false)],
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.inputs i3
+.inputs i4
+.inputs i5
+.inputs i6
+.inputs i7
+.outputs o0
+.names i0 i1 i2 i9
+111 1
+.names i3 i4 i5 i6 i7 i9 o0
+111111 1
+.end
+"##,
+        to_blif_collapse(&and_chain, usize::MAX)
+    );
+}
+
+#[test]
+fn test_to_blif_with_names() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_and(0, 1),
+            Gate::new_nor(2, 4),
+        ],
+        [(3, false), (5, true), (2, false)],
+    )
+    .unwrap();
+    let names = WriterNames {
+        inputs: vec!["state".to_string(), "x".to_string(), "y[0]".to_string()],
+        outputs: vec!["state_next".to_string(), "z".to_string()],
+        wire_prefix: "n".to_string(),
+    };
+    let write_blif = |names: WriterNames| {
+        let mut out = vec![];
+        blif::to_blif_with_options(
+            &circuit,
+            1,
+            0,
+            "top",
+            &blif::BLIFOptions {
+                latch_inits: vec![Some(false)],
+                names,
+                ..blif::BLIFOptions::default()
+            },
+            &mut out,
+        )
+        .map(|_| String::from_utf8(out).unwrap())
+        .map_err(|e| {
+            assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+            e.to_string()
+        })
+    };
+    assert_eq!(
+        Ok(r##".model top
+.inputs state
+.inputs x
+.inputs y[0]
+.outputs state_next
+.outputs z
+.outputs o2
+.latch state_next state 0
+.names state x state_next
+10 1
+01 1
+.names state x n4
+11 1
+.names y[0] n4 n5
+00 1
+.names y[0] o2
+1 1
+.names n5 z
+0 1
+.end
+"##
+        .to_string()),
+        write_blif(names.clone())
+    );
+    assert_eq!(
+        Err("Name collision: z".to_string()),
+        write_blif(WriterNames {
+            outputs: vec!["z".to_string(), "z".to_string()],
+            ..names.clone()
+        })
+    );
+    assert_eq!(
+        Err("Name collision: n5".to_string()),
+        write_blif(WriterNames {
+            inputs: vec!["n5".to_string()],
+            ..names.clone()
+        })
+    );
+    assert_eq!(
+        Err("Invalid name y#0".to_string()),
+        write_blif(WriterNames {
+            inputs: vec!["y#0".to_string()],
+            ..names.clone()
+        })
+    );
+    assert_eq!(
+        Err("Invalid name y 0".to_string()),
+        write_blif(WriterNames {
+            outputs: vec!["y 0".to_string()],
+            ..names.clone()
+        })
+    );
+}
+
+#[test]
+fn test_to_blif_lut() {
+    let circuit = Circuit::new(
+        3,
+        [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+        [(4, false), (3, true), (0, false)],
+    )
+    .unwrap();
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Depth);
+    let mut out = vec![];
+    blif::to_blif_lut(&lcircuit, 0, 0, "top", &mut out).unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.outputs o0
+.outputs o1
+.outputs o2
+.names i0 i1 i3
+11 1
+.names i0 i1 i2 o0
+110 1
+-01 1
+0-1 1
+.names i0 o2
+1 1
+.names i3 o1
+0 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let mut out = vec![];
+    blif::to_blif_lut_with_options(
+        &lcircuit,
+        1,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            latch_inits: vec![Some(true)],
+            names: WriterNames {
+                inputs: vec!["s".to_string(), "a".to_string(), "b".to_string()],
+                outputs: vec!["s_next".to_string(), "x".to_string(), "y".to_string()],
+                wire_prefix: "w".to_string(),
+            },
+            ..blif::BLIFOptions::default()
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs s
+.inputs a
+.inputs b
+.outputs s_next
+.outputs x
+.outputs y
+.latch s_next s 1
+.names s a w3
+11 1
+.names s a b s_next
+110 1
+-01 1
+0-1 1
+.names s y
+1 1
+.names w3 x
+0 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+}
+
 fn strs2_to_vec_string<'a>(
     iter: impl IntoIterator<Item = (&'a str, &'a str)>,
 ) -> Vec<(String, String)> {
@@ -656,3 +1038,683 @@ fn test_from_blif() {
         ]))
     );
 }
+
+#[test]
+fn test_from_blif_with_info() {
+    let to_remove = write_files(strs2_to_vec_string([(
+        "xxxlatchinit.blif",
+        r##".model top
+.inputs a b c d e
+.outputs x y z w
+.latch x a 0
+.latch y b re NIL 1
+.latch z c 2
+.latch w d
+.names a e x
+11 1
+.names b e y
+10 1
+.names c e z
+01 1
+.names d e w
+00 1
+.end
+"##,
+    )]));
+    let (circuit, _, info) = blif::from_blif_with_info(&to_remove.0[0]).unwrap();
+    assert_eq!(5, circuit.input_len());
+    assert_eq!(4, circuit.outputs().len());
+    assert_eq!(
+        blif::BLIFInfo {
+            latch_inits: vec![Some(false), Some(true), None, None],
+            fsm_states: vec![],
+            exdc_ignored: false,
+        },
+        info
+    );
+}
+
+struct MemResolver(HashMap<String, String>);
+
+impl blif::BLIFSearchResolver for MemResolver {
+    type Reader = io::Cursor<Vec<u8>>;
+    fn resolve(&mut self, including: &str, path: &str) -> io::Result<(String, Self::Reader)> {
+        let name = format!("{}/{}", including, path);
+        self.0
+            .get(path)
+            .map(|s| (name, io::Cursor::new(s.as_bytes().to_vec())))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+    }
+}
+
+#[test]
+fn test_from_blif_reader() {
+    let mut resolver = MemResolver(HashMap::from_iter([(
+        "sub.blif".to_string(),
+        r##".model sub
+.inputs a b
+.outputs x
+.names a b x
+11 1
+.end
+"##
+        .to_string(),
+    )]));
+    let (circuit, mapping) = blif::from_blif_reader(
+        "top.blif",
+        r##".model top
+.inputs c d
+.outputs y
+.subckt sub a=c b=d x=y
+.end
+.search sub.blif
+"##
+        .as_bytes(),
+        &mut resolver,
+    )
+    .unwrap();
+    assert_eq!(2, circuit.input_len());
+    assert_eq!(1, circuit.outputs().len());
+    assert_eq!(
+        vec!["c".to_string(), "d".to_string(), "y".to_string()],
+        mapping.into_iter().map(|(s, _)| s).collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        blif::from_blif_reader(
+            "top.blif",
+            ".search missing.blif\n".as_bytes(),
+            &mut resolver
+        ),
+        Err(blif::BLIFError::IOError(_))
+    ));
+    // with options and information
+    let text = r##".model top
+.inputs s c d
+.outputs s1 y
+.latch s1 s 1
+.subckt sub a=c b=d x=y
+.names s c s1
+11 1
+.exdc
+.names c y
+1 1
+.end
+.search sub.blif
+"##;
+    let (circuit, mapping, info) = blif::from_blif_reader_with_options(
+        "top.blif",
+        text.as_bytes(),
+        &mut resolver,
+        &blif::BLIFReadOptions {
+            exdc_outputs: true,
+            ..blif::BLIFReadOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(3, circuit.input_len());
+    assert_eq!(3, circuit.outputs().len());
+    assert_eq!(
+        vec!["s", "c", "d", "s1", "y", "s1#exdc", "y#exdc"],
+        mapping.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!(vec![Some(true)], info.latch_inits);
+    assert!(!info.exdc_ignored);
+    let (circuit, mapping, info) = blif::from_blif_reader_with_options(
+        "top.blif",
+        text.as_bytes(),
+        &mut resolver,
+        &blif::BLIFReadOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        blif::from_blif_reader("top.blif", text.as_bytes(), &mut resolver).unwrap(),
+        (circuit, mapping)
+    );
+    assert_eq!(vec![Some(true)], info.latch_inits);
+    assert!(info.exdc_ignored);
+}
+
+#[test]
+fn test_from_blif_search_relative() {
+    fs::create_dir_all("xxxsearchdir").unwrap();
+    let to_remove = write_files(strs2_to_vec_string([
+        (
+            "xxxsearchdir/top.blif",
+            r##".model top
+.inputs c d
+.outputs y
+.subckt sub a=c b=d x=y
+.end
+.search sub.blif
+"##,
+        ),
+        (
+            "xxxsearchdir/sub.blif",
+            r##".model sub
+.inputs a b
+.outputs x
+.names a b x
+11 1
+.end
+"##,
+        ),
+    ]));
+    let result = blif::from_blif(&to_remove.0[0]);
+    drop(to_remove);
+    fs::remove_dir("xxxsearchdir").unwrap();
+    let (circuit, mapping) = result.unwrap();
+    assert_eq!(2, circuit.input_len());
+    assert_eq!("y", mapping[2].0);
+    assert_eq!(1, circuit.outputs().len());
+}
+
+#[test]
+fn test_from_blif_internal_latches() {
+    let to_remove = write_files(strs2_to_vec_string([(
+        "xxxinternallatch.blif",
+        r##".model top
+.inputs a b clk
+.outputs y z
+.latch n q re clk 1
+.names a q n
+11 1
+.names q y
+1 1
+.subckt cnt en=b o=z
+.end
+.model cnt
+.inputs en
+.outputs o
+.latch nx o 0
+.latch ny w 2
+.names en o w nx
+1-- 1
+.names en w ny
+11 1
+.end
+"##,
+    )]));
+    let (circuit, mapping, info) = blif::from_blif_with_info(&to_remove.0[0]).unwrap();
+    assert_eq!(5, circuit.input_len());
+    assert_eq!(5, circuit.outputs().len());
+    assert_eq!(
+        vec![
+            ("a".to_string(), AssignEntry::Var(3, false)),
+            ("b".to_string(), AssignEntry::Var(4, false)),
+            ("clk".to_string(), AssignEntry::NoMap),
+            ("y".to_string(), AssignEntry::Var(0, false)),
+            ("z".to_string(), AssignEntry::Var(1, false)),
+            ("q".to_string(), AssignEntry::Var(0, false)),
+            ("cnt#0/o".to_string(), AssignEntry::Var(1, false)),
+            ("cnt#0/w".to_string(), AssignEntry::Var(2, false)),
+        ],
+        mapping
+    );
+    assert_eq!(
+        blif::BLIFInfo {
+            latch_inits: vec![Some(true), Some(false), None],
+            fsm_states: vec![],
+            exdc_ignored: false,
+        },
+        info
+    );
+}
+
+const TEST_GENLIB: &str = r##"GATE inv 1 O=!a;
+GATE buf 1 O=a;
+GATE nand2 2 Y=!(a*b);
+GATE and2 3 Y=a*b;
+GATE nor2 2 Y=!(a+b);
+GATE andn 3 Y=!a*b;
+GATE xor2 5 Y=a^b;
+LATCH dff 6 Q=D;
+PIN D NONINV 1 999 1 .2 1 .2
+SEQ Q ANY RISING_EDGE
+CONTROL CLK 1 999 1 .2 1 .2
+"##;
+
+#[test]
+fn test_to_blif_with_library() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 1),
+            Gate::new_nimpl(3, 2),
+            Gate::new_xor(0, 4),
+            Gate::new_nor(1, 5),
+        ],
+        [(5, true), (3, false), (6, false), (6, false)],
+    )
+    .unwrap();
+    let library = genlib::from_genlib(TEST_GENLIB.as_bytes()).unwrap();
+    let mut out = vec![];
+    blif::to_blif_with_options(
+        &circuit,
+        0,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            latch_inits: vec![],
+            library: Some(library.clone()),
+            max_names_inputs: 0,
+            names: WriterNames::default(),
+        },
+        &mut out,
+    )
+    .unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.outputs o0
+.outputs o1
+.outputs o2
+.outputs o3
+.gate and2 a=i0 b=i1 Y=o1
+.gate andn a=i2 b=o1 Y=i4
+.gate xor2 a=i0 b=i4 Y=i5
+.gate nor2 a=i1 b=i5 Y=o2
+.gate inv a=i5 O=o0
+.gate buf a=o2 O=o3
+.end
+"##,
+        text
+    );
+    // without cells for nor and buf
+    let mut out = vec![];
+    blif::to_blif_with_options(
+        &circuit,
+        0,
+        0,
+        "top",
+        &blif::BLIFOptions {
+            latch_inits: vec![],
+            library: Some(genlib::GenlibLibrary {
+                cells: library
+                    .cells
+                    .iter()
+                    .filter(|c| c.name != "nor2" && c.name != "buf")
+                    .cloned()
+                    .collect(),
+            }),
+            max_names_inputs: 0,
+            names: WriterNames::default(),
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.outputs o0
+.outputs o1
+.outputs o2
+.outputs o3
+.gate and2 a=i0 b=i1 Y=o1
+.gate andn a=i2 b=o1 Y=i4
+.gate xor2 a=i0 b=i4 Y=i5
+.names i1 i5 o2
+00 1
+.gate inv a=i5 O=o0
+.names o2 o3
+1 1
+.end
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // read it
+    let to_remove = write_files(strs2_to_vec_string([("xxxgates.blif", text.as_str())]));
+    let (result, mapping, _) = blif::from_blif_with_options(
+        &to_remove.0[0],
+        &blif::BLIFReadOptions {
+            library: Some(library),
+            exdc_outputs: false,
+            ..blif::BLIFReadOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        Circuit::new(
+            3,
+            [
+                Gate::new_and(0, 1),
+                Gate::new_nimpl(3, 2),
+                Gate::new_xor(4, 0),
+                Gate::new_nor(5, 1),
+            ],
+            [(5, true), (3, false), (6, false), (6, false)],
+        )
+        .unwrap(),
+        result
+    );
+    assert_eq!(
+        vec![
+            ("i0".to_string(), AssignEntry::Var(0, false)),
+            ("i1".to_string(), AssignEntry::Var(1, false)),
+            ("i2".to_string(), AssignEntry::Var(2, false)),
+            ("o0".to_string(), AssignEntry::Var(5, true)),
+            ("o1".to_string(), AssignEntry::Var(3, false)),
+            ("o2".to_string(), AssignEntry::Var(6, false)),
+            ("o3".to_string(), AssignEntry::Var(6, false)),
+        ],
+        mapping
+    );
+}
+
+#[test]
+fn test_from_blif_with_library() {
+    let library = genlib::from_genlib(TEST_GENLIB.as_bytes()).unwrap();
+    let options = blif::BLIFReadOptions {
+        library: Some(library),
+        exdc_outputs: false,
+        ..blif::BLIFReadOptions::default()
+    };
+    let to_remove = write_files(strs2_to_vec_string([
+        (
+            "xxxmlatch.blif",
+            r##".model top
+.inputs a b clk
+.outputs y
+.mlatch dff D=n Q=q clk 1
+.gate nand2 a=a b=q Y=n
+.gate buf a=q O=y
+.end
+"##,
+        ),
+        (
+            "xxxbadcell.blif",
+            r##".model top
+.inputs a b
+.outputs y
+.gate nand3 a=a b=b Y=y
+.end
+"##,
+        ),
+        (
+            "xxxbadmapping.blif",
+            r##".model top
+.inputs a b
+.outputs y
+.gate nand2 a=a c=b Y=y
+.end
+"##,
+        ),
+        (
+            "xxxbadtype.blif",
+            r##".model top
+.inputs a b
+.outputs y
+.mlatch nand2 a=a b=b Y=y clk
+.end
+"##,
+        ),
+        (
+            "xxxmlatchoutput.blif",
+            r##".model top
+.inputs a b clk
+.outputs y
+.gate nand2 a=a b=b Y=q
+.mlatch dff D=a Q=q clk
+.gate buf a=q O=y
+.end
+"##,
+        ),
+    ]));
+    assert_eq!(
+        (
+            Circuit::new(2, [Gate::new_and(1, 0)], [(2, true), (0, false)]).unwrap(),
+            vec![
+                ("a".to_string(), AssignEntry::Var(1, false)),
+                ("b".to_string(), AssignEntry::NoMap),
+                ("clk".to_string(), AssignEntry::NoMap),
+                ("y".to_string(), AssignEntry::Var(0, false)),
+                ("q".to_string(), AssignEntry::Var(0, false)),
+            ],
+            blif::BLIFInfo {
+                latch_inits: vec![Some(true)],
+                fsm_states: vec![],
+                exdc_ignored: false,
+            }
+        ),
+        blif::from_blif_with_options(&to_remove.0[0], &options).unwrap()
+    );
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[1], &options),
+        Err(blif::BLIFError::UnknownCell(_, 4, _))
+    ));
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[2], &options),
+        Err(blif::BLIFError::BadCellMapping(_, 4, _))
+    ));
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[3], &options),
+        Err(blif::BLIFError::BadCellType(_, 4, _))
+    ));
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[4], &options),
+        Err(blif::BLIFError::AlreadyDefinedAsOutput(_, 5, _))
+    ));
+    assert!(matches!(
+        blif::from_blif(&to_remove.0[0]),
+        Err(blif::BLIFError::UnsupportedGate(_, 4))
+    ));
+}
+
+#[test]
+fn test_from_blif_exdc() {
+    let to_remove = write_files(strs2_to_vec_string([
+        (
+            "xxxexdc.blif",
+            r##".model top
+.inputs a b c
+.outputs x y
+.names a b t
+11 1
+.names t c x
+1- 1
+-1 1
+.names a c y
+10 1
+.exdc
+.names a b t
+00 1
+.names t c x
+11 1
+.end
+"##,
+        ),
+        (
+            "xxxexdcbad.blif",
+            r##".model top
+.inputs a b
+.outputs x
+.names a b x
+11 1
+.exdc
+.latch x a
+.end
+"##,
+        ),
+    ]));
+    let options = blif::BLIFReadOptions {
+        library: None,
+        exdc_outputs: true,
+        ..blif::BLIFReadOptions::default()
+    };
+    let (circuit, mapping, _) = blif::from_blif_with_options(&to_remove.0[0], &options).unwrap();
+    assert_eq!(3, circuit.input_len());
+    assert_eq!(3, circuit.outputs().len());
+    assert_eq!(
+        vec!["a", "b", "c", "x", "y", "x#exdc", "y#exdc"],
+        mapping.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        ("y#exdc".to_string(), AssignEntry::Value(false)),
+        mapping[6]
+    );
+    // ignore external don't care network
+    let (circuit, mapping, info) = blif::from_blif_with_info(&to_remove.0[0]).unwrap();
+    assert!(info.exdc_ignored);
+    assert_eq!(3, circuit.input_len());
+    assert_eq!(2, circuit.outputs().len());
+    assert_eq!(
+        vec!["a", "b", "c", "x", "y"],
+        mapping.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[1], &options),
+        Err(blif::BLIFError::UnsupportedEXDC(_, 7))
+    ));
+    assert!(blif::from_blif(&to_remove.0[1]).is_ok());
+}
+
+#[test]
+fn test_from_blif_fsm() {
+    let to_remove = write_files(strs2_to_vec_string([
+        (
+            "xxxfsm.blif",
+            r##".model top
+.inputs a b
+.outputs y
+.start_kiss
+.i 2
+.o 1
+.r s1
+0- s0 s1 1
+1- s0 s0 0
+-1 s1 s2 0
+-0 s1 s1 1
+-- s2 s0 -
+.end_kiss
+.end
+"##,
+        ),
+        (
+            "xxxfsmnoend.blif",
+            r##".model top
+.inputs a b
+.outputs y
+.start_kiss
+.i 2
+.o 1
+00 s0 s1 1
+.end
+"##,
+        ),
+    ]));
+    let options = blif::BLIFReadOptions {
+        fsm_encoding: kiss2::KISS2Encoding::Gray,
+        ..blif::BLIFReadOptions::default()
+    };
+    let (circuit, mapping, info) = blif::from_blif_with_options(&to_remove.0[0], &options).unwrap();
+    assert_eq!(4, circuit.input_len());
+    assert_eq!(3, circuit.outputs().len());
+    assert_eq!(
+        vec!["a", "b", "y", "fsm#state0", "fsm#state1"],
+        mapping.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        blif::BLIFInfo {
+            latch_inits: vec![Some(true), Some(false)],
+            fsm_states: vec![
+                ("s0".to_string(), vec![false, false]),
+                ("s1".to_string(), vec![true, false]),
+                ("s2".to_string(), vec![true, true]),
+            ],
+            exdc_ignored: false,
+        },
+        info
+    );
+    assert!(matches!(
+        blif::from_blif_with_options(&to_remove.0[1], &options),
+        Err(blif::BLIFError::NoFSMEnd(_))
+    ));
+}
+
+#[test]
+fn test_to_blif_hier() {
+    let mut out = vec![];
+    blif::to_blif_hier(
+        &common::adder_hier_circuit(),
+        0,
+        0,
+        "top",
+        &blif::BLIFOptions::default(),
+        &mut out,
+    )
+    .unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert_eq!(
+        r##".model top
+.inputs i0
+.inputs i1
+.inputs i2
+.inputs i3
+.inputs i4
+.outputs o0
+.outputs o1
+.outputs o2
+.names i5 o0
+1 1
+.names i7 o1
+1 1
+.names i8 o2
+1 1
+.subckt fa i0=i0 i1=i1 i2=i4 o0=i5 o1=i6
+.subckt fa i0=i2 i1=i3 i2=i6 o0=i7 o1=i8
+.end
+.model fa
+.inputs i0
+.inputs i1
+.inputs i2
+.outputs o0
+.outputs o1
+.names i0 i1 i3
+10 1
+01 1
+.names i3 i2 o0
+10 1
+01 1
+.names i0 i1 i5
+11 1
+.names i3 i2 i6
+11 1
+.names i5 i6 i7
+00 1
+.names i7 o1
+0 1
+.end
+"##,
+        text
+    );
+    // read it and check whether it is two-bit adder
+    let to_remove = write_files(strs2_to_vec_string([("xxxhier.blif", text.as_str())]));
+    let (circuit, _) = blif::from_blif(&to_remove.0[0]).unwrap();
+    for v in 0..32 {
+        let a = (v & 1) + ((v >> 1) & 2);
+        let b = ((v >> 1) & 1) + ((v >> 2) & 2);
+        let s = a + b + ((v >> 4) & 1);
+        assert_eq!(
+            vec![(s & 1) != 0, (s & 2) != 0, (s & 4) != 0],
+            circuit.eval((0..5).map(|i| ((v >> i) & 1) != 0)),
+            "{}",
+            v
+        );
+    }
+    // sub-circuit with name of top model
+    let err = blif::to_blif_hier(
+        &common::adder_hier_circuit(),
+        0,
+        0,
+        "fa",
+        &blif::BLIFOptions::default(),
+        &mut vec![],
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!("Name collision: fa", err.to_string());
+}
@@ -0,0 +1,272 @@
+use gateconvert::bristol::{self, BristolInfo};
+use gateconvert::AssignEntry;
+use gateutil::gatesim::*;
+
+fn to_bristol_helper(circuit: Circuit<usize>) -> String {
+    let mut out = vec![];
+    bristol::to_bristol(&circuit, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_to_bristol() {
+    assert_eq!(
+        "0 0\n0\n0\n\n",
+        to_bristol_helper(Circuit::new(0, [], []).unwrap()).as_str()
+    );
+    assert_eq!(
+        r##"10 13
+1 3
+1 6
+
+2 1 0 2 3 AND
+1 1 1 11 INV
+1 1 2 4 INV
+2 1 11 4 5 AND
+2 1 3 5 7 XOR
+1 1 0 6 INV
+2 1 7 6 9 AND
+1 1 3 8 INV
+1 1 9 10 EQW
+1 1 0 12 EQW
+"##,
+        to_bristol_helper(
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 2),
+                    Gate::new_nor(1, 2),
+                    Gate::new_xor(3, 4),
+                    Gate::new_nimpl(5, 0),
+                ],
+                [
+                    (5, false),
+                    (3, true),
+                    (6, false),
+                    (6, false),
+                    (1, true),
+                    (0, false)
+                ],
+            )
+            .unwrap()
+        )
+    );
+    assert_eq!(
+        r##"11 15
+1 4
+1 2
+
+1 1 0 4 INV
+1 1 1 5 INV
+2 1 4 5 6 AND
+1 1 2 7 INV
+1 1 3 8 INV
+2 1 7 8 9 AND
+1 1 9 10 INV
+1 1 6 11 INV
+2 1 10 11 14 AND
+2 1 14 11 12 AND
+1 1 12 13 INV
+"##,
+        to_bristol_helper(
+            Circuit::new(
+                4,
+                [
+                    Gate::new_nor(0, 1),
+                    Gate::new_nor(2, 3),
+                    Gate::new_nor(4, 5),
+                    Gate::new_nimpl(6, 4),
+                ],
+                [(7, true), (6, false)],
+            )
+            .unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_to_bristol_with_info() {
+    let mut out = vec![];
+    bristol::to_bristol_with_info(
+        &Circuit::new(
+            4,
+            [
+                Gate::new_xor(0, 2),
+                Gate::new_and(1, 3),
+                Gate::new_nimpl(4, 5),
+            ],
+            [(4, false), (5, true), (6, false)],
+        )
+        .unwrap(),
+        &BristolInfo {
+            input_values: vec![2, 2],
+            output_values: vec![1, 2],
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"4 8
+2 2 2
+2 1 2
+
+2 1 0 2 5 XOR
+2 1 1 3 4 AND
+1 1 4 6 INV
+2 1 5 6 7 AND
+"##,
+        String::from_utf8(out).unwrap()
+    );
+}
+
+type BristolResult = (Circuit<usize>, Vec<(usize, AssignEntry)>, BristolInfo);
+
+fn from_bristol_helper(text: &str) -> Result<BristolResult, String> {
+    bristol::from_bristol_with_info(text.as_bytes()).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_from_bristol() {
+    // output of to_bristol
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 2),
+                    Gate::new_nor(1, 2),
+                    Gate::new_xor(3, 4),
+                    Gate::new_nimpl(5, 0),
+                ],
+                [
+                    (5, false),
+                    (3, true),
+                    (6, false),
+                    (6, false),
+                    (1, true),
+                    (0, false)
+                ],
+            )
+            .unwrap(),
+            vec![
+                (0, AssignEntry::Var(0, false)),
+                (1, AssignEntry::Var(1, false)),
+                (2, AssignEntry::Var(2, false)),
+                (7, AssignEntry::Var(5, false)),
+                (8, AssignEntry::Var(3, true)),
+                (9, AssignEntry::Var(6, false)),
+                (10, AssignEntry::Var(6, false)),
+                (11, AssignEntry::Var(1, true)),
+                (12, AssignEntry::Var(0, false)),
+            ],
+            BristolInfo {
+                input_values: vec![3],
+                output_values: vec![6],
+            }
+        )),
+        from_bristol_helper(
+            r##"10 13
+1 3
+1 6
+
+2 1 0 2 3 AND
+1 1 1 11 INV
+1 1 2 4 INV
+2 1 11 4 5 AND
+2 1 3 5 7 XOR
+1 1 0 6 INV
+2 1 7 6 9 AND
+1 1 3 8 INV
+1 1 9 10 EQW
+1 1 0 12 EQW
+"##
+        )
+    );
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                4,
+                [
+                    Gate::new_and(0, 2),
+                    Gate::new_and(1, 3),
+                    Gate::new_xor(4, 5),
+                    Gate::new_and(4, 3),
+                ],
+                [(6, true), (7, false)],
+            )
+            .unwrap(),
+            vec![
+                (0, AssignEntry::Var(0, false)),
+                (1, AssignEntry::Var(1, false)),
+                (2, AssignEntry::Var(2, false)),
+                (3, AssignEntry::Var(3, false)),
+                (10, AssignEntry::Value(true)),
+                (11, AssignEntry::Var(6, true)),
+                (12, AssignEntry::Var(7, false)),
+            ],
+            BristolInfo {
+                input_values: vec![2, 2],
+                output_values: vec![1, 2],
+            }
+        )),
+        from_bristol_helper(
+            r##"7 13
+2 2 2
+2 1 2
+
+4 2 0 1 2 3 4 5 MAND
+2 1 4 5 6 XOR
+1 1 6 7 INV
+1 1 1 10 EQ
+1 1 7 11 EQW
+2 1 4 3 12 AND
+1 1 0 8 EQW
+"##
+        )
+    );
+    for (result, text) in [
+        ("5: Unknown gate OR", "1 3\n1 2\n1 1\n\n2 1 0 1 2 OR\n"),
+        (
+            "5: Wrong number of arguments of AND",
+            "1 3\n1 2\n1 1\n\n3 1 0 1 2 2 AND\n",
+        ),
+        (
+            "5: Syntax error: 3 1 0 1 2 AND",
+            "1 3\n1 2\n1 1\n\n3 1 0 1 2 AND\n",
+        ),
+        (
+            "5: Syntax error: 1 1 2 2 EQ",
+            "1 3\n1 2\n1 1\n\n1 1 2 2 EQ\n",
+        ),
+        (
+            "2: Syntax error: 1 2 x",
+            "1 3\n1 2 x\n1 1\n\n2 1 0 1 2 AND\n",
+        ),
+        ("2: Syntax error: 2 2", "1 3\n2 2\n1 1\n\n2 1 0 1 2 AND\n"),
+        ("5: Wire 3 out of range", "1 3\n1 2\n1 1\n\n2 1 0 1 3 AND\n"),
+        (
+            "6: Wire 2 already driven",
+            "2 3\n1 2\n1 1\n\n2 1 0 1 2 AND\n2 1 0 1 2 XOR\n",
+        ),
+        (
+            "Wrong number of gates: expected 2, got 1",
+            "2 3\n1 2\n1 1\n\n2 1 0 1 2 AND\n",
+        ),
+        ("Wire 3 is undriven", "1 4\n1 2\n1 1\n\n2 1 0 1 2 AND\n"),
+        (
+            "Cycle in circuit caused by 3",
+            "2 4\n1 2\n1 1\n\n2 1 0 3 2 AND\n2 1 0 2 3 XOR\n",
+        ),
+        (
+            "Too many inputs and outputs",
+            "1 3\n1 2\n1 2\n\n2 1 0 1 2 AND\n",
+        ),
+    ] {
+        assert_eq!(
+            Err(result.to_string()),
+            from_bristol_helper(text),
+            "{}",
+            text
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use gateconvert::btor2;
+use gateconvert::btor2::{self, BTOR2Options};
+use gateconvert::AssignEntry;
 use gateutil::gatesim::*;
 
 fn to_btor2_helper(circuit: Circuit<usize>, state_len: usize) -> String {
@@ -251,3 +252,299 @@ fn test_to_btor2() {
         .as_str()
     );
 }
+
+#[test]
+fn test_to_btor2_with_options() {
+    let mut out = vec![];
+    btor2::to_btor2_with_options(
+        Circuit::new(
+            4,
+            [Gate::new_and(0, 3), Gate::new_xor(1, 2)],
+            [(4, false), (5, true), (0, false), (2, true)],
+        )
+        .unwrap(),
+        3,
+        &BTOR2Options {
+            state_inits: vec![Some(true), None, Some(true)],
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"1 sort bitvec 1
+2 state 1
+3 state 1
+4 state 1
+5 input 1
+6 and 1 2 5
+7 xor 1 3 4
+8 next 1 2 6
+9 not 1 7
+10 next 1 3 9
+11 next 1 4 2
+12 not 1 4
+13 output 12
+14 one 1
+15 init 1 2 14
+16 init 1 4 14
+"##,
+        String::from_utf8(out).unwrap()
+    );
+}
+
+type BTOR2Result = (Circuit<usize>, Vec<((usize, usize), AssignEntry)>);
+
+fn from_btor2_helper(text: &str) -> Result<BTOR2Result, String> {
+    btor2::from_btor2(text.as_bytes()).map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_from_btor2() {
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                2,
+                [Gate::new_and(0, 1), Gate::new_nimpl(1, 0)],
+                [(2, false), (3, false)]
+            )
+            .unwrap(),
+            vec![
+                ((2, 0), AssignEntry::Var(0, false)),
+                ((3, 0), AssignEntry::Var(1, false)),
+                ((6, 0), AssignEntry::Var(2, false)),
+                ((7, 0), AssignEntry::Var(3, false)),
+            ]
+        )),
+        from_btor2_helper(concat!(
+            "1 sort bitvec 1\n2 input 1\n3 input 1\n4 and 1 2 3\n5 or 1 2 -3\n",
+            "6 output 4\n7 output -5 ; negated\n"
+        ))
+    );
+    // state with next state and bad property
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                2,
+                [Gate::new_xor(0, 1)],
+                [(2, false), (0, false), (2, true)]
+            )
+            .unwrap(),
+            vec![
+                ((2, 0), AssignEntry::Var(0, false)),
+                ((3, 0), AssignEntry::Var(1, false)),
+                ((5, 0), AssignEntry::Var(2, false)),
+                ((6, 0), AssignEntry::Var(0, false)),
+                ((7, 0), AssignEntry::Var(2, true)),
+            ]
+        )),
+        from_btor2_helper(concat!(
+            "1 sort bitvec 1\n2 state 1\n3 input 1\n4 xor 1 2 3\n5 next 1 2 4\n",
+            "6 output 2\n7 bad -4\n"
+        ))
+    );
+    // justice property - arguments have distinct bit indices
+    assert_eq!(
+        Ok((
+            Circuit::new(2, [], [(0, false), (1, true)]).unwrap(),
+            vec![
+                ((2, 0), AssignEntry::Var(0, false)),
+                ((3, 0), AssignEntry::Var(1, false)),
+                ((4, 0), AssignEntry::Var(0, false)),
+                ((4, 1), AssignEntry::Var(1, true)),
+            ]
+        )),
+        from_btor2_helper("1 sort bitvec 1\n2 input 1\n3 input 1\n4 justice 2 2 -3\n")
+    );
+    // bit-vectors
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                2,
+                [Gate::new_xor(1, 0), Gate::new_and(1, 0)],
+                [(0, true), (2, true), (3, true)]
+            )
+            .unwrap(),
+            vec![
+                ((3, 0), AssignEntry::Var(0, false)),
+                ((3, 1), AssignEntry::Var(1, false)),
+                ((7, 0), AssignEntry::Var(0, true)),
+                ((7, 1), AssignEntry::Var(2, true)),
+                ((8, 0), AssignEntry::Var(3, true)),
+            ]
+        )),
+        from_btor2_helper(concat!(
+            "1 sort bitvec 2\n2 sort bitvec 1\n3 input 1\n4 constd 1 -1\n",
+            "5 add 1 3 4\n6 ult 2 3 4\n7 output 5\n8 output 6\n"
+        ))
+    );
+    // round trip
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 2),
+            Gate::new_nor(1, 2),
+            Gate::new_xor(3, 4),
+        ],
+        [(5, false), (3, true)],
+    )
+    .unwrap();
+    let (circuit2, _) = from_btor2_helper(&to_btor2_helper(circuit.clone(), 1)).unwrap();
+    assert_eq!(circuit, circuit2);
+    // errors
+    assert_eq!(
+        Err("3: Unknown node 5".to_string()),
+        from_btor2_helper("1 sort bitvec 1\n2 input 1\n3 and 1 2 5\n")
+    );
+    assert_eq!(
+        Err("4: Sort mismatch".to_string()),
+        from_btor2_helper("1 sort bitvec 1\n2 sort bitvec 2\n3 input 2\n4 and 1 3 3\n")
+    );
+    assert_eq!(
+        Err("3: Unsupported array sort".to_string()),
+        from_btor2_helper("1 sort bitvec 1\n2 sort array 1 1\n3 input 2\n")
+    );
+    assert_eq!(
+        Err("2: Unknown operator foo".to_string()),
+        from_btor2_helper("1 sort bitvec 1\n2 foo 1\n")
+    );
+}
+
+fn from_btor2_with_options_helper(text: &str) -> Result<BTOR2Options, String> {
+    btor2::from_btor2_with_options(text.as_bytes())
+        .map(|(_, _, options)| options)
+        .map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_from_btor2_with_options() {
+    let circuit = Circuit::new(
+        4,
+        [Gate::new_and(0, 3), Gate::new_xor(1, 2)],
+        [(4, false), (5, true), (0, false), (2, true)],
+    )
+    .unwrap();
+    let options = BTOR2Options {
+        state_inits: vec![Some(true), None, Some(true)],
+    };
+    let mut out = vec![];
+    btor2::to_btor2_with_options(circuit.clone(), 3, &options, &mut out).unwrap();
+    let (circuit2, _, options2) = btor2::from_btor2_with_options(out.as_slice()).unwrap();
+    assert_eq!(circuit, circuit2);
+    assert_eq!(options, options2);
+    // state without next state is input
+    assert_eq!(
+        Ok(BTOR2Options {
+            state_inits: vec![Some(false), Some(true)]
+        }),
+        from_btor2_with_options_helper(concat!(
+            "1 sort bitvec 2\n2 state 1\n3 state 1\n4 input 1\n5 add 1 2 4\n",
+            "6 next 1 2 5\n7 constd 1 2\n8 init 1 2 7\n9 init 1 3 7\n"
+        ))
+    );
+    assert_eq!(
+        Err("5: Initial state is not constant".to_string()),
+        from_btor2_with_options_helper(
+            "1 sort bitvec 1
+2 state 1
+3 input 1
+4 next 1 2 3
+5 init 1 2 3
+"
+        )
+    );
+    assert_eq!(
+        Err("5: Initial state for 2 already defined".to_string()),
+        from_btor2_with_options_helper(
+            "1 sort bitvec 1
+2 state 1
+3 zero 1
+4 init 1 2 3
+5 init 1 2 3
+"
+        )
+    );
+}
+
+// evaluate outputs of BTOR2 nodes by using circuit and mapping
+fn eval_btor2_outputs(
+    circuit: &Circuit<usize>,
+    mapping: &[((usize, usize), AssignEntry)],
+    input_len: usize,
+    inputs: u64,
+) -> Vec<bool> {
+    let mut circ_inputs = vec![false; circuit.input_len()];
+    for (i, (_, e)) in mapping[0..input_len].iter().enumerate() {
+        if let AssignEntry::Var(idx, _) = e {
+            circ_inputs[*idx] = ((inputs >> i) & 1) != 0;
+        }
+    }
+    let outputs = circuit.eval(circ_inputs);
+    let mut out_count = 0;
+    mapping[input_len..]
+        .iter()
+        .map(|(_, e)| match e {
+            AssignEntry::Value(v) => *v,
+            AssignEntry::Var(_, _) => {
+                out_count += 1;
+                outputs[out_count - 1]
+            }
+            AssignEntry::NoMap => panic!("Unexpected"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_from_btor2_arith() {
+    let ops = [
+        "add", "sub", "mul", "udiv", "urem", "sdiv", "srem", "smod", "sll", "srl", "sra", "rol",
+        "ror",
+    ];
+    let mut text = "1 sort bitvec 3\n2 input 1\n3 input 1\n".to_string();
+    for (i, op) in ops.iter().enumerate() {
+        text += &format!("{} {} 1 2 3\n", 4 + i, op);
+    }
+    for i in 0..ops.len() {
+        text += &format!("{} output {}\n", 4 + ops.len() + i, 4 + i);
+    }
+    let (circuit, mapping) = from_btor2_helper(&text).unwrap();
+    let sext = |x: u64| ((x as i64) << 61) >> 61;
+    for v in 0..64u64 {
+        let (a, b) = (v & 7, v >> 3);
+        let (sa, sb) = (sext(a), sext(b));
+        let expected = [
+            a + b,
+            a.wrapping_sub(b),
+            a * b,
+            a.checked_div(b).unwrap_or(7),
+            if b != 0 { a % b } else { a },
+            if b != 0 {
+                (sa / sb) as u64
+            } else if sa >= 0 {
+                7
+            } else {
+                1
+            },
+            if b != 0 { (sa % sb) as u64 } else { a },
+            if b != 0 {
+                let r = sa % sb;
+                (if r != 0 && ((r < 0) != (sb < 0)) {
+                    r + sb
+                } else {
+                    r
+                }) as u64
+            } else {
+                a
+            },
+            if b < 3 { a << b } else { 0 },
+            if b < 3 { a >> b } else { 0 },
+            (sa >> b.min(2)) as u64,
+            ((a << (b % 3)) | (a >> ((3 - b % 3) % 3))) & 7,
+            ((a >> (b % 3)) | (a << ((3 - b % 3) % 3))) & 7,
+        ];
+        let outputs = eval_btor2_outputs(&circuit, &mapping, 6, v);
+        for (i, e) in expected.iter().enumerate() {
+            let result = (0..3).fold(0u64, |r, bit| r | (u64::from(outputs[3 * i + bit]) << bit));
+            assert_eq!(e & 7, result, "{} {} {}", ops[i], a, b);
+        }
+    }
+}
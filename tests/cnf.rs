@@ -27,6 +27,111 @@ fn test_to_cnf() {
     );
 }
 
+fn to_cnf_with_options_helper(
+    circuit: Circuit<usize>,
+    output_mode: cnf::CNFOutputMode,
+    xor_clauses: bool,
+) -> Result<(String, Vec<Option<usize>>), String> {
+    let mut out = vec![];
+    let options = cnf::CNFOptions {
+        output_mode,
+        xor_clauses,
+    };
+    cnf::to_cnf_with_options(&circuit, options, &mut out)
+        .map(|map| (String::from_utf8(out).unwrap(), map))
+        .map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_to_cnf_with_options() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 1),
+            Gate::new_nor(0, 2),
+            Gate::new_nimpl(3, 2),
+            Gate::new_xor(4, 5),
+        ],
+        [(6, false), (3, true)],
+    )
+    .unwrap();
+    let gate_clauses = concat!(
+        "1 -4 0\n2 -4 0\n-1 -2 4 0\n",
+        "-1 -5 0\n-3 -5 0\n1 3 5 0\n",
+        "4 -6 0\n-3 -6 0\n3 -4 6 0\n",
+        "5 6 -7 0\n-5 -6 -7 0\n-5 6 7 0\n5 -6 7 0\n"
+    );
+    assert_eq!(
+        Ok((
+            format!("p cnf 7 15\n{}7 0\n-4 0\n", gate_clauses),
+            vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7)
+            ]
+        )),
+        to_cnf_with_options_helper(circuit.clone(), cnf::CNFOutputMode::Constrained, false)
+    );
+    assert_eq!(
+        Ok((
+            format!("p cnf 7 13\n{}", gate_clauses),
+            vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7)
+            ]
+        )),
+        to_cnf_with_options_helper(circuit, cnf::CNFOutputMode::Free, false)
+    );
+    assert_eq!(
+        Ok(("p cnf 2 2\n1 0\n-2 0\n".to_string(), vec![Some(1), Some(2)])),
+        to_cnf_with_options_helper(
+            Circuit::new(2, [], [(0, false), (1, true)]).unwrap(),
+            cnf::CNFOutputMode::Constrained,
+            false
+        )
+    );
+    // XOR chains as XOR clauses
+    assert_eq!(
+        Ok((
+            "p cnf 6 6\nx1 2 3 4 -5 0\n1 -6 0\n4 -6 0\n-1 -4 6 0\n5 0\n-6 0\n".to_string(),
+            vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                None,
+                None,
+                Some(5),
+                Some(6)
+            ]
+        )),
+        to_cnf_with_options_helper(
+            Circuit::new(
+                4,
+                [
+                    Gate::new_xor(0, 1),
+                    Gate::new_xor(4, 2),
+                    Gate::new_xor(5, 3),
+                    Gate::new_and(0, 3),
+                ],
+                [(6, false), (7, true)]
+            )
+            .unwrap(),
+            cnf::CNFOutputMode::Constrained,
+            true
+        )
+    );
+}
+
 fn from_cnf_helper(code: &str) -> Result<(Circuit<usize>, Vec<Option<usize>>), String> {
     let mut b = code.as_bytes();
     cnf::from_cnf(&mut b).map_err(|x| x.to_string())
@@ -83,3 +188,232 @@ fn test_from_cnf() {
         from_cnf_helper("p cnf 4 3\n1 4 0\n3 -3 0\n-2 2 0\n"),
     );
 }
+
+fn from_cnf_with_options_helper(
+    code: &str,
+    recover_gates: bool,
+    xor_clauses: bool,
+) -> Result<(Circuit<usize>, Vec<cnf::CNFVarEntry>), String> {
+    let mut b = code.as_bytes();
+    let options = cnf::CNFReadOptions {
+        recover_gates,
+        xor_clauses,
+    };
+    cnf::from_cnf_with_options(&mut b, options).map_err(|x| x.to_string())
+}
+
+#[test]
+fn test_from_cnf_with_options() {
+    use cnf::CNFVarEntry::*;
+    // without recovery
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                2,
+                [
+                    Gate::new_nimpl(1, 0),
+                    Gate::new_and(0, 1),
+                    Gate::new_nor(2, 3)
+                ],
+                [(4, false)]
+            )
+            .unwrap(),
+            vec![Input(Some(0)), Input(Some(1))]
+        )),
+        from_cnf_with_options_helper("p cnf 2 2\n1 -2 0\n-1 -2 0\n", false, false),
+    );
+    // Tseitin encoding of circuit from to_cnf_with_options
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_nor(0, 2),
+                    Gate::new_and(0, 1),
+                    Gate::new_nimpl(4, 2),
+                    Gate::new_xor(3, 5),
+                    Gate::new_nimpl(6, 4),
+                ],
+                [(7, false)]
+            )
+            .unwrap(),
+            vec![
+                Input(Some(0)),
+                Input(Some(1)),
+                Input(Some(2)),
+                GateOutput,
+                GateOutput,
+                GateOutput,
+                GateOutput
+            ]
+        )),
+        from_cnf_with_options_helper(
+            concat!(
+                "p cnf 7 15\n1 -4 0\n2 -4 0\n-1 -2 4 0\n",
+                "-1 -5 0\n-3 -5 0\n1 3 5 0\n",
+                "4 -6 0\n-3 -6 0\n3 -4 6 0\n",
+                "5 6 -7 0\n-5 -6 -7 0\n-5 6 7 0\n5 -6 7 0\n7 0\n-4 0\n"
+            ),
+            true,
+            false
+        ),
+    );
+    // AND definition with constraints
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 1),
+                    Gate::new_nimpl(3, 2),
+                    Gate::new_nor(4, 1),
+                    Gate::new_and(4, 1),
+                    Gate::new_nor(5, 6),
+                ],
+                [(7, false)]
+            )
+            .unwrap(),
+            vec![Input(Some(0)), Input(Some(1)), Input(Some(2)), GateOutput]
+        )),
+        from_cnf_with_options_helper(
+            "p cnf 4 6\n1 -4 0\n2 -4 0\n-3 -4 0\n-1 -2 3 4 0\n2 4 0\n-2 -4 0\n",
+            true,
+            false
+        ),
+    );
+    // ITE definition
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                4,
+                [
+                    Gate::new_nor(0, 1),
+                    Gate::new_nimpl(4, 2),
+                    Gate::new_nimpl(5, 3),
+                    Gate::new_and(0, 3),
+                    Gate::new_nor(6, 7),
+                    Gate::new_and(0, 1),
+                    Gate::new_nimpl(2, 0),
+                    Gate::new_nor(9, 10),
+                    Gate::new_nimpl(11, 3),
+                    Gate::new_nimpl(8, 12),
+                ],
+                [(13, false)]
+            )
+            .unwrap(),
+            vec![
+                Input(Some(0)),
+                Input(Some(1)),
+                Input(Some(2)),
+                GateOutput,
+                Input(Some(3))
+            ]
+        )),
+        from_cnf_with_options_helper(
+            "p cnf 5 7\n-1 2 -4 0\n-1 -2 4 0\n1 3 -4 0\n1 -3 4 0\n1 2 3 5 0\n-5 -1 0\n4 5 0\n",
+            true,
+            false
+        ),
+    );
+    // XOR definition
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_xor(0, 1),
+                    Gate::new_xor(3, 2),
+                    Gate::new_and(4, 0)
+                ],
+                [(5, true)]
+            )
+            .unwrap(),
+            vec![Input(Some(0)), Input(Some(1)), Input(Some(2)), GateOutput]
+        )),
+        from_cnf_with_options_helper(
+            concat!(
+                "p cnf 4 9\n1 2 3 -4 0\n1 2 -3 4 0\n1 -2 3 4 0\n1 -2 -3 -4 0\n",
+                "-1 2 3 4 0\n-1 2 -3 -4 0\n-1 -2 3 -4 0\n-1 -2 -3 4 0\n-4 -1 0\n"
+            ),
+            true,
+            false
+        ),
+    );
+    // XOR clauses
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [Gate::new_xor(0, 1), Gate::new_nimpl(2, 3)],
+                [(4, false)]
+            )
+            .unwrap(),
+            vec![Input(Some(0)), Input(Some(1)), Input(Some(2))]
+        )),
+        from_cnf_with_options_helper("p cnf 3 2\nx1 -2 0\nc x\n  x 2 3 2 0\n", false, true),
+    );
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                4,
+                [
+                    Gate::new_xor(0, 1),
+                    Gate::new_xor(2, 3),
+                    Gate::new_xor(4, 5),
+                    Gate::new_and(0, 3),
+                    Gate::new_nimpl(6, 7),
+                ],
+                [(8, false)]
+            )
+            .unwrap(),
+            vec![
+                Input(Some(0)),
+                Input(Some(1)),
+                Input(Some(2)),
+                Input(Some(3)),
+                GateOutput,
+                GateOutput
+            ]
+        )),
+        from_cnf_with_options_helper(
+            "p cnf 6 6\nx1 2 3 4 -5 0\n1 -6 0\n4 -6 0\n-1 -4 6 0\n5 0\n-6 0\n",
+            true,
+            true
+        ),
+    );
+    assert_eq!(
+        Err("3:1: expected literal or terminating zero, found \"3a\"".to_string()),
+        from_cnf_with_options_helper("p cnf 3 2\nx1 -2 0\nx 2 3a 0\n", false, true),
+    );
+    assert_eq!(
+        Err("3:1: variable 4 out of range".to_string()),
+        from_cnf_with_options_helper("p cnf 3 2\nx1 -2 0\nx 2 4 0\n", false, true),
+    ); // variable count from greatest variable if header is missing or has zero count
+    for code in ["1 -2 0\nx2 3 0\n", "p cnf 0 2\n1 -2 0\nx2 3 0\n"] {
+        assert_eq!(
+            Ok((
+                Circuit::new(
+                    3,
+                    [
+                        Gate::new_nimpl(1, 0),
+                        Gate::new_xor(1, 2),
+                        Gate::new_nimpl(4, 3),
+                    ],
+                    [(5, false)]
+                )
+                .unwrap(),
+                vec![Input(Some(0)), Input(Some(1)), Input(Some(2))]
+            )),
+            from_cnf_with_options_helper(code, false, true),
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        Ok((
+            Circuit::new(2, [Gate::new_nimpl(1, 0)], [(2, true)]).unwrap(),
+            vec![Input(Some(0)), Input(Some(1))]
+        )),
+        from_cnf_with_options_helper("p cnf 0 1\n1 -2 0\n", false, false),
+    );
+}
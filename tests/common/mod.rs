@@ -0,0 +1,37 @@
+use gateconvert::HierCircuit;
+use gateutil::gatesim::*;
+
+// two-bit adder from two instances of full adder.
+pub fn adder_hier_circuit() -> HierCircuit {
+    let full_adder = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_xor(3, 2),
+            Gate::new_and(0, 1),
+            Gate::new_and(3, 2),
+            Gate::new_nor(5, 6),
+        ],
+        [(4, false), (7, true)],
+    )
+    .unwrap();
+    // inputs: a0, b0, a1, b1, c, instance outputs: s0, c0, s1, c1
+    // outputs: s0, s1, c1, instance inputs: a0, b0, c, a1, b1, c0
+    let top = Circuit::new(
+        9,
+        [],
+        [
+            (5, false),
+            (7, false),
+            (8, false),
+            (0, false),
+            (1, false),
+            (4, false),
+            (2, false),
+            (3, false),
+            (6, false),
+        ],
+    )
+    .unwrap();
+    HierCircuit::new([("fa".to_string(), full_adder)], [0, 0], top).unwrap()
+}
@@ -0,0 +1,93 @@
+use gateconvert::genlib::*;
+
+fn from_genlib_helper(text: &str) -> GenlibLibrary {
+    from_genlib(text.as_bytes()).unwrap()
+}
+
+#[test]
+fn test_from_genlib() {
+    use GenlibExpr::*;
+    assert_eq!(
+        GenlibLibrary {
+            cells: vec![
+                GenlibCell {
+                    name: "zero".to_string(),
+                    area: 0.0,
+                    output: "O".to_string(),
+                    inputs: vec![],
+                    function: Value(false),
+                    seq: None,
+                },
+                GenlibCell {
+                    name: "inv".to_string(),
+                    area: 1.0,
+                    output: "O".to_string(),
+                    inputs: vec!["a".to_string()],
+                    function: Not(Box::new(Input(0))),
+                    seq: None,
+                },
+                GenlibCell {
+                    name: "aoi21".to_string(),
+                    area: 3.5,
+                    output: "Y".to_string(),
+                    inputs: vec!["c".to_string(), "a".to_string(), "b".to_string()],
+                    function: Not(Box::new(Or(
+                        Box::new(And(Box::new(Input(1)), Box::new(Input(2)))),
+                        Box::new(Input(0))
+                    ))),
+                    seq: None,
+                },
+                GenlibCell {
+                    name: "andn xor".to_string(),
+                    area: 4.0,
+                    output: "O".to_string(),
+                    inputs: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                    function: Xor(
+                        Box::new(And(Box::new(Input(0)), Box::new(Not(Box::new(Input(1)))))),
+                        Box::new(Not(Box::new(Input(2))))
+                    ),
+                    seq: None,
+                },
+                GenlibCell {
+                    name: "dff".to_string(),
+                    area: 6.0,
+                    output: "Q".to_string(),
+                    inputs: vec!["D".to_string()],
+                    function: Input(0),
+                    seq: Some(GenlibSeq {
+                        seq_type: "RISING_EDGE".to_string(),
+                        control: Some("CLK".to_string()),
+                    }),
+                },
+            ]
+        },
+        from_genlib_helper(
+            r##"# simple library
+GATE zero 0 O=CONST0;
+GATE inv 1 O=!a;
+PIN * INV 1 999 1 0.2 1 0.2
+GATE aoi21 3.5 Y=!(a*b + c);
+PIN c INV 1 999 1 0.2 1 0.2
+PIN a INV 1 999 1 0.2 1 0.2
+GATE "andn xor" 4 O=a b' ^ !c;
+LATCH dff 6 Q=D;
+PIN D NONINV 1 999 1 .2 1 .2
+SEQ Q ANY RISING_EDGE
+CONTROL CLK 1 999 1 .2 1 .2
+CONSTRAINT D 0.2 0.2
+"##
+        )
+    );
+    assert!(matches!(
+        from_genlib("GATE inv 1 O=!a".as_bytes()),
+        Err(GenlibError::UnexpectedEOF)
+    ));
+    assert!(matches!(
+        from_genlib("GATE inv 1 O=!a;\nGATE inv 1 O=a;".as_bytes()),
+        Err(GenlibError::CellAlreadyDefined(2, _))
+    ));
+    assert!(matches!(
+        from_genlib("PIN * INV 1 999 1 0.2 1 0.2".as_bytes()),
+        Err(GenlibError::OutsideOfCell(1))
+    ));
+}
@@ -0,0 +1,139 @@
+use gateconvert::kiss2;
+
+const TEST_FSM: &str = r##"# test fsm
+.i 2
+.o 1
+.p 5
+.s 3
+.r s1
+0- s0 s1 1
+1- s0 s0 0
+-1 s1 s2 0
+-0 s1 s1 1
+-- s2 s0 -
+.e
+"##;
+
+// expected (next state, output) for state and inputs
+fn test_fsm_expected(state: usize, a: bool, b: bool) -> (usize, bool) {
+    match state {
+        0 => (if a { 0 } else { 1 }, !a),
+        1 => (if b { 2 } else { 1 }, !b),
+        _ => (0, false),
+    }
+}
+
+fn from_kiss2_helper(encoding: kiss2::KISS2Encoding, codes: [Vec<bool>; 3], reset: Vec<bool>) {
+    let (circuit, info) = kiss2::from_kiss2(TEST_FSM.as_bytes(), encoding).unwrap();
+    let state_len = codes[0].len();
+    assert_eq!(
+        kiss2::KISS2Info {
+            state_codes: vec![
+                ("s0".to_string(), codes[0].clone()),
+                ("s1".to_string(), codes[1].clone()),
+                ("s2".to_string(), codes[2].clone()),
+            ],
+            reset_state: "s1".to_string(),
+            state_inits: reset.into_iter().map(Some).collect(),
+        },
+        info
+    );
+    assert_eq!(state_len + 2, circuit.input_len());
+    assert_eq!(state_len + 1, circuit.outputs().len());
+    for (state, code) in codes.iter().enumerate() {
+        for i in 0..4 {
+            let (a, b) = ((i & 1) != 0, (i & 2) != 0);
+            let (next, out) = test_fsm_expected(state, a, b);
+            let result = circuit.eval(code.iter().copied().chain([a, b]));
+            assert_eq!(
+                codes[next].iter().copied().chain([out]).collect::<Vec<_>>(),
+                result,
+                "{:?} {} {}",
+                encoding,
+                state,
+                i
+            );
+        }
+    }
+}
+
+#[test]
+fn test_from_kiss2() {
+    from_kiss2_helper(
+        kiss2::KISS2Encoding::Binary,
+        [vec![false, false], vec![true, false], vec![false, true]],
+        vec![true, false],
+    );
+    from_kiss2_helper(
+        kiss2::KISS2Encoding::Gray,
+        [vec![false, false], vec![true, false], vec![true, true]],
+        vec![true, false],
+    );
+    from_kiss2_helper(
+        kiss2::KISS2Encoding::OneHot,
+        [
+            vec![true, false, false],
+            vec![false, true, false],
+            vec![false, false, true],
+        ],
+        vec![false, true, false],
+    );
+    // unused input and constant output
+    let (circuit, info) = kiss2::from_kiss2(
+        ".i 2\n.o 2\n-- * - 10\n-1 a b 00\n-0 a a 00\n-- b a 00\n".as_bytes(),
+        kiss2::KISS2Encoding::Binary,
+    )
+    .unwrap();
+    assert_eq!(3, circuit.input_len());
+    assert_eq!(3, circuit.outputs().len());
+    assert_eq!(vec![Some(false)], info.state_inits);
+    for i in 0..8 {
+        let (s, b) = ((i & 1) != 0, (i & 4) != 0);
+        assert_eq!(
+            vec![!s && b, true, false],
+            circuit.eval([s, (i & 2) != 0, b]),
+            "{}",
+            i
+        );
+    }
+    assert!(matches!(
+        kiss2::from_kiss2(
+            ".i 2\n.o 1\n0 a b 1\n".as_bytes(),
+            kiss2::KISS2Encoding::Binary
+        ),
+        Err(kiss2::KISS2Error::BadInputCube(3))
+    ));
+    assert!(matches!(
+        kiss2::from_kiss2(
+            ".i 2\n.o 1\n00 a b x\n".as_bytes(),
+            kiss2::KISS2Encoding::Binary
+        ),
+        Err(kiss2::KISS2Error::BadOutputCube(3))
+    ));
+    assert!(matches!(
+        kiss2::from_kiss2(".i 2\n00 a b 1\n".as_bytes(), kiss2::KISS2Encoding::Binary),
+        Err(kiss2::KISS2Error::NoHeader(2))
+    ));
+    assert!(matches!(
+        kiss2::from_kiss2(
+            ".i 2\n.o 1\n.r c\n00 a b 1\n".as_bytes(),
+            kiss2::KISS2Encoding::Binary
+        ),
+        Err(kiss2::KISS2Error::UnknownResetState(_))
+    )); // zero-input single-state FSM
+    for encoding in [kiss2::KISS2Encoding::Binary, kiss2::KISS2Encoding::Gray] {
+        assert!(matches!(
+            kiss2::from_kiss2(".i 0\n.o 2\n.s 1\n s0 s0 10\n".as_bytes(), encoding),
+            Err(kiss2::KISS2Error::ConstantOutputsWithoutInputs)
+        ));
+    }
+    let (circuit, info) = kiss2::from_kiss2(
+        ".i 0\n.o 2\n.s 1\n s0 s0 10\n".as_bytes(),
+        kiss2::KISS2Encoding::OneHot,
+    )
+    .unwrap();
+    assert_eq!(1, circuit.input_len());
+    assert_eq!(3, circuit.outputs().len());
+    assert_eq!(vec![Some(true)], info.state_inits);
+    assert_eq!(vec![true, true, false], circuit.eval([true]));
+}
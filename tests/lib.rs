@@ -1,4 +1,5 @@
 use gateconvert::*;
+use gateutil::gatesim::*;
 
 #[test]
 fn test_assign_map_to_string() {
@@ -29,3 +30,30 @@ fn test_assign_map_to_string() {
         ])
     );
 }
+
+#[test]
+fn test_hier_circuit_new() {
+    let sc = Circuit::new(2, [Gate::new_xor(0, 1)], [(2, false)]).unwrap();
+    let top = Circuit::new(
+        3,
+        [Gate::new_and(1, 2)],
+        [(3, false), (0, false), (1, true)],
+    )
+    .unwrap();
+    let hcircuit = HierCircuit::new([("xor2".to_string(), sc.clone())], [0], top.clone()).unwrap();
+    assert_eq!(2, hcircuit.input_len());
+    assert_eq!(1, hcircuit.output_len());
+    assert_eq!(&[0], hcircuit.instances());
+    assert_eq!(&top, hcircuit.circuit());
+    // bad instance index
+    assert!(HierCircuit::new([("xor2".to_string(), sc.clone())], [1], top.clone()).is_none());
+    // too few outputs for instances
+    assert!(HierCircuit::new([("xor2".to_string(), sc.clone())], [0, 0], top.clone()).is_none());
+    // duplicate names
+    assert!(HierCircuit::new(
+        [("xor2".to_string(), sc.clone()), ("xor2".to_string(), sc)],
+        [0],
+        top
+    )
+    .is_none());
+}
@@ -0,0 +1,93 @@
+use gateconvert::lutmap;
+use gateutil::gatesim::*;
+
+fn check_lut_circuit(circuit: &Circuit<usize>, lcircuit: &lutmap::LUTCircuit) {
+    let input_len = circuit.input_len();
+    assert_eq!(input_len, lcircuit.input_len());
+    for v in 0..1usize << input_len {
+        let inputs = (0..input_len)
+            .map(|i| ((v >> i) & 1) != 0)
+            .collect::<Vec<_>>();
+        assert_eq!(circuit.eval(inputs.clone()), lcircuit.eval(inputs), "{}", v);
+    }
+}
+
+#[test]
+fn test_lut_map() {
+    let circuit = Circuit::new(
+        3,
+        [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+        [(4, false), (3, true)],
+    )
+    .unwrap();
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Depth);
+    assert_eq!(
+        vec![
+            lutmap::LUT {
+                inputs: vec![0, 1],
+                table: 0x8
+            },
+            lutmap::LUT {
+                inputs: vec![0, 1, 2],
+                table: 0x78
+            },
+        ],
+        lcircuit.luts()
+    );
+    assert_eq!(&[(4, false), (3, true)], lcircuit.outputs());
+    assert_eq!(2, lcircuit.lut_count());
+    assert_eq!(1, lcircuit.depth());
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Area);
+    assert_eq!(2, lcircuit.lut_count());
+    check_lut_circuit(&circuit, &lcircuit);
+
+    // parity of 6 inputs
+    let circuit = Circuit::new(
+        6,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_xor(6, 2),
+            Gate::new_xor(7, 3),
+            Gate::new_xor(8, 4),
+            Gate::new_xor(9, 5),
+        ],
+        [(10, false)],
+    )
+    .unwrap();
+    let lcircuit = lutmap::lut_map(&circuit, 6, lutmap::LUTMapGoal::Depth);
+    assert_eq!(1, lcircuit.lut_count());
+    assert_eq!(1, lcircuit.depth());
+    assert_eq!(0x6996_9669_9669_6996, lcircuit.luts()[0].table);
+    check_lut_circuit(&circuit, &lcircuit);
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Depth);
+    assert_eq!(2, lcircuit.lut_count());
+    assert_eq!(2, lcircuit.depth());
+    check_lut_circuit(&circuit, &lcircuit);
+
+    // 2-bit multiplier
+    let circuit = Circuit::new(
+        4,
+        [
+            Gate::new_and(0, 2),
+            Gate::new_and(1, 2),
+            Gate::new_and(0, 3),
+            Gate::new_and(1, 3),
+            Gate::new_xor(5, 6),
+            Gate::new_and(5, 6),
+            Gate::new_xor(7, 9),
+            Gate::new_and(7, 9),
+            Gate::new_xor(8, 10),
+        ],
+        [(4, false), (8, false), (10, false), (11, false), (12, true)],
+    )
+    .unwrap();
+    for k in 2..=6 {
+        for goal in [lutmap::LUTMapGoal::Depth, lutmap::LUTMapGoal::Area] {
+            let lcircuit = lutmap::lut_map(&circuit, k, goal);
+            check_lut_circuit(&circuit, &lcircuit);
+        }
+    }
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Depth);
+    assert_eq!(5, lcircuit.lut_count());
+    assert_eq!(1, lcircuit.depth());
+}
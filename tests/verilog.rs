@@ -1,6 +1,12 @@
+use gateconvert::lutmap;
 use gateconvert::verilog;
+use gateconvert::{AssignEntry, HDLPort, HDLPortDir, HDLReset, HDLTestVectors, WriterNames};
 use gateutil::gatesim::*;
 
+use std::io;
+
+mod common;
+
 fn to_verilog_helper(circuit: Circuit<usize>, optimize_negs: bool) -> String {
     let mut out = vec![];
     verilog::to_verilog(circuit, "top", optimize_negs, &mut out).unwrap();
@@ -328,12 +334,14 @@ endmodule
     output o17;
     output o18;
     output o19;
-    assign o4 = (o0 & i1);
-    assign o10 = ~(o2 | o4);
+    assign o4 = (i0 & i1);
+    assign o10 = ~(i2 | o4);
     assign o6 = (i3 & ~o10);
     assign o8 = (i4 ^ o6);
-    assign o3 = ~o0;
-    assign o1 = ~o2;
+    assign o0 = i0;
+    assign o3 = ~i0;
+    assign o2 = i2;
+    assign o1 = ~i2;
     assign o11 = ~o4;
     assign o5 = ~o10;
     assign o9 = ~o6;
@@ -386,3 +394,858 @@ endmodule
         .as_str()
     );
 }
+
+#[test]
+fn test_to_verilog_lut() {
+    let circuit = Circuit::new(
+        3,
+        [Gate::new_and(0, 1), Gate::new_xor(3, 2)],
+        [(4, false), (3, true), (0, false)],
+    )
+    .unwrap();
+    let lcircuit = lutmap::lut_map(&circuit, 4, lutmap::LUTMapGoal::Depth);
+    let mut out = vec![];
+    verilog::to_verilog_lut(&lcircuit, "top", &mut out).unwrap();
+    assert_eq!(
+        r##"module top (
+    i0,
+    i1,
+    i2,
+    o0,
+    o1,
+    o2);
+    input i0;
+    input i1;
+    input i2;
+    output o0;
+    output o1;
+    output o2;
+    wire i3;
+    LUT2 #(.INIT(4'h8)) lut0 (.O(i3), .I0(i0), .I1(i1));
+    LUT3 #(.INIT(8'h78)) lut1 (.O(o0), .I0(i0), .I1(i1), .I2(i2));
+    assign o2 = i0;
+    assign o1 = ~i3;
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let mut out = vec![];
+    verilog::to_verilog_lut_with_options(
+        &lcircuit,
+        "top",
+        &verilog::VerilogOptions {
+            names: WriterNames {
+                inputs: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                outputs: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+                wire_prefix: "w".to_string(),
+            },
+            ..verilog::VerilogOptions::default()
+        },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"module top (
+    a,
+    b,
+    c,
+    x,
+    y,
+    z);
+    input a;
+    input b;
+    input c;
+    output x;
+    output y;
+    output z;
+    wire w3;
+    LUT2 #(.INIT(4'h8)) lut0 (.O(w3), .I0(a), .I1(b));
+    LUT3 #(.INIT(8'h78)) lut1 (.O(x), .I0(a), .I1(b), .I2(c));
+    assign z = a;
+    assign y = ~w3;
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+}
+
+type VerilogResult = (Circuit<usize>, Vec<(String, AssignEntry)>);
+
+fn from_verilog_helper(text: &str) -> Result<VerilogResult, String> {
+    verilog::from_verilog(text.as_bytes()).map_err(|x| x.to_string())
+}
+
+fn strs_to_assign_map<'a>(
+    iter: impl IntoIterator<Item = (&'a str, AssignEntry)>,
+) -> Vec<(String, AssignEntry)> {
+    iter.into_iter().map(|(s, a)| (s.to_string(), a)).collect()
+}
+
+#[test]
+fn test_from_verilog() {
+    // round trip
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 2),
+            Gate::new_nor(1, 2),
+            Gate::new_xor(3, 4),
+            Gate::new_nimpl(5, 0),
+        ],
+        [(5, false), (3, true), (6, false), (6, false), (1, true)],
+    )
+    .unwrap();
+    for optimize_negs in [false, true] {
+        assert_eq!(
+            Ok((
+                circuit.clone(),
+                strs_to_assign_map([
+                    ("i0", AssignEntry::Var(0, false)),
+                    ("i1", AssignEntry::Var(1, false)),
+                    ("i2", AssignEntry::Var(2, false)),
+                    ("o0", AssignEntry::Var(5, false)),
+                    ("o1", AssignEntry::Var(3, true)),
+                    ("o2", AssignEntry::Var(6, false)),
+                    ("o3", AssignEntry::Var(6, false)),
+                    ("o4", AssignEntry::Var(1, true)),
+                ])
+            )),
+            from_verilog_helper(&to_verilog_helper(circuit.clone(), optimize_negs))
+        );
+    }
+    // primitives, vectors, constants
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 1),
+                    Gate::new_and(3, 2),
+                    Gate::new_nimpl(0, 4),
+                    Gate::new_nimpl(0, 2),
+                    Gate::new_nimpl(0, 6),
+                ],
+                [(5, true), (7, true)]
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("a", AssignEntry::Var(0, false)),
+                ("b[0]", AssignEntry::Var(1, false)),
+                ("b[1]", AssignEntry::Var(2, false)),
+                ("y", AssignEntry::Var(5, true)),
+                ("z", AssignEntry::Var(7, true)),
+                ("c", AssignEntry::Value(false)),
+            ])
+        )),
+        from_verilog_helper(
+            r##"module t(input a, input [1:0] b, output y, output z, output c);
+    /* comment */
+    wire w; // comment
+    nand g1(w, a, b[0], b[1]), g2(y, w, a);
+    assign z = a ? ~b[1] : 1'b1,
+        c = 1'b0;
+endmodule
+"##
+        )
+    );
+    assert_eq!(
+        Ok((
+            Circuit::new(2, [Gate::new_xor(0, 1)], [(2, true), (0, true)]).unwrap(),
+            strs_to_assign_map([
+                ("\\a", AssignEntry::Var(0, false)),
+                ("b", AssignEntry::Var(1, false)),
+                ("unused", AssignEntry::NoMap),
+                ("x", AssignEntry::Var(2, true)),
+                ("y", AssignEntry::Var(0, true)),
+            ])
+        )),
+        from_verilog_helper(
+            r##"module t(\\a , b, unused, x, y);
+    input \\a , b, unused;
+    output x, y;
+    xnor (x, \\a , b);
+    not (y, \\a );
+endmodule
+"##
+        )
+    );
+    // errors
+    assert_eq!(
+        Err("Cycle in module caused by y".to_string()),
+        from_verilog_helper("module t(a,y); input a; output y; assign y = y & a; endmodule")
+    );
+    assert_eq!(
+        Err("Net y is undriven".to_string()),
+        from_verilog_helper("module t(a,y); input a; output y; assign w = a; endmodule")
+    );
+    assert_eq!(
+        Err("1: Net y already driven".to_string()),
+        from_verilog_helper(
+            "module t(a,y); input a; output y; assign y = a; buf (y, a); endmodule"
+        )
+    );
+    assert_eq!(
+        Err("1: Vector b without bit select".to_string()),
+        from_verilog_helper("module t(b,y); input [1:0] b; output y; assign y = b; endmodule")
+    );
+    assert_eq!(
+        Err("2: Unsupported sub".to_string()),
+        from_verilog_helper("module t(a,y); input a; output y;\nsub u0(a, y); endmodule")
+    );
+}
+
+#[test]
+fn test_to_verilog_hier() {
+    let mut out = vec![];
+    verilog::to_verilog_hier(&common::adder_hier_circuit(), "top", false, &mut out).unwrap();
+    assert_eq!(
+        r##"module fa (
+    i0,
+    i1,
+    i2,
+    o0,
+    o1);
+    input i0;
+    input i1;
+    input i2;
+    output o0;
+    output o1;
+    wire i3;
+    wire i5;
+    wire i6;
+    wire i7;
+    assign i3 = (i0 ^ i1);
+    assign o0 = (i3 ^ i2);
+    assign i5 = (i0 & i1);
+    assign i6 = (i3 & i2);
+    assign i7 = ~(i5 | i6);
+    assign o1 = ~i7;
+endmodule
+module top (
+    i0,
+    i1,
+    i2,
+    i3,
+    i4,
+    o0,
+    o1,
+    o2);
+    input i0;
+    input i1;
+    input i2;
+    input i3;
+    input i4;
+    output o0;
+    output o1;
+    output o2;
+    wire i5;
+    wire i6;
+    wire i7;
+    wire i8;
+    assign o0 = i5;
+    assign o1 = i7;
+    assign o2 = i8;
+    fa inst0 (.i0(i0), .i1(i1), .i2(i4), .o0(i5), .o1(i6));
+    fa inst1 (.i0(i2), .i1(i3), .i2(i6), .o0(i7), .o1(i8));
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // sub-circuit with name of top module
+    let err = verilog::to_verilog_hier(&common::adder_hier_circuit(), "fa", false, &mut vec![])
+        .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!("Name collision: fa", err.to_string());
+}
+
+fn to_verilog_with_options_helper(
+    circuit: Circuit<usize>,
+    options: &verilog::VerilogOptions,
+) -> String {
+    let mut out = vec![];
+    verilog::to_verilog_with_options(circuit, "top", false, options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_to_verilog_with_options() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 2),
+            Gate::new_and(0, 2),
+            Gate::new_xor(1, 4),
+            Gate::new_and(1, 4),
+        ],
+        [(3, false), (5, false), (6, false)],
+    )
+    .unwrap();
+    assert_eq!(
+        r##"module top (
+    clk,
+    i2,
+    o2);
+    input clk;
+    input i2;
+    output o2;
+    reg i0;
+    reg i1;
+    wire o0;
+    wire o1;
+    wire i4;
+    assign o0 = (i0 ^ i2);
+    assign i4 = (i0 & i2);
+    assign o1 = (i1 ^ i4);
+    assign o2 = (i1 & i4);
+    always @(posedge clk) begin
+        i0 <= o0;
+        i1 <= o1;
+    end
+endmodule
+"##,
+        to_verilog_with_options_helper(
+            circuit.clone(),
+            &verilog::VerilogOptions {
+                state_len: 2,
+                ..verilog::VerilogOptions::default()
+            }
+        )
+    );
+    assert_eq!(
+        r##"module top (
+    clock,
+    reset,
+    i2,
+    o2);
+    input clock;
+    input reset;
+    input i2;
+    output o2;
+    reg i0;
+    reg i1;
+    wire o0;
+    wire o1;
+    wire i4;
+    assign o0 = (i0 ^ i2);
+    assign i4 = (i0 & i2);
+    assign o1 = (i1 ^ i4);
+    assign o2 = (i1 & i4);
+    always @(posedge clock) begin
+        if (reset) begin
+            i0 <= 1'b1;
+            i1 <= 1'b0;
+        end else begin
+            i0 <= o0;
+            i1 <= o1;
+        end
+    end
+endmodule
+"##,
+        to_verilog_with_options_helper(
+            circuit.clone(),
+            &verilog::VerilogOptions {
+                state_len: 2,
+                clock: "clock".to_string(),
+                reset: Some(HDLReset {
+                    name: "reset".to_string(),
+                    asynchronous: false,
+                    values: vec![true],
+                }),
+                names: WriterNames::default(),
+                ports: vec![],
+            }
+        )
+    );
+    assert_eq!(
+        r##"module top (
+    clk,
+    rst,
+    i2,
+    o2);
+    input clk;
+    input rst;
+    input i2;
+    output o2;
+    reg i0;
+    reg i1;
+    wire o0;
+    wire o1;
+    wire i4;
+    assign o0 = (i0 ^ i2);
+    assign i4 = (i0 & i2);
+    assign o1 = (i1 ^ i4);
+    assign o2 = (i1 & i4);
+    always @(posedge clk or posedge rst) begin
+        if (rst) begin
+            i0 <= 1'b0;
+            i1 <= 1'b1;
+        end else begin
+            i0 <= o0;
+            i1 <= o1;
+        end
+    end
+endmodule
+"##,
+        to_verilog_with_options_helper(
+            circuit.clone(),
+            &verilog::VerilogOptions {
+                state_len: 2,
+                clock: "clk".to_string(),
+                reset: Some(HDLReset {
+                    name: "rst".to_string(),
+                    asynchronous: true,
+                    values: vec![false, true],
+                }),
+                names: WriterNames::default(),
+                ports: vec![],
+            }
+        )
+    );
+}
+
+#[test]
+fn test_to_verilog_with_names() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_and(0, 1),
+            Gate::new_xor(3, 2),
+            Gate::new_and(3, 2),
+            Gate::new_nor(4, 6),
+        ],
+        [(5, false), (7, true), (2, false)],
+    )
+    .unwrap();
+    let names = WriterNames {
+        inputs: vec!["a".to_string(), "wire".to_string(), "c[0]".to_string()],
+        outputs: vec!["sum".to_string(), "carry".to_string()],
+        wire_prefix: "t".to_string(),
+    };
+    assert_eq!(
+        r##"module top (
+    a,
+    \wire ,
+    \c[0] ,
+    sum,
+    carry,
+    o2);
+    input a;
+    input \wire ;
+    input \c[0] ;
+    output sum;
+    output carry;
+    output o2;
+    wire t3;
+    wire t4;
+    wire t6;
+    wire t7;
+    assign t3 = (a ^ \wire );
+    assign t4 = (a & \wire );
+    assign sum = (t3 ^ \c[0] );
+    assign t6 = (t3 & \c[0] );
+    assign t7 = ~(t4 | t6);
+    assign o2 = \c[0] ;
+    assign carry = ~t7;
+endmodule
+"##,
+        to_verilog_with_options_helper(
+            circuit.clone(),
+            &verilog::VerilogOptions {
+                names: names.clone(),
+                ..verilog::VerilogOptions::default()
+            }
+        )
+    );
+    let write_error = |options: verilog::VerilogOptions| {
+        let mut out = vec![];
+        let err =
+            verilog::to_verilog_with_options(circuit.clone(), "top", false, &options, &mut out)
+                .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        err.to_string()
+    };
+    assert_eq!(
+        "Name collision: a",
+        write_error(verilog::VerilogOptions {
+            names: WriterNames {
+                outputs: vec!["sum".to_string(), "a".to_string()],
+                ..names.clone()
+            },
+            ..verilog::VerilogOptions::default()
+        })
+    );
+    assert_eq!(
+        "Name collision: t4",
+        write_error(verilog::VerilogOptions {
+            names: WriterNames {
+                inputs: vec!["a".to_string(), "t4".to_string()],
+                ..names.clone()
+            },
+            ..verilog::VerilogOptions::default()
+        })
+    );
+    assert_eq!(
+        "Name collision: a",
+        write_error(verilog::VerilogOptions {
+            state_len: 1,
+            clock: "a".to_string(),
+            names: names.clone(),
+            ..verilog::VerilogOptions::default()
+        })
+    );
+    assert_eq!(
+        "Invalid name a b",
+        write_error(verilog::VerilogOptions {
+            names: WriterNames {
+                inputs: vec!["a b".to_string()],
+                ..names.clone()
+            },
+            ..verilog::VerilogOptions::default()
+        })
+    );
+}
+
+#[test]
+fn test_to_verilog_with_ports() {
+    let circuit = Circuit::new(
+        4,
+        [
+            Gate::new_xor(0, 2),
+            Gate::new_and(0, 2),
+            Gate::new_xor(1, 3),
+            Gate::new_xor(6, 5),
+            Gate::new_and(1, 3),
+            Gate::new_and(6, 5),
+            Gate::new_nor(8, 9),
+        ],
+        [(4, false), (7, false), (10, true)],
+    )
+    .unwrap();
+    let ports = vec![
+        HDLPort::new("a", 2, HDLPortDir::Input),
+        HDLPort::new("s", 2, HDLPortDir::Output),
+        HDLPort::new("b", 2, HDLPortDir::Input),
+        HDLPort::new("c", 1, HDLPortDir::Output),
+    ];
+    assert_eq!(
+        r##"module top (
+    a,
+    s,
+    b,
+    c);
+    input [1:0] a;
+    output [1:0] s;
+    input [1:0] b;
+    output c;
+    wire i0;
+    wire i1;
+    wire i2;
+    wire i3;
+    wire o0;
+    wire o1;
+    wire o2;
+    wire i5;
+    wire i6;
+    wire i8;
+    wire i9;
+    wire i10;
+    assign i0 = a[0];
+    assign i1 = a[1];
+    assign i2 = b[0];
+    assign i3 = b[1];
+    assign o0 = (i0 ^ i2);
+    assign i5 = (i0 & i2);
+    assign i6 = (i1 ^ i3);
+    assign o1 = (i6 ^ i5);
+    assign i8 = (i1 & i3);
+    assign i9 = (i6 & i5);
+    assign i10 = ~(i8 | i9);
+    assign o2 = ~i10;
+    assign s[0] = o0;
+    assign s[1] = o1;
+    assign c = o2;
+endmodule
+"##,
+        to_verilog_with_options_helper(
+            circuit.clone(),
+            &verilog::VerilogOptions {
+                ports: ports.clone(),
+                ..verilog::VerilogOptions::default()
+            }
+        )
+    );
+    let mut out = vec![];
+    let options = verilog::VerilogOptions {
+        ports: vec![
+            HDLPort::new("i1", 4, HDLPortDir::Input),
+            ports[1].clone(),
+            ports[3].clone(),
+        ],
+        ..verilog::VerilogOptions::default()
+    };
+    assert_eq!(
+        "Name collision: i1",
+        verilog::to_verilog_with_options(circuit.clone(), "top", false, &options, &mut out)
+            .unwrap_err()
+            .to_string()
+    );
+    // bad layouts
+    for (ports, message) in [
+        (
+            vec![ports[0].clone(), ports[1].clone(), ports[3].clone()],
+            "Input ports have 2 bits instead of 4",
+        ),
+        (
+            vec![ports[0].clone(), ports[1].clone(), ports[2].clone()],
+            "Output ports have 2 bits instead of 3",
+        ),
+        (
+            vec![HDLPort::new("z", 0, HDLPortDir::Input)]
+                .into_iter()
+                .chain(ports.clone())
+                .collect(),
+            "Port z has zero width",
+        ),
+    ] {
+        let options = verilog::VerilogOptions {
+            ports,
+            ..verilog::VerilogOptions::default()
+        };
+        let err =
+            verilog::to_verilog_with_options(circuit.clone(), "top", false, &options, &mut out)
+                .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(message, err.to_string());
+    }
+}
+
+#[test]
+fn test_to_verilog_testbench() {
+    let circuit = Circuit::new(
+        2,
+        [Gate::new_xor(0, 1), Gate::new_and(0, 1)],
+        [(2, false), (3, true)],
+    )
+    .unwrap();
+    let mut out = vec![];
+    verilog::to_verilog_testbench(
+        &circuit,
+        "top",
+        &verilog::VerilogOptions::default(),
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"module top_tb;
+    reg [1:0] inputs;
+    wire [1:0] outputs;
+    integer errors;
+    top dut (.i0(inputs[0]), .i1(inputs[1]), .o0(outputs[0]), .o1(outputs[1]));
+    initial begin
+        errors = 0;
+        inputs = 2'b00;
+        #1;
+        if (outputs !== 2'b10) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 2'b10);
+            errors = errors + 1;
+        end
+        inputs = 2'b01;
+        #1;
+        if (outputs !== 2'b11) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 2'b11);
+            errors = errors + 1;
+        end
+        inputs = 2'b10;
+        #1;
+        if (outputs !== 2'b11) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 2'b11);
+            errors = errors + 1;
+        end
+        inputs = 2'b11;
+        #1;
+        if (outputs !== 2'b00) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 2'b00);
+            errors = errors + 1;
+        end
+        if (errors == 0)
+            $display("PASSED");
+        else
+            $display("FAILED: %0d errors", errors);
+        $finish;
+    end
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let circuit =
+        Circuit::new(3, [Gate::new_and(0, 1), Gate::new_nor(3, 2)], [(4, false)]).unwrap();
+    let mut out = vec![];
+    verilog::to_verilog_testbench(
+        &circuit,
+        "top",
+        &verilog::VerilogOptions::default(),
+        HDLTestVectors::Random { count: 4, seed: 1 },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"module top_tb;
+    reg [2:0] inputs;
+    wire [0:0] outputs;
+    integer errors;
+    top dut (.i0(inputs[0]), .i1(inputs[1]), .i2(inputs[2]), .o0(outputs[0]));
+    initial begin
+        errors = 0;
+        inputs = 3'b001;
+        #1;
+        if (outputs !== 1'b1) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b1);
+            errors = errors + 1;
+        end
+        inputs = 3'b111;
+        #1;
+        if (outputs !== 1'b0) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b0);
+            errors = errors + 1;
+        end
+        inputs = 3'b110;
+        #1;
+        if (outputs !== 1'b0) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b0);
+            errors = errors + 1;
+        end
+        inputs = 3'b011;
+        #1;
+        if (outputs !== 1'b0) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b0);
+            errors = errors + 1;
+        end
+        if (errors == 0)
+            $display("PASSED");
+        else
+            $display("FAILED: %0d errors", errors);
+        $finish;
+    end
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // sequential circuit with layout of ports
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(1, 2),
+            Gate::new_xor(0, 3),
+            Gate::new_and(0, 3),
+        ],
+        [(4, false), (5, false)],
+    )
+    .unwrap();
+    let options = verilog::VerilogOptions {
+        state_len: 1,
+        reset: Some(HDLReset {
+            name: "rst".to_string(),
+            asynchronous: false,
+            values: vec![true],
+        }),
+        ports: vec![
+            HDLPort::new("ab", 2, HDLPortDir::Input),
+            HDLPort::new("y", 1, HDLPortDir::Output),
+        ],
+        ..verilog::VerilogOptions::default()
+    };
+    let mut out = vec![];
+    verilog::to_verilog_testbench(
+        &circuit,
+        "top",
+        &options,
+        HDLTestVectors::Random { count: 3, seed: 1 },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"module top_tb;
+    reg clock;
+    reg reset;
+    reg [1:0] inputs;
+    wire [0:0] outputs;
+    integer errors;
+    top dut (.clk(clock), .rst(reset), .ab(inputs[1:0]), .y(outputs[0]));
+    initial begin
+        errors = 0;
+        clock = 0;
+        reset = 1;
+        #1;
+        clock = 1;
+        #1;
+        clock = 0;
+        reset = 0;
+        inputs = 2'b01;
+        #1;
+        if (outputs !== 1'b1) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b1);
+            errors = errors + 1;
+        end
+        clock = 1;
+        #1;
+        clock = 0;
+        inputs = 2'b11;
+        #1;
+        if (outputs !== 1'b0) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b0);
+            errors = errors + 1;
+        end
+        clock = 1;
+        #1;
+        clock = 0;
+        inputs = 2'b10;
+        #1;
+        if (outputs !== 1'b0) begin
+            $display("Error: inputs %b: outputs %b, expected %b", inputs, outputs, 1'b0);
+            errors = errors + 1;
+        end
+        clock = 1;
+        #1;
+        clock = 0;
+        if (errors == 0)
+            $display("PASSED");
+        else
+            $display("FAILED: %0d errors", errors);
+        $finish;
+    end
+endmodule
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // errors
+    let mut out = vec![];
+    let err = verilog::to_verilog_testbench(
+        &circuit,
+        "top",
+        &verilog::VerilogOptions {
+            reset: None,
+            ..options.clone()
+        },
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!("Sequential circuit without reset", err.to_string());
+    let circuit = Circuit::new(21, [], (0..21).map(|i| (i, false))).unwrap();
+    let err = verilog::to_verilog_testbench(
+        &circuit,
+        "top",
+        &verilog::VerilogOptions::default(),
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!(
+        "Too many inputs for exhaustive test vectors: 21",
+        err.to_string()
+    );
+}
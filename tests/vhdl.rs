@@ -1,6 +1,11 @@
 use gateconvert::vhdl;
+use gateconvert::{AssignEntry, HDLPort, HDLPortDir, HDLReset, HDLTestVectors, WriterNames};
 use gateutil::gatesim::*;
 
+use std::io;
+
+mod common;
+
 fn to_vhdl_helper(circuit: Circuit<usize>, optimize_negs: bool) -> String {
     let mut out = vec![];
     vhdl::to_vhdl(circuit, "top", "behavior", optimize_negs, &mut out).unwrap();
@@ -300,12 +305,14 @@ entity top is
 end top;
 architecture behavior of top is
 begin
-    o4 <= o0 and i1;
-    o10 <= o2 nor o4;
+    o4 <= i0 and i1;
+    o10 <= i2 nor o4;
     o6 <= i3 and not o10;
     o8 <= i4 xor o6;
-    o3 <= not o0;
-    o1 <= not o2;
+    o0 <= i0;
+    o3 <= not i0;
+    o2 <= i2;
+    o1 <= not i2;
     o11 <= not o4;
     o5 <= not o10;
     o9 <= not o6;
@@ -358,3 +365,910 @@ end behavior;
         .as_str()
     );
 }
+
+fn to_vhdl_with_options_helper(circuit: Circuit<usize>, options: &vhdl::VHDLOptions) -> String {
+    let mut out = vec![];
+    vhdl::to_vhdl_with_options(circuit, "top", "behavior", false, options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_to_vhdl_with_options() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 2),
+            Gate::new_and(0, 2),
+            Gate::new_xor(1, 4),
+            Gate::new_and(1, 4),
+        ],
+        [(3, false), (5, false), (6, false)],
+    )
+    .unwrap();
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        clk : in std_logic;
+        i2 : in std_logic;
+        o2 : out std_logic
+    );
+end top;
+architecture behavior of top is
+    signal i0 : std_logic;
+    signal i1 : std_logic;
+    signal o0 : std_logic;
+    signal o1 : std_logic;
+    signal i4 : std_logic;
+begin
+    o0 <= i0 xor i2;
+    i4 <= i0 and i2;
+    o1 <= i1 xor i4;
+    o2 <= i1 and i4;
+    process(clk)
+    begin
+        if rising_edge(clk) then
+            i0 <= o0;
+            i1 <= o1;
+        end if;
+    end process;
+end behavior;
+"##,
+        to_vhdl_with_options_helper(
+            circuit.clone(),
+            &vhdl::VHDLOptions {
+                state_len: 2,
+                ..vhdl::VHDLOptions::default()
+            }
+        )
+    );
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        clock : in std_logic;
+        reset : in std_logic;
+        i2 : in std_logic;
+        o2 : out std_logic
+    );
+end top;
+architecture behavior of top is
+    signal i0 : std_logic;
+    signal i1 : std_logic;
+    signal o0 : std_logic;
+    signal o1 : std_logic;
+    signal i4 : std_logic;
+begin
+    o0 <= i0 xor i2;
+    i4 <= i0 and i2;
+    o1 <= i1 xor i4;
+    o2 <= i1 and i4;
+    process(clock)
+    begin
+        if rising_edge(clock) then
+            if reset = '1' then
+                i0 <= '1';
+                i1 <= '0';
+            else
+                i0 <= o0;
+                i1 <= o1;
+            end if;
+        end if;
+    end process;
+end behavior;
+"##,
+        to_vhdl_with_options_helper(
+            circuit.clone(),
+            &vhdl::VHDLOptions {
+                state_len: 2,
+                clock: "clock".to_string(),
+                reset: Some(HDLReset {
+                    name: "reset".to_string(),
+                    asynchronous: false,
+                    values: vec![true],
+                }),
+                names: WriterNames::default(),
+                ports: vec![],
+            }
+        )
+    );
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        clk : in std_logic;
+        rst : in std_logic;
+        i2 : in std_logic;
+        o2 : out std_logic
+    );
+end top;
+architecture behavior of top is
+    signal i0 : std_logic;
+    signal i1 : std_logic;
+    signal o0 : std_logic;
+    signal o1 : std_logic;
+    signal i4 : std_logic;
+begin
+    o0 <= i0 xor i2;
+    i4 <= i0 and i2;
+    o1 <= i1 xor i4;
+    o2 <= i1 and i4;
+    process(clk, rst)
+    begin
+        if rst = '1' then
+            i0 <= '0';
+            i1 <= '1';
+        elsif rising_edge(clk) then
+            i0 <= o0;
+            i1 <= o1;
+        end if;
+    end process;
+end behavior;
+"##,
+        to_vhdl_with_options_helper(
+            circuit,
+            &vhdl::VHDLOptions {
+                state_len: 2,
+                clock: "clk".to_string(),
+                reset: Some(HDLReset {
+                    name: "rst".to_string(),
+                    asynchronous: true,
+                    values: vec![false, true],
+                }),
+                names: WriterNames::default(),
+                ports: vec![],
+            }
+        )
+    );
+}
+
+type VHDLResult = (Circuit<usize>, Vec<(String, AssignEntry)>);
+
+fn from_vhdl_helper(text: &str) -> Result<VHDLResult, String> {
+    vhdl::from_vhdl(text.as_bytes()).map_err(|x| x.to_string())
+}
+
+fn strs_to_assign_map<'a>(
+    iter: impl IntoIterator<Item = (&'a str, AssignEntry)>,
+) -> Vec<(String, AssignEntry)> {
+    iter.into_iter().map(|(s, a)| (s.to_string(), a)).collect()
+}
+
+#[test]
+fn test_from_vhdl() {
+    // round trip
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 2),
+            Gate::new_nor(1, 2),
+            Gate::new_xor(3, 4),
+            Gate::new_nimpl(5, 0),
+        ],
+        [(5, false), (3, true), (6, false), (6, false), (1, true)],
+    )
+    .unwrap();
+    for optimize_negs in [false, true] {
+        assert_eq!(
+            Ok((
+                circuit.clone(),
+                strs_to_assign_map([
+                    ("i0", AssignEntry::Var(0, false)),
+                    ("i1", AssignEntry::Var(1, false)),
+                    ("i2", AssignEntry::Var(2, false)),
+                    ("o0", AssignEntry::Var(5, false)),
+                    ("o1", AssignEntry::Var(3, true)),
+                    ("o2", AssignEntry::Var(6, false)),
+                    ("o3", AssignEntry::Var(6, false)),
+                    ("o4", AssignEntry::Var(1, true)),
+                ])
+            )),
+            from_vhdl_helper(&to_vhdl_helper(circuit.clone(), optimize_negs))
+        );
+    }
+    // vectors, conditional assignments, constants
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                3,
+                [
+                    Gate::new_and(0, 1),
+                    Gate::new_and(3, 2),
+                    Gate::new_nimpl(0, 4),
+                    Gate::new_nimpl(0, 2),
+                    Gate::new_nimpl(0, 6),
+                ],
+                [(5, true), (7, true)]
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("a", AssignEntry::Var(0, false)),
+                ("b(0)", AssignEntry::Var(1, false)),
+                ("b(1)", AssignEntry::Var(2, false)),
+                ("y", AssignEntry::Var(5, true)),
+                ("z", AssignEntry::Var(7, true)),
+                ("c", AssignEntry::Value(false)),
+            ])
+        )),
+        from_vhdl_helper(
+            r##"LIBRARY ieee;
+USE ieee.std_logic_1164.ALL;
+ENTITY T IS
+    PORT(
+        a : IN std_logic;
+        b : IN std_logic_vector(1 DOWNTO 0);
+        y, z : OUT std_logic;
+        c : OUT bit
+    );
+END ENTITY t;
+ARCHITECTURE rtl OF t IS
+    SIGNAL w : std_logic; -- comment
+BEGIN
+    w <= not (A and b(0) and b(1));
+    g2: y <= w nand a;
+    z <= not b(1) when a = '1' else '1';
+    c <= '0';
+END ARCHITECTURE;
+"##
+        )
+    );
+    assert_eq!(
+        Ok((
+            Circuit::new(2, [Gate::new_xor(0, 1)], [(2, true), (0, true)]).unwrap(),
+            strs_to_assign_map([
+                ("\\A\\", AssignEntry::Var(0, false)),
+                ("b", AssignEntry::Var(1, false)),
+                ("unused", AssignEntry::NoMap),
+                ("x", AssignEntry::Var(2, true)),
+                ("y", AssignEntry::Var(0, true)),
+            ])
+        )),
+        from_vhdl_helper(
+            r##"entity t is
+    port(\A\, b, unused : in std_logic; x, y : out std_logic);
+end;
+architecture a of t is
+begin
+    x <= \A\ xnor b;
+    y <= '0' when \A\ /= '0' else '1';
+end;
+"##
+        )
+    );
+    // errors
+    assert_eq!(
+        Err("Cycle in architecture caused by y".to_string()),
+        from_vhdl_helper(
+            "entity t is port(a : in bit; y : out bit); end; architecture r of t is begin\n\
+            y <= y and a; end;"
+        )
+    );
+    assert_eq!(
+        Err("Signal y is undriven".to_string()),
+        from_vhdl_helper(
+            "entity t is port(a : in bit; y : out bit); end; architecture r of t is\n\
+            signal w : bit; begin w <= a; end;"
+        )
+    );
+    assert_eq!(
+        Err("2: Signal y already driven".to_string()),
+        from_vhdl_helper(
+            "entity t is port(a : in bit; y : out bit); end; architecture r of t is begin\n\
+            y <= a; y <= not a; end;"
+        )
+    );
+    assert_eq!(
+        Err("2: Vector b without index".to_string()),
+        from_vhdl_helper(
+            "entity t is port(b : in bit_vector(0 to 1); y : out bit); end;\n\
+            architecture r of t is begin y <= b; end;"
+        )
+    );
+    assert_eq!(
+        Err("2: Index b(2) out of range".to_string()),
+        from_vhdl_helper(
+            "entity t is port(b : in bit_vector(0 to 1); y : out bit); end;\n\
+            architecture r of t is begin y <= b(2); end;"
+        )
+    );
+    assert_eq!(
+        Err("2: Unsupported process".to_string()),
+        from_vhdl_helper(
+            "entity t is port(a : in bit; y : out bit); end; architecture r of t is begin\n\
+            p: process(a) begin y <= a; end process; end;"
+        )
+    );
+    assert_eq!(
+        Err("1: Syntax error: or after and without parentheses".to_string()),
+        from_vhdl_helper(
+            "entity t is port(a, b : in bit; y : out bit); end; architecture r of t is begin \
+            y <= a and b or a; end;"
+        )
+    );
+}
+
+#[test]
+fn test_to_vhdl_hier() {
+    let mut out = vec![];
+    vhdl::to_vhdl_hier(
+        &common::adder_hier_circuit(),
+        "top",
+        "behavior",
+        false,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity fa is
+    port(
+        i0 : in std_logic;
+        i1 : in std_logic;
+        i2 : in std_logic;
+        o0 : out std_logic;
+        o1 : out std_logic
+    );
+end fa;
+architecture behavior of fa is
+    signal i3 : std_logic;
+    signal i5 : std_logic;
+    signal i6 : std_logic;
+    signal i7 : std_logic;
+begin
+    i3 <= i0 xor i1;
+    o0 <= i3 xor i2;
+    i5 <= i0 and i1;
+    i6 <= i3 and i2;
+    i7 <= i5 nor i6;
+    o1 <= not i7;
+end behavior;
+library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        i0 : in std_logic;
+        i1 : in std_logic;
+        i2 : in std_logic;
+        i3 : in std_logic;
+        i4 : in std_logic;
+        o0 : out std_logic;
+        o1 : out std_logic;
+        o2 : out std_logic
+    );
+end top;
+architecture behavior of top is
+    component fa
+    port(
+        i0 : in std_logic;
+        i1 : in std_logic;
+        i2 : in std_logic;
+        o0 : out std_logic;
+        o1 : out std_logic
+    );
+    end component;
+    signal i5 : std_logic;
+    signal i6 : std_logic;
+    signal i7 : std_logic;
+    signal i8 : std_logic;
+begin
+    o0 <= i5;
+    o1 <= i7;
+    o2 <= i8;
+    inst0: fa port map (i0 => i0, i1 => i1, i2 => i4, o0 => i5, o1 => i6);
+    inst1: fa port map (i0 => i2, i1 => i3, i2 => i6, o0 => i7, o1 => i8);
+end behavior;
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // sub-circuit with name of top entity (VHDL names are case insensitive)
+    let err = vhdl::to_vhdl_hier(
+        &common::adder_hier_circuit(),
+        "FA",
+        "behavior",
+        false,
+        &mut vec![],
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!("Name collision: FA", err.to_string());
+}
+
+#[test]
+fn test_to_vhdl_with_names() {
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(0, 1),
+            Gate::new_and(0, 1),
+            Gate::new_xor(3, 2),
+            Gate::new_and(3, 2),
+            Gate::new_nor(4, 6),
+        ],
+        [(5, false), (7, true), (2, false)],
+    )
+    .unwrap();
+    let names = WriterNames {
+        inputs: vec!["a".to_string(), "Signal".to_string(), "c\\0".to_string()],
+        outputs: vec!["sum".to_string(), "carry".to_string()],
+        wire_prefix: "t".to_string(),
+    };
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        a : in std_logic;
+        \Signal\ : in std_logic;
+        \c\\0\ : in std_logic;
+        sum : out std_logic;
+        carry : out std_logic;
+        o2 : out std_logic
+    );
+end top;
+architecture behavior of top is
+    signal t3 : std_logic;
+    signal t4 : std_logic;
+    signal t6 : std_logic;
+    signal t7 : std_logic;
+begin
+    t3 <= a xor \Signal\;
+    t4 <= a and \Signal\;
+    sum <= t3 xor \c\\0\;
+    t6 <= t3 and \c\\0\;
+    t7 <= t4 nor t6;
+    o2 <= \c\\0\;
+    carry <= not t7;
+end behavior;
+"##,
+        to_vhdl_with_options_helper(
+            circuit.clone(),
+            &vhdl::VHDLOptions {
+                names: names.clone(),
+                ..vhdl::VHDLOptions::default()
+            }
+        )
+    );
+    let write_error = |options: vhdl::VHDLOptions| {
+        let mut out = vec![];
+        let err = vhdl::to_vhdl_with_options(
+            circuit.clone(),
+            "top",
+            "behavior",
+            false,
+            &options,
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        err.to_string()
+    };
+    assert_eq!(
+        "Name collision: SUM",
+        write_error(vhdl::VHDLOptions {
+            names: WriterNames {
+                outputs: vec!["sum".to_string(), "SUM".to_string()],
+                ..names.clone()
+            },
+            ..vhdl::VHDLOptions::default()
+        })
+    );
+    assert_eq!(
+        "Name collision: t4",
+        write_error(vhdl::VHDLOptions {
+            names: WriterNames {
+                inputs: vec!["a".to_string(), "T4".to_string()],
+                ..names.clone()
+            },
+            ..vhdl::VHDLOptions::default()
+        })
+    );
+    assert_eq!(
+        "Name collision: \\signal\\",
+        write_error(vhdl::VHDLOptions {
+            names: WriterNames {
+                outputs: vec!["sum".to_string(), "signal".to_string()],
+                ..names.clone()
+            },
+            state_len: 1,
+            clock: "signal".to_string(),
+            ..vhdl::VHDLOptions::default()
+        })
+    );
+    assert_eq!(
+        "Invalid name a\tb",
+        write_error(vhdl::VHDLOptions {
+            names: WriterNames {
+                inputs: vec!["a\tb".to_string()],
+                ..names.clone()
+            },
+            ..vhdl::VHDLOptions::default()
+        })
+    );
+}
+
+#[test]
+fn test_to_vhdl_with_ports() {
+    let circuit = Circuit::new(
+        4,
+        [
+            Gate::new_xor(0, 2),
+            Gate::new_and(0, 2),
+            Gate::new_xor(1, 3),
+            Gate::new_xor(6, 5),
+            Gate::new_and(1, 3),
+            Gate::new_and(6, 5),
+            Gate::new_nor(8, 9),
+        ],
+        [(4, false), (7, false), (10, true)],
+    )
+    .unwrap();
+    let ports = vec![
+        HDLPort::new("a", 2, HDLPortDir::Input),
+        HDLPort::new("s", 2, HDLPortDir::Output),
+        HDLPort::new("b", 2, HDLPortDir::Input),
+        HDLPort::new("c", 1, HDLPortDir::Output),
+    ];
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top is
+    port(
+        a : in std_logic_vector(1 downto 0);
+        s : out std_logic_vector(1 downto 0);
+        b : in std_logic_vector(1 downto 0);
+        c : out std_logic
+    );
+end top;
+architecture behavior of top is
+    signal i0 : std_logic;
+    signal i1 : std_logic;
+    signal i2 : std_logic;
+    signal i3 : std_logic;
+    signal o0 : std_logic;
+    signal o1 : std_logic;
+    signal o2 : std_logic;
+    signal i5 : std_logic;
+    signal i6 : std_logic;
+    signal i8 : std_logic;
+    signal i9 : std_logic;
+    signal i10 : std_logic;
+begin
+    i0 <= a(0);
+    i1 <= a(1);
+    i2 <= b(0);
+    i3 <= b(1);
+    o0 <= i0 xor i2;
+    i5 <= i0 and i2;
+    i6 <= i1 xor i3;
+    o1 <= i6 xor i5;
+    i8 <= i1 and i3;
+    i9 <= i6 and i5;
+    i10 <= i8 nor i9;
+    o2 <= not i10;
+    s(0) <= o0;
+    s(1) <= o1;
+    c <= o2;
+end behavior;
+"##,
+        to_vhdl_with_options_helper(
+            circuit.clone(),
+            &vhdl::VHDLOptions {
+                ports: ports.clone(),
+                ..vhdl::VHDLOptions::default()
+            }
+        )
+    );
+    let mut out = vec![];
+    let options = vhdl::VHDLOptions {
+        ports: vec![
+            HDLPort::new("i1", 4, HDLPortDir::Input),
+            ports[1].clone(),
+            ports[3].clone(),
+        ],
+        ..vhdl::VHDLOptions::default()
+    };
+    assert_eq!(
+        "Name collision: i1",
+        vhdl::to_vhdl_with_options(
+            circuit.clone(),
+            "top",
+            "behavior",
+            false,
+            &options,
+            &mut out
+        )
+        .unwrap_err()
+        .to_string()
+    );
+    // bad layouts
+    for (ports, message) in [
+        (
+            vec![ports[0].clone(), ports[1].clone(), ports[3].clone()],
+            "Input ports have 2 bits instead of 4",
+        ),
+        (
+            vec![ports[0].clone(), ports[1].clone(), ports[2].clone()],
+            "Output ports have 2 bits instead of 3",
+        ),
+        (
+            vec![HDLPort::new("z", 0, HDLPortDir::Input)]
+                .into_iter()
+                .chain(ports.clone())
+                .collect(),
+            "Port z has zero width",
+        ),
+    ] {
+        let options = vhdl::VHDLOptions {
+            ports,
+            ..vhdl::VHDLOptions::default()
+        };
+        let err = vhdl::to_vhdl_with_options(
+            circuit.clone(),
+            "top",
+            "behavior",
+            false,
+            &options,
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(message, err.to_string());
+    }
+}
+
+#[test]
+fn test_to_vhdl_testbench() {
+    let circuit = Circuit::new(
+        2,
+        [Gate::new_xor(0, 1), Gate::new_and(0, 1)],
+        [(2, false), (3, true)],
+    )
+    .unwrap();
+    let mut out = vec![];
+    vhdl::to_vhdl_testbench(
+        &circuit,
+        "top",
+        "behavior",
+        &vhdl::VHDLOptions::default(),
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top_tb is
+end top_tb;
+architecture behavior of top_tb is
+    signal inputs : std_logic_vector(1 downto 0);
+    signal outputs : std_logic_vector(1 downto 0);
+begin
+    dut: entity work.top port map (i0 => inputs(0), i1 => inputs(1), o0 => outputs(0), o1 => outputs(1));
+    process
+        variable errors : natural := 0;
+    begin
+        inputs <= "00";
+        wait for 1 ns;
+        if outputs /= "10" then
+            report "Error: inputs 00: expected outputs 10" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "01";
+        wait for 1 ns;
+        if outputs /= "11" then
+            report "Error: inputs 01: expected outputs 11" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "10";
+        wait for 1 ns;
+        if outputs /= "11" then
+            report "Error: inputs 10: expected outputs 11" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "11";
+        wait for 1 ns;
+        if outputs /= "00" then
+            report "Error: inputs 11: expected outputs 00" severity error;
+            errors := errors + 1;
+        end if;
+        if errors = 0 then
+            report "PASSED";
+        else
+            report "FAILED: " & integer'image(errors) & " errors" severity failure;
+        end if;
+        wait;
+    end process;
+end behavior;
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    let circuit =
+        Circuit::new(3, [Gate::new_and(0, 1), Gate::new_nor(3, 2)], [(4, false)]).unwrap();
+    let mut out = vec![];
+    vhdl::to_vhdl_testbench(
+        &circuit,
+        "top",
+        "behavior",
+        &vhdl::VHDLOptions::default(),
+        HDLTestVectors::Random { count: 4, seed: 1 },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top_tb is
+end top_tb;
+architecture behavior of top_tb is
+    signal inputs : std_logic_vector(2 downto 0);
+    signal outputs : std_logic_vector(0 downto 0);
+begin
+    dut: entity work.top port map (i0 => inputs(0), i1 => inputs(1), i2 => inputs(2), o0 => outputs(0));
+    process
+        variable errors : natural := 0;
+    begin
+        inputs <= "001";
+        wait for 1 ns;
+        if outputs /= "1" then
+            report "Error: inputs 001: expected outputs 1" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "111";
+        wait for 1 ns;
+        if outputs /= "0" then
+            report "Error: inputs 111: expected outputs 0" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "110";
+        wait for 1 ns;
+        if outputs /= "0" then
+            report "Error: inputs 110: expected outputs 0" severity error;
+            errors := errors + 1;
+        end if;
+        inputs <= "011";
+        wait for 1 ns;
+        if outputs /= "0" then
+            report "Error: inputs 011: expected outputs 0" severity error;
+            errors := errors + 1;
+        end if;
+        if errors = 0 then
+            report "PASSED";
+        else
+            report "FAILED: " & integer'image(errors) & " errors" severity failure;
+        end if;
+        wait;
+    end process;
+end behavior;
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // sequential circuit with layout of ports
+    let circuit = Circuit::new(
+        3,
+        [
+            Gate::new_xor(1, 2),
+            Gate::new_xor(0, 3),
+            Gate::new_and(0, 3),
+        ],
+        [(4, false), (5, false)],
+    )
+    .unwrap();
+    let options = vhdl::VHDLOptions {
+        state_len: 1,
+        reset: Some(HDLReset {
+            name: "rst".to_string(),
+            asynchronous: false,
+            values: vec![true],
+        }),
+        ports: vec![
+            HDLPort::new("ab", 2, HDLPortDir::Input),
+            HDLPort::new("y", 1, HDLPortDir::Output),
+        ],
+        ..vhdl::VHDLOptions::default()
+    };
+    let mut out = vec![];
+    vhdl::to_vhdl_testbench(
+        &circuit,
+        "top",
+        "behavior",
+        &options,
+        HDLTestVectors::Random { count: 3, seed: 1 },
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"library ieee;
+use ieee.std_logic_1164.all;
+entity top_tb is
+end top_tb;
+architecture behavior of top_tb is
+    signal clock : std_logic;
+    signal reset : std_logic;
+    signal inputs : std_logic_vector(1 downto 0);
+    signal outputs : std_logic_vector(0 downto 0);
+begin
+    dut: entity work.top port map (clk => clock, rst => reset, ab => inputs(1 downto 0), y => outputs(0));
+    process
+        variable errors : natural := 0;
+    begin
+        clock <= '0';
+        reset <= '1';
+        wait for 1 ns;
+        clock <= '1';
+        wait for 1 ns;
+        clock <= '0';
+        reset <= '0';
+        inputs <= "01";
+        wait for 1 ns;
+        if outputs /= "1" then
+            report "Error: inputs 01: expected outputs 1" severity error;
+            errors := errors + 1;
+        end if;
+        clock <= '1';
+        wait for 1 ns;
+        clock <= '0';
+        inputs <= "11";
+        wait for 1 ns;
+        if outputs /= "0" then
+            report "Error: inputs 11: expected outputs 0" severity error;
+            errors := errors + 1;
+        end if;
+        clock <= '1';
+        wait for 1 ns;
+        clock <= '0';
+        inputs <= "10";
+        wait for 1 ns;
+        if outputs /= "0" then
+            report "Error: inputs 10: expected outputs 0" severity error;
+            errors := errors + 1;
+        end if;
+        clock <= '1';
+        wait for 1 ns;
+        clock <= '0';
+        if errors = 0 then
+            report "PASSED";
+        else
+            report "FAILED: " & integer'image(errors) & " errors" severity failure;
+        end if;
+        wait;
+    end process;
+end behavior;
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // errors
+    let mut out = vec![];
+    let err = vhdl::to_vhdl_testbench(
+        &circuit,
+        "top",
+        "behavior",
+        &vhdl::VHDLOptions {
+            reset: None,
+            ..options.clone()
+        },
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!("Sequential circuit without reset", err.to_string());
+    let circuit = Circuit::new(21, [], (0..21).map(|i| (i, false))).unwrap();
+    let err = vhdl::to_vhdl_testbench(
+        &circuit,
+        "top",
+        "behavior",
+        &vhdl::VHDLOptions::default(),
+        HDLTestVectors::Exhaustive,
+        &mut out,
+    )
+    .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!(
+        "Too many inputs for exhaustive test vectors: 21",
+        err.to_string()
+    );
+}
@@ -0,0 +1,453 @@
+use gateconvert::yosys_json::{self, YosysJSONOptions};
+use gateconvert::{AssignEntry, HDLPort, HDLPortDir};
+use gateutil::gatesim::*;
+
+fn to_yosys_json_helper(circuit: Circuit<usize>, options: &YosysJSONOptions) -> String {
+    let mut out = vec![];
+    yosys_json::to_yosys_json_with_options(&circuit, "simple", options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+type YosysJSONResult = (Circuit<usize>, Vec<(String, AssignEntry)>);
+
+fn from_yosys_json_helper(text: &str, top: Option<&str>) -> Result<YosysJSONResult, String> {
+    yosys_json::from_yosys_json(text.as_bytes(), top).map_err(|e| e.to_string())
+}
+
+fn strs_to_assign_map<'a>(
+    iter: impl IntoIterator<Item = (&'a str, AssignEntry)>,
+) -> Vec<(String, AssignEntry)> {
+    iter.into_iter().map(|(s, a)| (s.to_string(), a)).collect()
+}
+
+fn sample_circuit() -> Circuit<usize> {
+    Circuit::new(
+        3,
+        [
+            Gate::new_and(0, 2),
+            Gate::new_nor(1, 2),
+            Gate::new_xor(3, 4),
+            Gate::new_nimpl(5, 0),
+        ],
+        [(5, false), (3, true), (6, false), (1, true), (0, false)],
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_to_yosys_json() {
+    let mut out = vec![];
+    yosys_json::to_yosys_json(
+        &Circuit::new(2, [Gate::new_nimpl(0, 1)], [(2, false), (2, true)]).unwrap(),
+        "simple",
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(
+        r##"{
+  "creator": "gateconvert",
+  "modules": {
+    "simple": {
+      "attributes": {
+        "top": "00000000000000000000000000000001"
+      },
+      "ports": {
+        "i0": {
+          "direction": "input",
+          "bits": [
+            2
+          ]
+        },
+        "i1": {
+          "direction": "input",
+          "bits": [
+            3
+          ]
+        },
+        "o0": {
+          "direction": "output",
+          "bits": [
+            4
+          ]
+        },
+        "o1": {
+          "direction": "output",
+          "bits": [
+            5
+          ]
+        }
+      },
+      "cells": {
+        "$g2": {
+          "hide_name": 1,
+          "type": "$_ANDNOT_",
+          "parameters": {},
+          "attributes": {},
+          "port_directions": {
+            "A": "input",
+            "B": "input",
+            "Y": "output"
+          },
+          "connections": {
+            "A": [
+              2
+            ],
+            "B": [
+              3
+            ],
+            "Y": [
+              4
+            ]
+          }
+        },
+        "$n2": {
+          "hide_name": 1,
+          "type": "$_NOT_",
+          "parameters": {},
+          "attributes": {},
+          "port_directions": {
+            "A": "input",
+            "Y": "output"
+          },
+          "connections": {
+            "A": [
+              4
+            ],
+            "Y": [
+              5
+            ]
+          }
+        }
+      },
+      "netnames": {
+        "i0": {
+          "hide_name": 0,
+          "bits": [
+            2
+          ],
+          "attributes": {}
+        },
+        "i1": {
+          "hide_name": 0,
+          "bits": [
+            3
+          ],
+          "attributes": {}
+        },
+        "o0": {
+          "hide_name": 0,
+          "bits": [
+            4
+          ],
+          "attributes": {}
+        },
+        "o1": {
+          "hide_name": 0,
+          "bits": [
+            5
+          ],
+          "attributes": {}
+        }
+      }
+    }
+  }
+}
+"##,
+        String::from_utf8(out).unwrap()
+    );
+    // round trip
+    assert_eq!(
+        Ok((
+            sample_circuit(),
+            strs_to_assign_map([
+                ("i0", AssignEntry::Var(0, false)),
+                ("i1", AssignEntry::Var(1, false)),
+                ("i2", AssignEntry::Var(2, false)),
+                ("o0", AssignEntry::Var(5, false)),
+                ("o1", AssignEntry::Var(3, true)),
+                ("o2", AssignEntry::Var(6, false)),
+                ("o3", AssignEntry::Var(1, true)),
+                ("o4", AssignEntry::Var(0, false)),
+            ])
+        )),
+        from_yosys_json_helper(
+            &to_yosys_json_helper(sample_circuit(), &YosysJSONOptions::default()),
+            None
+        )
+    );
+}
+
+#[test]
+fn test_to_yosys_json_with_options() {
+    let options = YosysJSONOptions {
+        state_len: 1,
+        clock: "clk".to_string(),
+        ports: vec![
+            HDLPort::new("a", 2, HDLPortDir::Input),
+            HDLPort::new("x", 3, HDLPortDir::Output),
+            HDLPort::new("y", 1, HDLPortDir::Output),
+        ],
+    };
+    // round trip
+    assert_eq!(
+        Ok((
+            sample_circuit(),
+            strs_to_assign_map([
+                ("state", AssignEntry::Var(0, false)),
+                ("clk", AssignEntry::NoMap),
+                ("a[0]", AssignEntry::Var(1, false)),
+                ("a[1]", AssignEntry::Var(2, false)),
+                ("next_state", AssignEntry::Var(5, false)),
+                ("x[0]", AssignEntry::Var(3, true)),
+                ("x[1]", AssignEntry::Var(6, false)),
+                ("x[2]", AssignEntry::Var(1, true)),
+                ("y", AssignEntry::Var(0, false)),
+            ])
+        )),
+        from_yosys_json_helper(&to_yosys_json_helper(sample_circuit(), &options), None)
+    );
+    let mut out = vec![];
+    let err = yosys_json::to_yosys_json_with_options(
+        &sample_circuit(),
+        "simple",
+        &YosysJSONOptions {
+            clock: "a".to_string(),
+            ..options.clone()
+        },
+        &mut out,
+    )
+    .unwrap_err();
+    assert_eq!("Name collision: a", err.to_string());
+    // bad layouts
+    for (ports, message) in [
+        (
+            vec![options.ports[0].clone(), options.ports[1].clone()],
+            "Output ports have 3 bits instead of 4",
+        ),
+        (
+            vec![HDLPort::new("z", 0, HDLPortDir::Output)]
+                .into_iter()
+                .chain(options.ports.clone())
+                .collect(),
+            "Port z has zero width",
+        ),
+    ] {
+        let err = yosys_json::to_yosys_json_with_options(
+            &sample_circuit(),
+            "simple",
+            &YosysJSONOptions {
+                ports,
+                ..options.clone()
+            },
+            &mut out,
+        )
+        .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        assert_eq!(message, err.to_string());
+    }
+}
+
+fn cell(name: &str, cell_type: &str, connections: &str) -> String {
+    format!(
+        r#""{}": {{ "hide_name": 1, "type": "{}", "parameters": {{}}, "attributes": {{}},
+"connections": {{ {} }} }}"#,
+        name, cell_type, connections
+    )
+}
+
+fn module(ports: &str, cells: &[String]) -> String {
+    format!(
+        r#"{{ "creator": "Yosys", "modules": {{ "top": {{ "attributes": {{}},
+"ports": {{ {} }}, "cells": {{ {} }},
+"netnames": {{ "q": {{ "hide_name": 0, "bits": [ 20, 21 ], "attributes": {{}} }} }} }} }} }}"#,
+        ports,
+        cells.join(", ")
+    )
+}
+
+#[test]
+fn test_from_yosys_json() {
+    assert_eq!(
+        Ok((
+            Circuit::new(
+                6,
+                [
+                    Gate::new_and(4, 3),
+                    Gate::new_nimpl(2, 4),
+                    Gate::new_nor(6, 7),
+                    Gate::new_nor(8, 5),
+                    Gate::new_nimpl(0, 2),
+                    Gate::new_xor(10, 1),
+                    Gate::new_nimpl(11, 5),
+                    Gate::new_and(5, 1),
+                    Gate::new_nor(12, 13),
+                ],
+                [
+                    (9, false),
+                    (14, true),
+                    (8, true),
+                    (10, true),
+                    (11, false),
+                    (2, false),
+                    (0, false)
+                ],
+            )
+            .unwrap(),
+            strs_to_assign_map([
+                ("q[0]", AssignEntry::Var(0, false)),
+                ("q[1]", AssignEntry::Var(1, false)),
+                ("clk", AssignEntry::NoMap),
+                ("a[0]", AssignEntry::Var(2, false)),
+                ("a[1]", AssignEntry::Var(3, false)),
+                ("a[2]", AssignEntry::Var(4, false)),
+                ("rst", AssignEntry::Var(5, false)),
+                ("q[0]:next", AssignEntry::Var(9, false)),
+                ("q[1]:next", AssignEntry::Var(14, true)),
+                ("y[0]", AssignEntry::Var(8, true)),
+                ("y[1]", AssignEntry::Var(10, true)),
+                ("y[2]", AssignEntry::Var(11, false)),
+                ("y[3]", AssignEntry::Value(true)),
+                ("y[4]", AssignEntry::Var(2, false)),
+                ("y[5]", AssignEntry::Var(0, false)),
+            ])
+        )),
+        from_yosys_json_helper(
+            &module(
+                r#""clk": { "direction": "input", "bits": [ 2 ] },
+"a": { "direction": "input", "bits": [ 3, 4, 5 ] },
+"rst": { "direction": "input", "bits": [ 6 ] },
+"y": { "direction": "output", "bits": [ 7, 8, 9, "1", 3, 20 ] }"#,
+                &[
+                    cell(
+                        "$1",
+                        "$_MUX_",
+                        r#""A": [ 3 ], "B": [ 4 ], "S": [ 5 ], "Y": [ 7 ]"#
+                    ),
+                    cell("$2", "$_ORNOT_", r#""A": [ 3 ], "B": [ 20 ], "Y": [ 8 ]"#),
+                    cell("$3", "$_XNOR_", r#""A": [ 21 ], "B": [ 8 ], "Y": [ 9 ]"#),
+                    cell(
+                        "$4",
+                        "$_DFF_PP0_",
+                        r#""C": [ 2 ], "R": [ 6 ], "D": [ 7 ], "Q": [ 20 ]"#
+                    ),
+                    cell(
+                        "$5",
+                        "$_DFFE_PN_",
+                        r#""C": [ 2 ], "E": [ 6 ], "D": [ 9 ], "Q": [ 21 ]"#
+                    ),
+                ],
+            ),
+            None
+        )
+    );
+    let two_modules = r#"{ "modules": { "a": {},
+"b": { "attributes": { "top": "00000000000000000000000000000001" },
+"ports": { "x": { "direction": "output", "bits": [ "0" ] } } } } }"#;
+    assert_eq!(
+        Ok((
+            Circuit::new(0, [], []).unwrap(),
+            strs_to_assign_map([("x", AssignEntry::Value(false))])
+        )),
+        from_yosys_json_helper(two_modules, None)
+    );
+    assert_eq!(
+        Ok((Circuit::new(0, [], []).unwrap(), vec![])),
+        from_yosys_json_helper(two_modules, Some("a"))
+    );
+    assert_eq!(
+        Err("Module c not found".to_string()),
+        from_yosys_json_helper(two_modules, Some("c"))
+    );
+    assert_eq!(
+        Err("No top module".to_string()),
+        from_yosys_json_helper(r#"{ "modules": { "a": {}, "b": {} } }"#, None)
+    );
+    assert_eq!(
+        Err("Bad format: modules".to_string()),
+        from_yosys_json_helper(r#"{ "modules": [] }"#, None)
+    );
+    assert_eq!(
+        Err("JSON error: EOF while parsing a value at line 1 column 13".to_string()),
+        from_yosys_json_helper(r#"{ "modules": "#, None)
+    );
+    let ports = r#""a": { "direction": "input", "bits": [ 3 ] },
+"y": { "direction": "output", "bits": [ 7 ] }"#;
+    assert_eq!(
+        Err("Unknown type $_FOO_ of cell $1".to_string()),
+        from_yosys_json_helper(
+            &module(ports, &[cell("$1", "$_FOO_", r#""A": [ 3 ], "Y": [ 7 ]"#)]),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Unknown type $_DFFE__ of cell $1".to_string()),
+        from_yosys_json_helper(
+            &module(
+                ports,
+                &[cell("$1", "$_DFFE__", r#""D": [ 3 ], "Q": [ 7 ]"#)]
+            ),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Net 7 already driven".to_string()),
+        from_yosys_json_helper(
+            &module(
+                ports,
+                &[
+                    cell("$1", "$_NOT_", r#""A": [ 3 ], "Y": [ 7 ]"#),
+                    cell("$2", "$_NOT_", r#""A": [ 3 ], "Y": [ 7 ]"#)
+                ]
+            ),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Net 8 is undriven".to_string()),
+        from_yosys_json_helper(
+            &module(
+                ports,
+                &[cell(
+                    "$1",
+                    "$_AND_",
+                    r#""A": [ 3 ], "B": [ 8 ], "Y": [ 7 ]"#
+                )]
+            ),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Cycle in circuit caused by net 7".to_string()),
+        from_yosys_json_helper(
+            &module(
+                ports,
+                &[
+                    cell("$1", "$_AND_", r#""A": [ 3 ], "B": [ 8 ], "Y": [ 7 ]"#),
+                    cell("$2", "$_OR_", r#""A": [ 3 ], "B": [ 7 ], "Y": [ 8 ]"#)
+                ]
+            ),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Bad format: $1".to_string()),
+        from_yosys_json_helper(
+            &module(ports, &[cell("$1", "$_AND_", r#""A": [ 3 ], "Y": [ 7 ]"#)]),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Undefined bit in y".to_string()),
+        from_yosys_json_helper(
+            &module(r#""y": { "direction": "output", "bits": [ "x" ] }"#, &[]),
+            None
+        )
+    );
+    assert_eq!(
+        Err("Unsupported direction of port a".to_string()),
+        from_yosys_json_helper(
+            &module(r#""a": { "direction": "inout", "bits": [ 3 ] }"#, &[]),
+            None
+        )
+    );
+}